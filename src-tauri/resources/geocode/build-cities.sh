@@ -0,0 +1,29 @@
+#!/bin/sh
+# GeoNames のダンプから cities.tsv を作り直す。
+#
+#   curl -O https://download.geonames.org/export/dump/cities1000.zip && unzip cities1000.zip
+#   curl -O https://download.geonames.org/export/dump/admin1CodesASCII.txt
+#   curl -O https://download.geonames.org/export/dump/countryInfo.txt
+#   ./build-cities.sh . [最小人口] > cities.tsv
+#
+# 最小人口（GeoNames の population 列）の既定は 1000 で、cities1000 の全件（約 15 万件、7MB 前後）になる。
+# 埋め込むバイナリを小さくしたいときは 15000 などを指定する（約 3 万件。街と街の間が 100km を超える
+# 地域が増え、そこで撮った写真は場所不明になる）。地区（PPLX）や廃村（PPLH / PPLQ / PPLW）は除く。
+# cities.tsv はバイナリに埋め込まれるので、差し替えたらビルドし直すだけで反映される
+# （キャッシュ済みの地名もデータの大きさが変わったことを検知して引き直す）。
+set -eu
+
+src="${1:-.}"
+min_population="${2:-1000}"
+
+echo '# sss 逆ジオコーディング用の地名データ（name, region, country, latitude, longitude のタブ区切り）'
+echo '# 出典: GeoNames (https://www.geonames.org/) CC BY 4.0'
+echo "# $(basename "$src/cities1000.txt") から build-cities.sh で生成（人口 $min_population 人以上）"
+
+awk -F '\t' -v min_population="$min_population" '
+    FILENAME ~ /countryInfo\.txt$/ { if ($0 !~ /^#/) country[$1] = $5; next }
+    FILENAME ~ /admin1CodesASCII\.txt$/ { admin1[$1] = $2; next }
+    $8 == "PPLX" || $8 == "PPLH" || $8 == "PPLQ" || $8 == "PPLW" { next }
+    $15 + 0 < min_population + 0 { next }
+    { printf "%s\t%s\t%s\t%.4f\t%.4f\n", $2, admin1[$9 "." $11], country[$9], $5, $6 }
+' "$src/countryInfo.txt" "$src/admin1CodesASCII.txt" "$src/cities1000.txt"
@@ -0,0 +1,315 @@
+//! アニメーション GIF / WebP の判定と、アニメーションを保ったままの 4K 縮小。
+//!
+//! 判定はコンテナ構造（GIF のブロック列 / WebP の RIFF チャンク）を読むだけで、
+//! ピクセルはデコードしない。表示のたびに呼ばれるので軽さを優先している。
+
+use crate::image_processor::{MAX_HEIGHT_4K, MAX_WIDTH_4K};
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::{imageops::FilterType, AnimationDecoder, DynamicImage, Frame, ImageFormat};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// アニメーション情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationInfo {
+    pub format: ImageFormat,
+    pub frame_count: u32,
+    /// 1ループの総再生時間（ミリ秒）
+    pub duration_ms: u64,
+}
+
+/// アニメーション GIF / WebP ならフレーム数と総再生時間を返す（静止画・他形式は None）
+pub fn probe_animation(image_path: &Path) -> Option<AnimationInfo> {
+    let ext = image_path.extension()?.to_string_lossy().to_lowercase();
+    let file = File::open(image_path).ok()?;
+    let mut reader = BufReader::new(file);
+
+    let (format, delays) = match ext.as_str() {
+        "gif" => (ImageFormat::Gif, read_gif_frame_delays(&mut reader)?),
+        "webp" => (ImageFormat::WebP, read_webp_frame_durations(&mut reader)?),
+        _ => return None,
+    };
+
+    // 1フレームしかないものは静止画として扱う
+    if delays.len() < 2 {
+        return None;
+    }
+
+    Some(AnimationInfo {
+        format,
+        frame_count: delays.len() as u32,
+        duration_ms: delays.iter().map(|&d| normalize_frame_delay_ms(d)).sum(),
+    })
+}
+
+/// ブラウザと同じく 10ms 以下のフレーム遅延は 100ms として扱う
+/// （遅延 0 の GIF が実際には 100ms 間隔で再生されるのに合わせる）
+fn normalize_frame_delay_ms(delay_ms: u64) -> u64 {
+    if delay_ms <= 10 {
+        100
+    } else {
+        delay_ms
+    }
+}
+
+/// GIF のブロック列を辿り、各フレームの遅延（ミリ秒）を返す
+fn read_gif_frame_delays<R: Read>(reader: &mut R) -> Option<Vec<u64>> {
+    // ヘッダ（6バイト）+ 論理画面記述子（7バイト）
+    let mut header = [0u8; 13];
+    reader.read_exact(&mut header).ok()?;
+    if &header[..3] != b"GIF" {
+        return None;
+    }
+
+    // グローバルカラーテーブル
+    let packed = header[10];
+    if packed & 0x80 != 0 {
+        skip_bytes(reader, 3 * (1 << ((packed & 0x07) + 1)))?;
+    }
+
+    let mut delays = Vec::new();
+    let mut pending_delay = 0u64;
+
+    loop {
+        let mut introducer = [0u8; 1];
+        if reader.read_exact(&mut introducer).is_err() {
+            // 末尾が欠けたファイルでも読めた分までで判定する
+            break;
+        }
+
+        match introducer[0] {
+            // 拡張ブロック
+            0x21 => {
+                let mut label = [0u8; 1];
+                reader.read_exact(&mut label).ok()?;
+                if label[0] == 0xF9 {
+                    // Graphic Control Extension: サイズ(1) + フラグ(1) + 遅延(2, 1/100秒) + 透過色(1)
+                    let mut gce = [0u8; 5];
+                    reader.read_exact(&mut gce).ok()?;
+                    pending_delay = u16::from_le_bytes([gce[2], gce[3]]) as u64 * 10;
+                }
+                skip_sub_blocks(reader)?;
+            }
+            // 画像記述子（1フレーム）
+            0x2C => {
+                let mut descriptor = [0u8; 9];
+                reader.read_exact(&mut descriptor).ok()?;
+                let packed = descriptor[8];
+                if packed & 0x80 != 0 {
+                    skip_bytes(reader, 3 * (1 << ((packed & 0x07) + 1)))?;
+                }
+                // LZW 最小コードサイズ + 画像データ
+                skip_bytes(reader, 1)?;
+                skip_sub_blocks(reader)?;
+
+                delays.push(pending_delay);
+                pending_delay = 0;
+            }
+            // トレーラ
+            0x3B => break,
+            _ => break,
+        }
+    }
+
+    Some(delays)
+}
+
+/// WebP の RIFF チャンクを辿り、ANMF チャンクごとのフレーム表示時間（ミリ秒）を返す
+fn read_webp_frame_durations<R: Read + Seek>(reader: &mut R) -> Option<Vec<u64>> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).ok()?;
+    if &header[..4] != b"RIFF" || &header[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut durations = Vec::new();
+    let mut is_animated = false;
+
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if reader.read_exact(&mut chunk_header).is_err() {
+            break;
+        }
+        let fourcc = &chunk_header[..4];
+        let size = u32::from_le_bytes([
+            chunk_header[4],
+            chunk_header[5],
+            chunk_header[6],
+            chunk_header[7],
+        ]) as i64;
+        // チャンクは偶数バイト境界に揃えられる
+        let padded_size = size + (size & 1);
+
+        match fourcc {
+            b"VP8X" => {
+                let mut flags = [0u8; 1];
+                reader.read_exact(&mut flags).ok()?;
+                is_animated = flags[0] & 0x02 != 0;
+                reader.seek(SeekFrom::Current(padded_size - 1)).ok()?;
+            }
+            b"ANMF" => {
+                // X(3) + Y(3) + 幅-1(3) + 高さ-1(3) + 表示時間(3, ミリ秒) + フラグ(1)
+                let mut frame_header = [0u8; 16];
+                reader.read_exact(&mut frame_header).ok()?;
+                let duration =
+                    u32::from_le_bytes([frame_header[12], frame_header[13], frame_header[14], 0]);
+                durations.push(duration as u64);
+                reader.seek(SeekFrom::Current(padded_size - 16)).ok()?;
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(padded_size)).ok()?;
+            }
+        }
+    }
+
+    if is_animated {
+        Some(durations)
+    } else {
+        Some(Vec::new())
+    }
+}
+
+/// GIF のデータサブブロック列（長さ0のブロックで終端）を読み飛ばす
+fn skip_sub_blocks<R: Read>(reader: &mut R) -> Option<()> {
+    loop {
+        let mut size = [0u8; 1];
+        reader.read_exact(&mut size).ok()?;
+        if size[0] == 0 {
+            return Some(());
+        }
+        skip_bytes(reader, size[0] as u64)?;
+    }
+}
+
+fn skip_bytes<R: Read>(reader: &mut R, count: u64) -> Option<()> {
+    let skipped = std::io::copy(&mut reader.take(count), &mut std::io::sink()).ok()?;
+    (skipped == count).then_some(())
+}
+
+/// アニメーション GIF の全フレームを 4K に収まるよう縮小し、アニメーション GIF として返す
+pub fn optimize_animated_gif_for_4k(image_path: &Path) -> Result<Vec<u8>, String> {
+    let file = File::open(image_path).map_err(|e| format!("Failed to open image: {e}"))?;
+    let decoder =
+        GifDecoder::new(BufReader::new(file)).map_err(|e| format!("Failed to decode GIF: {e}"))?;
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .map_err(|e| format!("Failed to decode GIF frames: {e}"))?;
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut buffer, 10);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("Failed to encode GIF: {e}"))?;
+
+        for frame in frames {
+            // デコーダが返すフレームは合成済みの全面キャンバスなので、位置は常に (0, 0)
+            let delay = frame.delay();
+            let resized = DynamicImage::ImageRgba8(frame.into_buffer())
+                .resize(MAX_WIDTH_4K, MAX_HEIGHT_4K, FilterType::Lanczos3)
+                .to_rgba8();
+            encoder
+                .encode_frame(Frame::from_parts(resized, 0, 0, delay))
+                .map_err(|e| format!("Failed to encode GIF frame: {e}"))?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Delay, RgbaImage};
+    use std::path::PathBuf;
+
+    /// テスト専用のユニークな一時ファイルパス
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sss_animation_{}_{name}", std::process::id()))
+    }
+
+    /// 指定サイズ・フレーム遅延のアニメーション GIF を作る
+    fn write_gif(path: &Path, width: u32, height: u32, delays_ms: &[u32]) {
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut buffer);
+            encoder.set_repeat(Repeat::Infinite).unwrap();
+            for (i, &delay) in delays_ms.iter().enumerate() {
+                let shade = (i * 80) as u8;
+                let img = RgbaImage::from_pixel(width, height, image::Rgba([shade, 0, 0, 255]));
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(img, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        std::fs::write(path, buffer).unwrap();
+    }
+
+    #[test]
+    fn test_probe_animated_gif() {
+        let animated = temp_path("animated.gif");
+        write_gif(&animated, 8, 8, &[50, 50, 200]);
+        let info = probe_animation(&animated).expect("アニメーション GIF のはず");
+        assert_eq!(info.format, ImageFormat::Gif);
+        assert_eq!(info.frame_count, 3);
+        assert_eq!(info.duration_ms, 300);
+
+        // 1フレームの GIF は静止画
+        let still = temp_path("still.gif");
+        write_gif(&still, 8, 8, &[0]);
+        assert_eq!(probe_animation(&still), None);
+
+        let _ = std::fs::remove_file(&animated);
+        let _ = std::fs::remove_file(&still);
+    }
+
+    #[test]
+    fn test_probe_animated_webp() {
+        // VP8X（アニメーションフラグ）+ ANMF×2 の RIFF 構造だけを持つ WebP
+        let mut chunks = Vec::new();
+        chunks.extend_from_slice(b"VP8X");
+        chunks.extend_from_slice(&10u32.to_le_bytes());
+        chunks.extend_from_slice(&[0x02, 0, 0, 0, 7, 0, 0, 7, 0, 0]);
+        for duration in [40u32, 0] {
+            chunks.extend_from_slice(b"ANMF");
+            chunks.extend_from_slice(&16u32.to_le_bytes());
+            chunks.extend_from_slice(&[0; 12]);
+            chunks.extend_from_slice(&duration.to_le_bytes()[..3]);
+            chunks.push(0);
+        }
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+        webp.extend_from_slice(b"WEBP");
+        webp.extend_from_slice(&chunks);
+
+        let path = temp_path("animated.webp");
+        std::fs::write(&path, webp).unwrap();
+        let info = probe_animation(&path).expect("アニメーション WebP のはず");
+        assert_eq!(info.format, ImageFormat::WebP);
+        assert_eq!(info.frame_count, 2);
+        // 0ms のフレームは 100ms として数える
+        assert_eq!(info.duration_ms, 140);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_optimize_animated_gif_keeps_all_frames() {
+        let path = temp_path("wide.gif");
+        write_gif(&path, MAX_WIDTH_4K + 160, 8, &[100, 100]);
+
+        let optimized = optimize_animated_gif_for_4k(&path).unwrap();
+        let decoder = GifDecoder::new(std::io::Cursor::new(optimized)).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames
+            .iter()
+            .all(|f| f.buffer().dimensions().0 == MAX_WIDTH_4K));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
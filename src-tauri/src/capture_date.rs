@@ -0,0 +1,354 @@
+//! 撮影日時の解決。
+//!
+//! EXIF のない写真（WhatsApp で受け取った画像・スクリーンショット・プリントのスキャン等）にも
+//! 日付を与えるため、以下の順に手がかりを探し、最初に見つかったものを出どころと共に返す。
+//!
+//! 1. 埋め込みメタデータ（画像は EXIF、動画はコンテナの作成日時）
+//! 2. ファイル名（`IMG_20230515_123456` / `PXL_20230515_123456789` / `Screenshot_2023-05-15-12-34-56` 等）
+//! 3. フォルダ名（`2023-05-15 旅行` 等。近い階層から順に）
+//! 4. Google Takeout の JSON サイドカー（`photoTakenTime`）
+//! 5. ファイルの更新日時
+
+use crate::image_processor::{get_exif_info, is_video_file};
+use crate::path_norm::{canonical_path, strip_path_prefix, CasePolicy};
+use crate::video_metadata::read_video_metadata;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// 日付として認める年の範囲（連番やハッシュの数字列を日付と誤認しないように）
+const MIN_YEAR: i32 = 1900;
+const MAX_YEAR: i32 = 2100;
+
+/// 名前中の日付（YYYYMMDD / YYYY-MM-DD / YYYY_MM_DD / YYYY.MM.DD）と、続く時刻
+/// 時刻は HHMMSS / HH-MM-SS / HH.MM.SS / HH:MM:SS（"at" を挟む macOS のスクリーンショット形式も含む）
+/// PXL_ のミリ秒のように秒の後ろに続く数字は読み飛ばす
+static DATE_IN_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+        (?:^|[^0-9])
+        (?:
+            (?P<y1>\d{4})(?P<m1>\d{2})(?P<d1>\d{2})
+          | (?P<y2>\d{4})[-_.](?P<m2>\d{2})[-_.](?P<d2>\d{2})
+        )
+        (?:
+            (?:[-_T\ ]|\ at\ )
+            (?P<hh>\d{2})[-.:]?(?P<mm>\d{2})[-.:]?(?P<ss>\d{2})\d{0,3}
+        )?
+        (?:[^0-9]|$)",
+    )
+    .expect("valid regex")
+});
+
+/// 撮影日時の出どころ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureDateSource {
+    /// 画像の EXIF（DateTimeOriginal 等）
+    Exif,
+    /// 動画コンテナの作成日時
+    Video,
+    /// ファイル名中の日付
+    Filename,
+    /// フォルダ名中の日付
+    Folder,
+    /// Google Takeout の JSON サイドカー
+    Sidecar,
+    /// ファイルの更新日時
+    FileModified,
+}
+
+impl CaptureDateSource {
+    /// DB 保存用の文字列
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exif => "exif",
+            Self::Video => "video",
+            Self::Filename => "filename",
+            Self::Folder => "folder",
+            Self::Sidecar => "sidecar",
+            Self::FileModified => "fileModified",
+        }
+    }
+
+    /// DB 保存用の文字列から戻す
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "exif" => Some(Self::Exif),
+            "video" => Some(Self::Video),
+            "filename" => Some(Self::Filename),
+            "folder" => Some(Self::Folder),
+            "sidecar" => Some(Self::Sidecar),
+            "fileModified" => Some(Self::FileModified),
+            _ => None,
+        }
+    }
+}
+
+/// 解決した撮影日時
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureDate {
+    /// "YYYY-MM-DD HH:MM:SS"（撮影地の現地時刻。サイドカー・更新日時は端末のタイムゾーンで表す）
+    /// 日付しか分からない場合は 00:00:00
+    pub date_time: String,
+    pub source: CaptureDateSource,
+}
+
+impl CaptureDate {
+    /// 日付部分 "YYYY-MM-DD"
+    pub fn date(&self) -> &str {
+        self.date_time.split(' ').next().unwrap_or(&self.date_time)
+    }
+}
+
+/// 撮影日時を解決する（ファイルが読めない場合のみ None）
+///
+/// root はライブラリのルート。フォルダ名の日付はルートより上の階層（`/mnt/2019-05-01-backup` 等）からは拾わない。
+pub fn resolve_capture_date(path: &Path, root: Option<&Path>) -> Option<CaptureDate> {
+    let (date_time, source) = embedded_date(path)
+        .or_else(|| {
+            let stem = path.file_stem()?.to_string_lossy();
+            date_in_name(&stem).map(|dt| (dt, CaptureDateSource::Filename))
+        })
+        .or_else(|| folder_date(path, root).map(|dt| (dt, CaptureDateSource::Folder)))
+        .or_else(|| takeout_sidecar_date(path).map(|dt| (dt, CaptureDateSource::Sidecar)))
+        .or_else(|| modified_date(path).map(|dt| (dt, CaptureDateSource::FileModified)))?;
+
+    Some(CaptureDate {
+        date_time: date_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        source,
+    })
+}
+
+/// 画像の EXIF 撮影日時、または動画コンテナの作成日時（UTC なので現地時刻に直す）
+fn embedded_date(path: &Path) -> Option<(NaiveDateTime, CaptureDateSource)> {
+    if is_video_file(path) {
+        let creation_time = read_video_metadata(path)?.creation_time?;
+        let utc = NaiveDateTime::parse_from_str(&creation_time, "%Y-%m-%d %H:%M:%S").ok()?;
+        let local = utc.and_utc().with_timezone(&Local).naive_local();
+        return Some((local, CaptureDateSource::Video));
+    }
+
+    let date_time = get_exif_info(path).ok()?.date_time?;
+    NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|dt| (dt, CaptureDateSource::Exif))
+}
+
+/// 名前（拡張子を除いたファイル名・フォルダ名）から日時を探す
+fn date_in_name(name: &str) -> Option<NaiveDateTime> {
+    DATE_IN_NAME.captures_iter(name).find_map(|caps| {
+        let field = |name: &str| caps.name(name).and_then(|m| m.as_str().parse::<u32>().ok());
+
+        let year = field("y1").or_else(|| field("y2"))? as i32;
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return None;
+        }
+        let month = field("m1").or_else(|| field("m2"))?;
+        let day = field("d1").or_else(|| field("d2"))?;
+        let date = NaiveDate::from_ymd_opt(year, month, day)?;
+
+        // 時刻が範囲外（連番の数字列等）なら日付だけ使う
+        let time = match (field("hh"), field("mm"), field("ss")) {
+            (Some(h), Some(m), Some(s)) => NaiveTime::from_hms_opt(h, m, s),
+            _ => None,
+        }
+        .unwrap_or(NaiveTime::MIN);
+
+        Some(date.and_time(time))
+    })
+}
+
+/// 親フォルダ名から日付を探す（近い階層から順に、root 自身まで。フォルダ名の時刻は使わない）
+fn folder_date(path: &Path, root: Option<&Path>) -> Option<NaiveDateTime> {
+    let root = root.map(canonical_path);
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| {
+            root.as_deref().is_none_or(|root| {
+                strip_path_prefix(&canonical_path(dir), root, CasePolicy::platform()).is_some()
+            })
+        })
+        .filter_map(|dir| dir.file_name())
+        .find_map(|name| date_in_name(&name.to_string_lossy()))
+        .map(|dt| dt.date().and_time(NaiveTime::MIN))
+}
+
+/// Google Takeout のサイドカー候補
+/// "photo.jpg.json"、新しい形式の "photo.jpg.supplemental-metadata.json"、拡張子なしの "photo.json"
+fn takeout_sidecar_paths(path: &Path) -> Vec<PathBuf> {
+    let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    let mut candidates = vec![
+        path.with_file_name(format!("{file_name}.json")),
+        path.with_file_name(format!("{file_name}.supplemental-metadata.json")),
+    ];
+    if path.extension().is_some() {
+        candidates.push(path.with_extension("json"));
+    }
+    candidates
+}
+
+/// Google Takeout サイドカーの photoTakenTime（UTC の UNIX 秒）を現地時刻で返す
+fn takeout_sidecar_date(path: &Path) -> Option<NaiveDateTime> {
+    takeout_sidecar_paths(path).into_iter().find_map(|sidecar| {
+        let content = std::fs::read_to_string(sidecar).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let timestamp = &json["photoTakenTime"]["timestamp"];
+        // Takeout は文字列で書き出すが、数値でも受け付ける
+        let secs = timestamp
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .or_else(|| timestamp.as_i64())
+            .filter(|&secs| secs > 0)?;
+        let utc = DateTime::from_timestamp(secs, 0)?;
+        Some(utc.with_timezone(&Local).naive_local())
+    })
+}
+
+/// ファイルの更新日時（現地時刻）
+fn modified_date(path: &Path) -> Option<NaiveDateTime> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sss_capture_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn format(dt: Option<NaiveDateTime>) -> Option<String> {
+        dt.map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+
+    #[test]
+    fn test_date_in_filename() {
+        let cases = [
+            ("IMG_20230515_123456", Some("2023-05-15 12:34:56")),
+            ("VID_20230515_123456", Some("2023-05-15 12:34:56")),
+            ("PXL_20230515_123456789", Some("2023-05-15 12:34:56")),
+            (
+                "PXL_20230515_123456789.PORTRAIT",
+                Some("2023-05-15 12:34:56"),
+            ),
+            ("20230515_123456", Some("2023-05-15 12:34:56")),
+            ("IMG-20230515-WA0001", Some("2023-05-15 00:00:00")),
+            (
+                "Screenshot_2023-05-15-12-34-56",
+                Some("2023-05-15 12:34:56"),
+            ),
+            (
+                "Screenshot_20230515-123456_Chrome",
+                Some("2023-05-15 12:34:56"),
+            ),
+            (
+                "Screenshot 2023-05-15 at 12.34.56",
+                Some("2023-05-15 12:34:56"),
+            ),
+            ("2023-05-15 旅行", Some("2023-05-15 00:00:00")),
+            // 時刻が範囲外なら日付のみ
+            ("IMG_20230515_996699", Some("2023-05-15 00:00:00")),
+            // 日付として成り立たない数字列・長い数字列は無視
+            ("IMG_20231345_123456", None),
+            ("DSC_0001", None),
+            ("1234567890123", None),
+            ("photo", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                format(date_in_name(name)).as_deref(),
+                expected,
+                "name: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolution_chain_and_sources() {
+        let root = temp_dir("chain");
+        let dated_dir = root.join("2019-08-03 夏休み");
+        std::fs::create_dir_all(&dated_dir).unwrap();
+
+        // ファイル名の日付がフォルダ名より優先される
+        let named = dated_dir.join("IMG-20230515-WA0001.jpg");
+        std::fs::write(&named, b"not an image").unwrap();
+        let date = resolve_capture_date(&named, Some(&root)).unwrap();
+        assert_eq!(date.date_time, "2023-05-15 00:00:00");
+        assert_eq!(date.source, CaptureDateSource::Filename);
+        assert_eq!(date.date(), "2023-05-15");
+
+        // ファイル名に日付がなければフォルダ名
+        let scan = dated_dir.join("scan0001.jpg");
+        std::fs::write(&scan, b"not an image").unwrap();
+        let date = resolve_capture_date(&scan, Some(&root)).unwrap();
+        assert_eq!(date.date_time, "2019-08-03 00:00:00");
+        assert_eq!(date.source, CaptureDateSource::Folder);
+
+        // どちらもなければ Takeout のサイドカー
+        let plain_dir = root.join("Takeout");
+        std::fs::create_dir_all(&plain_dir).unwrap();
+        let takeout = plain_dir.join("photo.jpg");
+        std::fs::write(&takeout, b"not an image").unwrap();
+        std::fs::write(
+            plain_dir.join("photo.jpg.supplemental-metadata.json"),
+            r#"{"title": "photo.jpg", "photoTakenTime": {"timestamp": "1684154096", "formatted": "15 May 2023, 12:34:56 UTC"}}"#,
+        )
+        .unwrap();
+        let date = resolve_capture_date(&takeout, Some(&root)).unwrap();
+        assert_eq!(date.source, CaptureDateSource::Sidecar);
+        let expected = DateTime::from_timestamp(1_684_154_096, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        assert_eq!(date.date_time, expected);
+
+        // 手がかりがなければ更新日時
+        let bare = plain_dir.join("untitled.jpg");
+        std::fs::write(&bare, b"not an image").unwrap();
+        let date = resolve_capture_date(&bare, Some(&root)).unwrap();
+        assert_eq!(date.source, CaptureDateSource::FileModified);
+
+        // 存在しないファイルは None
+        assert_eq!(
+            resolve_capture_date(&plain_dir.join("missing.jpg"), Some(&root)),
+            None
+        );
+
+        // ライブラリのルートより上のフォルダ名（バックアップ先の日付など）は使わない
+        let library = root.join("2019-05-01-backup").join("photos");
+        std::fs::create_dir_all(library.join("misc")).unwrap();
+        let undated = library.join("misc").join("scan0002.jpg");
+        std::fs::write(&undated, b"not an image").unwrap();
+        let date = resolve_capture_date(&undated, Some(&library)).unwrap();
+        assert_eq!(date.source, CaptureDateSource::FileModified);
+        let date = resolve_capture_date(&undated, None).unwrap();
+        assert_eq!(date.date_time, "2019-05-01 00:00:00");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_source_round_trip() {
+        for source in [
+            CaptureDateSource::Exif,
+            CaptureDateSource::Video,
+            CaptureDateSource::Filename,
+            CaptureDateSource::Folder,
+            CaptureDateSource::Sidecar,
+            CaptureDateSource::FileModified,
+        ] {
+            assert_eq!(CaptureDateSource::parse(source.as_str()), Some(source));
+        }
+    }
+}
@@ -1,6 +1,15 @@
-use crate::commands::types::AppState;
-use crate::ignore::IgnoreFilter;
-use crate::image_processor::get_exif_info;
+use crate::commands::image::{get_cached_capture_date, modified_secs};
+use crate::commands::journal::{move_file, record_action};
+use crate::commands::scan::{index_unignored_files_in_background, reapply_rules_to_playlist};
+use crate::commands::types::{AddPatternError, AppState};
+use crate::ignore::{
+    active_patterns, anchored_pattern, check_rules, export_rules, plan_import, preview_pattern,
+    validate_pattern, IgnoreFilter, IgnoreImportReport, IgnorePreview, IgnoreRule, PatternError,
+    RuleHealth, RuleSource,
+};
+use crate::journal::JournalAction;
+use crate::metadata_rule::{MetadataRule, MetadataRuleEntry};
+use crate::scanner::list_media_files;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -150,37 +159,304 @@ pub async fn pick_image(image_path: String, state: State<'_, AppState>) -> Resul
     // ファイルをコピー
     fs::copy(source_path, &final_dest_path).map_err(|e| format!("Failed to copy file: {e}"))?;
 
-    Ok(final_dest_path.to_string_lossy().to_string())
+    let picked_path = final_dest_path.to_string_lossy().to_string();
+    record_action(
+        &state,
+        JournalAction::Pick {
+            source_path: image_path,
+            picked_path: picked_path.clone(),
+        },
+    );
+    Ok(picked_path)
 }
 
 /// 除外ルール一覧を取得
 #[tauri::command]
-pub async fn get_ignore_patterns(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+pub async fn get_ignore_patterns(state: State<'_, AppState>) -> Result<Vec<IgnoreRule>, String> {
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
     db.get_ignore_rules()
         .map_err(|e| format!("Failed to get ignore rules: {e}"))
 }
 
-/// 除外ルールを削除
+/// 除外ルールを削除（除外されていたファイルをすぐにプレイリストへ戻す）
 #[tauri::command]
 pub async fn remove_ignore_pattern(
+    app: tauri::AppHandle,
     pattern: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
     db.remove_ignore_rule(&pattern)
-        .map_err(|e| format!("Failed to remove ignore rule: {e}"))
+        .map_err(|e| format!("Failed to remove ignore rule: {e}"))?;
+    drop(db);
+
+    reapply_rules_to_playlist(&state)?;
+    index_unignored_files_in_background(&app);
+    Ok(())
+}
+
+/// 除外ルールを手動追加（動作中のプレイリストにもすぐに反映する）
+///
+/// `expires_at`（UNIX 秒）を指定するとその時刻までのスヌーズになり、過ぎたら写真が戻る。`label` は覚え書き。
+/// 書式の誤りは入力欄で示せるよう `AddPatternError::Invalid` で返す。
+#[tauri::command]
+pub async fn add_ignore_pattern(
+    app: tauri::AppHandle,
+    pattern: String,
+    expires_at: Option<i64>,
+    label: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AddPatternError> {
+    validate_pattern(&pattern).map_err(|error| AddPatternError::Invalid { error })?;
+    if expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now().timestamp()) {
+        return Err("Snooze expiry is in the past".to_string().into());
+    }
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let rule = IgnoreRule {
+        expires_at,
+        label: normalize_label(label),
+        ..IgnoreRule::new(pattern.clone(), Some(RuleSource::Settings))
+    };
+    let previous = find_ignore_rule(&db, &pattern)?;
+    db.add_ignore_rule(&rule)
+        .map_err(|e| format!("Failed to add ignore rule: {e}"))?;
+    drop(db);
+    record_action(
+        &state,
+        JournalAction::AddIgnoreRule {
+            rule: Box::new(rule),
+            previous: previous.map(Box::new),
+        },
+    );
+
+    reapply_rules_to_playlist(&state)?;
+    // `!` のルールはこれまで除外していたファイルを戻すので、ライブラリから拾い直す
+    if pattern.trim().starts_with('!') {
+        index_unignored_files_in_background(&app);
+    }
+    Ok(())
+}
+
+/// 除外ルールを止める・再開する（消さずに一時的に効かなくする。動作中のプレイリストにもすぐに反映する）
+#[tauri::command]
+pub async fn set_ignore_rule_enabled(
+    app: tauri::AppHandle,
+    pattern: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let found = db
+        .set_ignore_rule_enabled(&pattern, enabled)
+        .map_err(|e| format!("Failed to update ignore rule: {e}"))?;
+    drop(db);
+    if !found {
+        return Err(format!("Ignore rule not found: {pattern}"));
+    }
+
+    reapply_rules_to_playlist(&state)?;
+    // 止めたルールが除外していたファイル、または再開した `!` ルールが戻すファイルを拾い直す
+    if !enabled || pattern.trim().starts_with('!') {
+        index_unignored_files_in_background(&app);
+    }
+    Ok(())
+}
+
+/// 除外ルールのラベルを変える（空文字や None で消す）
+#[tauri::command]
+pub async fn set_ignore_rule_label(
+    pattern: String,
+    label: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let found = db
+        .set_ignore_rule_label(&pattern, normalize_label(label).as_deref())
+        .map_err(|e| format!("Failed to update ignore rule: {e}"))?;
+    if !found {
+        return Err(format!("Ignore rule not found: {pattern}"));
+    }
+    Ok(())
+}
+
+/// 同じパターンの保存済みルール（上書きする前の状態を取り消し用に残す）
+fn find_ignore_rule(
+    db: &crate::database::Database,
+    pattern: &str,
+) -> Result<Option<IgnoreRule>, String> {
+    Ok(db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?
+        .into_iter()
+        .find(|rule| rule.pattern == pattern))
+}
+
+/// 前後の空白を落とし、空ならラベルなしにする
+fn normalize_label(label: Option<String>) -> Option<String> {
+    label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+}
+
+/// 除外ルールとして保存できるか確かめる（読めなければ位置と理由を返す）
+#[tauri::command]
+pub async fn validate_ignore_pattern(pattern: String) -> Result<Option<PatternError>, String> {
+    Ok(validate_pattern(&pattern).err())
 }
 
-/// 除外ルールを手動追加
+/// 保存済みの除外ルールのうち、読めないものとライブラリのどこにもマッチしないものを返す
+///
+/// スキャンは除外したファイルを記録しないので、前回スキャンしたライブラリを除外ルールなしで歩き直して判定する。
 #[tauri::command]
-pub async fn add_ignore_pattern(pattern: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn check_ignore_rules(state: State<'_, AppState>) -> Result<Vec<RuleHealth>, String> {
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
-    db.add_ignore_rule(&pattern)
-        .map_err(|e| format!("Failed to add ignore rule: {e}"))
+    let patterns: Vec<String> = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?
+        .into_iter()
+        .map(|rule| rule.pattern)
+        .collect();
+    let root = db
+        .get_setting("last_directory_path")
+        .ok()
+        .flatten()
+        .ok_or("No library has been scanned yet")?;
+    drop(db);
+
+    let entries: Vec<(PathBuf, bool)> = walkdir::WalkDir::new(&root)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|entry| (entry.path().to_path_buf(), entry.file_type().is_dir()))
+        .collect();
+    Ok(check_rules(&patterns, Path::new(&root), &entries))
+}
+
+/// 除外ルールを追加する前に、いまのライブラリで何件が除外されるかを試す（DB は変えない）
+///
+/// すでに除外しているファイルは `file_metadata` に残らないので、ライブラリを除外ルールなしで歩き直して判定する。
+#[tauri::command]
+pub async fn preview_ignore_pattern(
+    pattern: String,
+    state: State<'_, AppState>,
+) -> Result<IgnorePreview, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let existing = active_patterns(
+        &db.get_ignore_rules()
+            .map_err(|e| format!("Failed to get ignore rules: {e}"))?,
+        chrono::Utc::now().timestamp(),
+    );
+    let root = db
+        .get_setting("last_directory_path")
+        .ok()
+        .flatten()
+        .ok_or("No library has been scanned yet")?;
+    drop(db);
+
+    let paths = list_media_files(Path::new(&root));
+    preview_pattern(
+        &existing,
+        pattern.trim(),
+        Some(Path::new(&root)),
+        &paths,
+        20,
+    )
 }
 
-/// 除外機能：画像をDBのignore_rulesに追加
+/// 除外ルールを gitignore 形式のファイルに書き出す（追加日時・期限は由来コメントとして残す。書き出した数を返す）
+#[tauri::command]
+pub async fn export_ignore_rules(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let rules = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
+    drop(db);
+
+    fs::write(
+        &file_path,
+        export_rules(&rules, chrono::Utc::now().timestamp()),
+    )
+    .map_err(|e| format!("Failed to write ignore file: {e}"))?;
+    Ok(rules.len())
+}
+
+/// gitignore 形式のファイルから除外ルールを読み込む（replace なら置き換え、そうでなければ追加）
+///
+/// 衝突した行・読めなかった行は読み込まずに結果で返す。読み込んだルールはプレイリストにすぐ反映する。
+#[tauri::command]
+pub async fn import_ignore_rules(
+    app: tauri::AppHandle,
+    file_path: String,
+    replace: bool,
+    state: State<'_, AppState>,
+) -> Result<IgnoreImportReport, String> {
+    let content =
+        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read ignore file: {e}"))?;
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let existing = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
+    let (mut rules, report) =
+        plan_import(&existing, &content, replace, chrono::Utc::now().timestamp());
+    for rule in &mut rules {
+        rule.source = Some(RuleSource::Import {
+            file_path: file_path.clone(),
+        });
+    }
+    db.import_ignore_rules(&rules, replace)
+        .map_err(|e| format!("Failed to import ignore rules: {e}"))?;
+    drop(db);
+
+    reapply_rules_to_playlist(&state)?;
+    // 置き換えや `!` の追加で戻るファイルがあるので、ライブラリから拾い直す
+    index_unignored_files_in_background(&app);
+    Ok(report)
+}
+
+/// メタデータの除外ルール一覧を取得
+#[tauri::command]
+pub async fn get_metadata_rules(
+    state: State<'_, AppState>,
+) -> Result<Vec<MetadataRuleEntry>, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    db.get_metadata_rules()
+        .map_err(|e| format!("Failed to get metadata rules: {e}"))
+}
+
+/// メタデータの除外ルールを追加（動作中のプレイリストにもすぐに反映する。追加したルールの id を返す）
+#[tauri::command]
+pub async fn add_metadata_rule(
+    rule: MetadataRule,
+    state: State<'_, AppState>,
+) -> Result<i64, String> {
+    rule.validate()?;
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let id = db
+        .add_metadata_rule(&rule)
+        .map_err(|e| format!("Failed to add metadata rule: {e}"))?;
+    drop(db);
+
+    reapply_rules_to_playlist(&state)?;
+    Ok(id)
+}
+
+/// メタデータの除外ルールを削除（除外されていたファイルをすぐにプレイリストへ戻す）
+#[tauri::command]
+pub async fn remove_metadata_rule(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    db.remove_metadata_rule(id)
+        .map_err(|e| format!("Failed to remove metadata rule: {e}"))?;
+    drop(db);
+
+    reapply_rules_to_playlist(&state)?;
+    Ok(())
+}
+
+/// 除外機能：画像をDBの除外ルールに追加（日付はメタデータの除外ルール、ファイル・フォルダは ignore_rules）
 #[tauri::command]
 pub async fn exclude_image(
     image_path: String,
@@ -193,58 +469,84 @@ pub async fn exclude_image(
         return Err("Image file does not exist".to_string());
     }
 
-    let pattern = match exclude_type.as_str() {
-        "date" => {
-            // EXIFから日付を取得
-            match get_exif_info(path) {
-                Ok(exif) => {
-                    if let Some(date_time) = exif.date_time {
-                        // "YYYY:MM:DD HH:MM:SS" から "YYYY-MM-DD" を抽出
-                        let date_part = date_time.split(' ').next().unwrap_or("");
-                        let date = date_part.replace(':', "-");
-                        format!("*{date}*")
-                    } else {
-                        return Err("No EXIF date found".to_string());
-                    }
-                }
-                Err(_) => return Err("Failed to read EXIF data".to_string()),
-            }
+    let root = state
+        .directory_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+
+    if exclude_type == "date" {
+        // 撮影日時（EXIF がなければファイル名・フォルダ名・サイドカー・更新日時）の日付をまるごと除外する
+        let modified_time = fs::metadata(path).map(|m| modified_secs(&m)).unwrap_or(0);
+        let capture_date =
+            get_cached_capture_date(&image_path, modified_time, root.as_deref(), &state.db)
+                .ok_or("Failed to resolve capture date")?;
+        let date = capture_date.date().to_string();
+        let rule = MetadataRule::DateRange {
+            from: Some(date.clone()),
+            to: Some(date.clone()),
+        };
+
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        let existed = db
+            .get_metadata_rules()
+            .map_err(|e| format!("Failed to get metadata rules: {e}"))?
+            .iter()
+            .any(|entry| entry.rule == rule);
+        let id = db
+            .add_metadata_rule(&rule)
+            .map_err(|e| format!("Failed to add metadata rule: {e}"))?;
+        drop(db);
+        // 同じルールがすでにあれば何も変わっていないので、取り消す対象にしない
+        if !existed {
+            record_action(&state, JournalAction::AddMetadataRule { id, rule });
         }
+
+        reapply_rules_to_playlist(&state)?;
+        return Ok(format!("撮影日の除外ルール追加: {date}"));
+    }
+
+    let pattern = match exclude_type.as_str() {
         "file" => {
             // ファイル名パターン
-            path.to_string_lossy().to_string()
+            anchored_pattern(path, root.as_deref())
         }
         "directory" => {
             // ディレクトリパターン
             if let Some(parent) = path.parent() {
-                format!("{}/*", parent.to_string_lossy())
+                format!("{}/*", anchored_pattern(parent, root.as_deref()))
             } else {
                 return Err("Failed to get parent directory".to_string());
             }
         }
         _ => return Err("Invalid exclude type".to_string()),
     };
+    validate_pattern(&pattern).map_err(|e| e.to_string())?;
 
     // DB に除外ルールを追加
+    let rule = IgnoreRule::new(
+        pattern.clone(),
+        Some(RuleSource::Menu {
+            image_path: image_path.clone(),
+            exclude_type: exclude_type.clone(),
+        }),
+    );
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
-    db.add_ignore_rule(&pattern)
+    let previous = find_ignore_rule(&db, &pattern)?;
+    db.add_ignore_rule(&rule)
         .map_err(|e| format!("Failed to add ignore rule: {e}"))?;
     drop(db);
-
-    // プレイリストから該当画像を削除（ファイル除外の場合のみ即座に削除）
-    if exclude_type == "file" {
-        let mut playlist_lock = state.playlist.lock().unwrap_or_else(|e| e.into_inner());
-        if let Some(ref mut playlist) = *playlist_lock {
-            playlist.update_images(vec![], vec![image_path.clone()]);
-        }
-        drop(playlist_lock);
-        Ok(format!("除外パターン追加: {pattern}"))
-    } else {
-        // 日付・ディレクトリ除外は再スキャンが必要
-        Ok(format!(
-            "除外パターン追加: {pattern} (変更を反映するには再スキャンしてください)"
-        ))
-    }
+    record_action(
+        &state,
+        JournalAction::AddIgnoreRule {
+            rule: Box::new(rule),
+            previous: previous.map(Box::new),
+        },
+    );
+
+    // 再スキャンを待たずに、当てはまる画像をプレイリストから外す
+    reapply_rules_to_playlist(&state)?;
+    Ok(format!("除外パターン追加: {pattern}"))
 }
 
 /// 最近表示した画像一覧を取得（最新100件、除外済み除く）
@@ -253,10 +555,7 @@ pub async fn get_recent_images(state: State<'_, AppState>) -> Result<Vec<RecentI
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
 
     // 除外パターンを取得してフィルタを構築
-    let patterns = db
-        .get_ignore_rules()
-        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
-    let ignore_filter = IgnoreFilter::from_patterns(&patterns);
+    let mut ignore_filter = load_ignore_filter(&db)?;
 
     // 最近表示した画像を多めに取得（除外フィルタ後に最大100件を返す。
     // 除外率が高い場合は100件未満になりうる）
@@ -265,10 +564,13 @@ pub async fn get_recent_images(state: State<'_, AppState>) -> Result<Vec<RecentI
         .map_err(|e| format!("Failed to get recent images: {e}"))?;
     drop(db);
 
-    // 除外パターンにマッチしないものだけ返す（最大100件）
+    // 除外パターン（フォルダごとの .sssignore を含む）にマッチしないものだけ返す（最大100件）
     let filtered: Vec<RecentImage> = all_recent
         .into_iter()
-        .filter(|(path, _, _)| !ignore_filter.is_ignored(Path::new(path)))
+        .filter(|(path, _, _)| {
+            ignore_filter.load_ignore_files_along(Path::new(path));
+            !ignore_filter.is_ignored(Path::new(path))
+        })
         .take(100)
         .map(|(path, display_count, last_displayed)| RecentImage {
             path,
@@ -280,8 +582,20 @@ pub async fn get_recent_images(state: State<'_, AppState>) -> Result<Vec<RecentI
     Ok(filtered)
 }
 
+/// DB の除外ルールからフィルタを作る（固定パターンは前回スキャンしたライブラリ基準）
+pub(crate) fn load_ignore_filter(db: &crate::database::Database) -> Result<IgnoreFilter, String> {
+    let rules = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
+    let filter = IgnoreFilter::from_rules(&rules, chrono::Utc::now().timestamp());
+    Ok(match db.get_setting("last_directory_path").ok().flatten() {
+        Some(root) => filter.with_root(Path::new(&root)),
+        None => filter,
+    })
+}
+
 /// ピック済みフォルダのパスを取得するヘルパー
-fn get_picked_directory(db: &crate::database::Database) -> Result<PathBuf, String> {
+pub(crate) fn get_picked_directory(db: &crate::database::Database) -> Result<PathBuf, String> {
     match db
         .get_setting("share_directory_path")
         .map_err(|e| e.to_string())?
@@ -307,15 +621,21 @@ pub async fn get_picked_images(state: State<'_, AppState>) -> Result<Vec<String>
     let picked_dir = get_picked_directory(&db)?;
     drop(db);
 
+    list_picked_images(&picked_dir)
+}
+
+/// ピック済みフォルダ内の画像をパス順に列挙するヘルパー
+pub(crate) fn list_picked_images(picked_dir: &Path) -> Result<Vec<String>, String> {
     if !picked_dir.exists() {
         return Ok(Vec::new());
     }
 
-    let image_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif"];
+    let image_extensions = [
+        "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "exr", "hdr",
+    ];
 
     let mut images: Vec<String> = Vec::new();
-    let entries =
-        fs::read_dir(&picked_dir).map_err(|e| format!("Failed to read directory: {e}"))?;
+    let entries = fs::read_dir(picked_dir).map_err(|e| format!("Failed to read directory: {e}"))?;
 
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read entry: {e}"))?;
@@ -360,7 +680,21 @@ pub async fn delete_picked_image(
         return Err("Cannot delete files outside the picked directory".to_string());
     }
 
-    fs::remove_file(path).map_err(|e| format!("Failed to delete file: {e}"))?;
+    // すぐには消さず退避しておき、取り消せるようにする（履歴から落ちたときに消える）
+    let file_name = path.file_name().ok_or("Failed to get file name")?;
+    let trash_path = state.trash_dir.join(format!(
+        "{}_{}",
+        chrono::Utc::now().timestamp_millis(),
+        file_name.to_string_lossy()
+    ));
+    move_file(path, &trash_path).map_err(|e| format!("Failed to delete file: {e}"))?;
+    record_action(
+        &state,
+        JournalAction::DeletePicked {
+            picked_path: image_path,
+            trash_path: trash_path.to_string_lossy().to_string(),
+        },
+    );
     Ok(())
 }
 
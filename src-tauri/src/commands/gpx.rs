@@ -0,0 +1,40 @@
+use crate::commands::types::AppState;
+use crate::gpx::{parse_gpx, GpxTrackSummary};
+use std::fs;
+use tauri::State;
+
+/// GPX ファイルを取り込む（同じファイルは置き換え）。GPS のない写真の撮影地推定に使う
+#[tauri::command]
+pub fn import_gpx(
+    state: State<AppState>,
+    file_path: String,
+) -> Result<Option<GpxTrackSummary>, String> {
+    let xml =
+        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read GPX file: {e}"))?;
+    let track = parse_gpx(&xml)?;
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let id = db
+        .insert_gpx_track(&file_path, &track)
+        .map_err(|e| format!("Failed to import GPX track: {e}"))?;
+    let tracks = db
+        .get_gpx_tracks()
+        .map_err(|e| format!("Failed to get GPX tracks: {e}"))?;
+    Ok(tracks.into_iter().find(|t| t.id == id))
+}
+
+/// 取り込み済みの GPX トラック一覧
+#[tauri::command]
+pub fn get_gpx_tracks(state: State<AppState>) -> Result<Vec<GpxTrackSummary>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_gpx_tracks()
+        .map_err(|e| format!("Failed to get GPX tracks: {e}"))
+}
+
+/// GPX トラックを削除
+#[tauri::command]
+pub fn remove_gpx_track(state: State<AppState>, id: i64) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_gpx_track(id)
+        .map_err(|e| format!("Failed to remove GPX track: {e}"))
+}
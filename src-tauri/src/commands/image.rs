@@ -1,15 +1,97 @@
+use crate::animation::{optimize_animated_gif_for_4k, probe_animation, AnimationInfo};
+use crate::capture_date::{resolve_capture_date, CaptureDate};
+use crate::commands::scan::release_expired_snoozes;
 use crate::commands::types::AppState;
+use crate::database::Database;
+use crate::geocode::{reverse_geocode, Location};
+use crate::gpx::{interpolate, photo_time_utc};
 use crate::image_processor::{
-    get_exif_info, get_image_dimensions, is_video_file, optimize_image_for_4k, ImageInfo,
+    compose_blurred_letterbox, detect_focal_point, encode_for_4k, get_exif_info,
+    get_image_dimensions, is_video_file, load_image, AspectRatio, ExifInfo, FocalPoint, ImageInfo,
     MAX_HEIGHT_4K, MAX_WIDTH_4K,
 };
+use crate::path_norm::canonical_path;
+use crate::poster::{extract_poster_frame, find_ffmpeg, temp_output_path};
+use crate::tone_mapping::{is_high_bit_depth_file, tone_map, ToneMapping};
+use crate::video_metadata::{read_video_metadata, VideoMetadata};
+use crate::xmp::{metadata_modified_time, read_xmp_metadata, XmpMetadata};
+use image::ImageFormat;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
 use tauri::State;
 
+/// 既定の表示アスペクト比（display_aspect_ratio 未設定時）
+const DEFAULT_DISPLAY_ASPECT_RATIO: AspectRatio = AspectRatio {
+    width: 16,
+    height: 9,
+};
+
+/// 表示用キャッシュの作り方（設定から決まる）
+#[derive(Debug, Clone, Copy)]
+struct RenderOptions {
+    apply_rotation: bool,
+    /// ぼかし背景で余白を埋める場合の表示アスペクト比（None なら黒帯のまま）
+    letterbox: Option<AspectRatio>,
+    /// 16bit / HDR 画像を 8bit にするときの階調変換
+    tone_mapping: ToneMapping,
+}
+
+impl RenderOptions {
+    /// 設定を読み込む
+    ///   - apply_exif_rotation: EXIF 回転を適用するか（デフォルト true）
+    ///   - letterbox_background: "blur" ならぼかし背景（デフォルトは黒帯）
+    ///   - display_aspect_ratio: ぼかし背景のキャンバス比率（"16:9" 形式、デフォルト 16:9）
+    ///   - tone_mapping:{ライブラリのパス} → tone_mapping: 階調変換（ライブラリごとに上書き可、デフォルト auto）
+    ///     パスは `last_directory_path` と同じ正規形（フロントエンドはそれをキーにして保存する）
+    fn load(db: &Database, library: Option<&Path>) -> Self {
+        let setting = |key: &str| db.get_setting(key).ok().flatten();
+
+        let apply_rotation = setting("apply_exif_rotation")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        let letterbox = (setting("letterbox_background").as_deref() == Some("blur")).then(|| {
+            setting("display_aspect_ratio")
+                .and_then(|v| AspectRatio::parse(&v))
+                .unwrap_or(DEFAULT_DISPLAY_ASPECT_RATIO)
+        });
+        let tone_mapping = library
+            .and_then(|dir| setting(&format!("tone_mapping:{}", canonical_path(dir))))
+            .or_else(|| setting("tone_mapping"))
+            .and_then(|v| ToneMapping::parse(&v))
+            .unwrap_or_default();
+
+        Self {
+            apply_rotation,
+            letterbox,
+            tone_mapping,
+        }
+    }
+
+    /// 現在のライブラリに対する設定を読み込む
+    fn for_current_library(state: &AppState) -> Self {
+        let library = state
+            .directory_path
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        Self::load(&db, library.as_deref())
+    }
+}
+
 /// 次の画像を取得（カウント+1）
 #[tauri::command]
-pub async fn get_next_image(state: State<'_, AppState>) -> Result<Option<ImageInfo>, String> {
+pub async fn get_next_image(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<ImageInfo>, String> {
+    // スヌーズの期限が来た写真は、バックグラウンドでこの周回の残りに戻す
+    if let Err(e) = release_expired_snoozes(&app, &state) {
+        eprintln!("{e}");
+    }
+
     let mut playlist_lock = state.playlist.lock().unwrap_or_else(|e| e.into_inner());
 
     if let Some(ref mut playlist) = *playlist_lock {
@@ -33,18 +115,10 @@ pub async fn get_next_image(state: State<'_, AppState>) -> Result<Option<ImageIn
             drop(playlist_lock);
 
             // 5枚先まで先読みキャッシュ（バックグラウンドで直列処理）
-            // apply_exif_rotation 設定を取得（デフォルト true）
-            let apply_rotation = {
-                let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
-                db.get_setting("apply_exif_rotation")
-                    .ok()
-                    .flatten()
-                    .map(|v| v != "false")
-                    .unwrap_or(true)
-            };
+            let options = RenderOptions::for_current_library(&state);
 
             let cache_dir = state.cache_dir.clone();
-            prefetch_and_cache_multiple(prefetch_paths, cache_dir, apply_rotation);
+            prefetch_and_cache_multiple(prefetch_paths, cache_dir, options, Arc::clone(&state.db));
 
             // 表示回数を増やす（新しい画像の場合のみ）
             if should_count {
@@ -54,7 +128,7 @@ pub async fn get_next_image(state: State<'_, AppState>) -> Result<Option<ImageIn
             }
 
             // 画像情報を取得
-            get_image_info_internal(&path_str, &state, apply_rotation)
+            get_image_info_internal(&path_str, &state, options)
         } else {
             Ok(None)
         }
@@ -83,18 +157,10 @@ pub async fn get_previous_image(state: State<'_, AppState>) -> Result<Option<Ima
 
             drop(playlist_lock);
 
-            let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
-            // apply_exif_rotation 設定を取得（デフォルト true）
-            let apply_rotation = db
-                .get_setting("apply_exif_rotation")
-                .ok()
-                .flatten()
-                .map(|v| v != "false")
-                .unwrap_or(true);
-            drop(db);
+            let options = RenderOptions::for_current_library(&state);
 
             // 画像情報を取得（カウントは増やさない）
-            get_image_info_internal(&path_str, &state, apply_rotation)
+            get_image_info_internal(&path_str, &state, options)
         } else {
             Ok(None)
         }
@@ -107,7 +173,7 @@ pub async fn get_previous_image(state: State<'_, AppState>) -> Result<Option<Ima
 fn get_image_info_internal(
     image_path: &str,
     state: &State<AppState>,
-    apply_rotation: bool,
+    options: RenderOptions,
 ) -> Result<Option<ImageInfo>, String> {
     let path = Path::new(image_path);
 
@@ -118,65 +184,135 @@ fn get_image_info_internal(
     // 動画ファイルかどうかを判定
     let is_video = is_video_file(path);
 
-    // ファイルサイズ
-    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    // ファイルサイズと更新日時（寸法・注目点キャッシュの鮮度判定に使う）
+    let metadata = std::fs::metadata(path).ok();
+    let file_size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let modified_time = metadata.as_ref().map(modified_secs).unwrap_or(0);
 
-    // 画像サイズ（動画の場合は0x0）
-    let (width, height) = if !is_video {
-        get_image_dimensions(path).unwrap_or((0, 0))
+    // 動画のメタデータ（スキャン時に保存済み。なければコンテナを読む）
+    let video = if is_video {
+        get_cached_video_metadata(image_path, modified_time, &state.db)
     } else {
-        (0, 0)
+        None
+    };
+
+    // 画像サイズ（動画は回転適用後の表示サイズ、読めなかった場合は0x0）
+    let (width, height) = match &video {
+        Some(video) => (video.width, video.height),
+        None if is_video => (0, 0),
+        None => get_cached_dimensions(image_path, modified_time, &state.db),
     };
 
-    // キャッシュ対象の判定：
-    //   - 4K超の場合は常にキャッシュ
-    //   - 4K未満でも apply_rotation=true の場合はキャッシュ経由で回転を適用
-    let needs_cache =
-        !is_video && (width > MAX_WIDTH_4K || height > MAX_HEIGHT_4K || apply_rotation);
-
-    let optimized_path = if needs_cache {
-        // キャッシュファイル名を生成（元のファイル名のハッシュを使用）
-        let hash = format!(
-            "{:x}",
-            md5::compute(format!("{image_path}:{apply_rotation}"))
-        );
-        let cache_file = state.cache_dir.join(format!("{hash}.jpg"));
-
-        // キャッシュが存在する場合は使用
-        if cache_file.exists() {
-            Some(cache_file.to_string_lossy().to_string())
+    // アニメーション GIF / WebP の判定（フレーム数と1ループの再生時間）
+    let animation = if !is_video {
+        probe_animation(path)
+    } else {
+        None
+    };
+
+    // 注目点（静止画のみ。未解析なら None を返し、バックグラウンドで解析する）
+    let analyze_focal = !is_video && animation.is_none();
+    let focal_point = if analyze_focal {
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.get_focal_point(image_path, modified_time)
+            .ok()
+            .flatten()
+            .map(|(x, y)| FocalPoint { x, y })
+    } else {
+        None
+    };
+
+    let cache_file = if is_video {
+        None
+    } else {
+        optimized_cache_file(
+            image_path,
+            (width, height),
+            animation.as_ref(),
+            animation.is_none() && is_high_bit_depth_file(path),
+            options,
+            &state.cache_dir,
+        )
+    };
+
+    // 動画のポスターフレーム（キャッシュ済みなら使い、なければバックグラウンドで切り出す）
+    let poster_path = if is_video {
+        let poster_file = poster_cache_file(image_path, &state.cache_dir);
+        if poster_file.exists() {
+            Some(poster_file.to_string_lossy().to_string())
         } else {
-            // キャッシュがない場合は、バックグラウンドで作成して元画像を返す
-            let cache_file_clone = cache_file.clone();
-            let path_clone = path.to_path_buf();
-
-            std::thread::spawn(
-                move || match optimize_image_for_4k(&path_clone, apply_rotation) {
-                    Ok(optimized_data) => {
-                        if let Err(e) = fs::write(&cache_file_clone, optimized_data) {
-                            eprintln!("Failed to write optimized image: {e}");
-                        }
+            if find_ffmpeg().is_some() {
+                let path_clone = image_path.to_string();
+                let duration_ms = video.as_ref().and_then(|v| v.duration_ms);
+                std::thread::spawn(move || {
+                    if let Err(e) = prepare_poster(&path_clone, &poster_file, duration_ms) {
+                        eprintln!("Failed to extract poster frame: {e}");
                     }
-                    Err(e) => {
-                        eprintln!("Failed to optimize image: {e}");
-                    }
-                },
-            );
-
-            // 元画像を返す（すぐに表示）
+                });
+            }
             None
         }
     } else {
         None
     };
 
-    // EXIF情報（画像のみ）
+    // キャッシュが存在する場合は使用
+    let optimized_path = cache_file
+        .as_ref()
+        .filter(|f| f.exists())
+        .map(|f| f.to_string_lossy().to_string());
+    // ぼかし背景設定時、静止画のキャッシュは常に合成画像
+    let letterboxed =
+        optimized_path.is_some() && animation.is_none() && options.letterbox.is_some();
+
+    // キャッシュや注目点がない場合は、バックグラウンドで作成して元画像を返す（すぐに表示）
+    let pending_cache = cache_file.filter(|f| !f.exists());
+    let pending_focal = analyze_focal && focal_point.is_none();
+    if pending_cache.is_some() || pending_focal {
+        let path_clone = image_path.to_string();
+        let db = Arc::clone(&state.db);
+
+        std::thread::spawn(move || {
+            if let Err(e) = prepare_image(
+                &path_clone,
+                modified_time,
+                pending_cache.as_deref(),
+                options,
+                pending_focal,
+                &db,
+            ) {
+                eprintln!("Failed to optimize image: {e}");
+            }
+        });
+    }
+
+    // EXIF情報（画像のみ。GPS のない写真は GPX トラックログから撮影地を推定して補う）
     let exif = if !is_video {
-        get_exif_info(path).ok()
+        get_exif_info(path)
+            .ok()
+            .map(|exif| with_inferred_gps(image_path, modified_time, exif, &state.db))
     } else {
         None
     };
 
+    // 撮影日時（スキャン時に解決済み。なければここで解決する）
+    let root = state
+        .directory_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let capture_date =
+        get_cached_capture_date(image_path, modified_time, root.as_deref(), &state.db);
+
+    // レーティング・キーワード・キャプション（XMP / IPTC。何もなければ None）
+    let xmp = get_cached_xmp_metadata(image_path, modified_time, &state.db)
+        .filter(|metadata| !metadata.is_empty());
+
+    // 撮影地（GPS 座標を同梱の地名データで引く。ネットワーク不要）
+    let location = exif
+        .as_ref()
+        .and_then(|exif| get_cached_location(image_path, modified_time, exif, &state.db));
+
     // データベースから統計情報を取得
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
     let (display_count, last_displayed) = db.get_image_stats(image_path).unwrap_or((0, None));
@@ -192,54 +328,381 @@ fn get_image_info_internal(
         exif,
         display_count,
         last_displayed,
+        focal_point,
+        letterboxed,
+        frame_count: animation.map(|a| a.frame_count),
+        animation_duration_ms: animation.map(|a| a.duration_ms),
+        video,
+        poster_path,
+        capture_date,
+        xmp,
+        location,
     }))
 }
 
-/// 複数の画像を先読みしてキャッシュ作成（バックグラウンドで直列処理）
-fn prefetch_and_cache_multiple(image_paths: Vec<String>, cache_dir: PathBuf, apply_rotation: bool) {
+/// ファイルの更新日時（UNIX 秒）
+pub(crate) fn modified_secs(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 画像寸法を DB キャッシュから取得し、なければヘッダを読んでキャッシュする
+fn get_cached_dimensions(image_path: &str, modified_time: i64, db: &Mutex<Database>) -> (u32, u32) {
+    {
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(Some(dimensions)) = db.get_image_dimensions(image_path, modified_time) {
+            return dimensions;
+        }
+    }
+
+    match get_image_dimensions(Path::new(image_path)) {
+        Ok((width, height)) => {
+            let db = db.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = db.upsert_image_dimensions(image_path, modified_time, width, height) {
+                eprintln!("Failed to cache image dimensions: {e}");
+            }
+            (width, height)
+        }
+        Err(_) => (0, 0),
+    }
+}
+
+/// 動画メタデータを DB から取得し、なければコンテナを読んで保存する
+fn get_cached_video_metadata(
+    video_path: &str,
+    modified_time: i64,
+    db: &Mutex<Database>,
+) -> Option<VideoMetadata> {
+    {
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(Some(metadata)) = db.get_video_metadata(video_path, modified_time) {
+            return Some(metadata);
+        }
+    }
+
+    let metadata = read_video_metadata(Path::new(video_path))?;
+    let db = db.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = db.upsert_video_metadata(video_path, modified_time, &metadata) {
+        eprintln!("Failed to cache video metadata: {e}");
+    }
+    Some(metadata)
+}
+
+/// 撮影日時を DB から取得し、なければ解決して保存する（root はライブラリのルート）
+pub(crate) fn get_cached_capture_date(
+    image_path: &str,
+    modified_time: i64,
+    root: Option<&Path>,
+    db: &Mutex<Database>,
+) -> Option<CaptureDate> {
+    {
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(Some(capture_date)) = db.get_capture_date(image_path, modified_time) {
+            return Some(capture_date);
+        }
+    }
+
+    let capture_date = resolve_capture_date(Path::new(image_path), root)?;
+    let db = db.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = db.upsert_capture_date(image_path, modified_time, &capture_date) {
+        eprintln!("Failed to cache capture date: {e}");
+    }
+    Some(capture_date)
+}
+
+/// XMP メタデータを DB から取得し、なければ読み取って保存する
+fn get_cached_xmp_metadata(
+    image_path: &str,
+    modified_time: i64,
+    db: &Mutex<Database>,
+) -> Option<XmpMetadata> {
+    let modified_time = metadata_modified_time(Path::new(image_path), modified_time);
+    {
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(Some(metadata)) = db.get_xmp_metadata(image_path, modified_time) {
+            return Some(metadata);
+        }
+    }
+
+    let metadata = read_xmp_metadata(Path::new(image_path));
+    let db = db.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = db.upsert_xmp_metadata(image_path, modified_time, &metadata) {
+        eprintln!("Failed to cache XMP metadata: {e}");
+    }
+    Some(metadata)
+}
+
+/// GPS 座標の地名を DB から取得し、なければ引いて保存する（GPS のない画像は None）
+fn get_cached_location(
+    image_path: &str,
+    modified_time: i64,
+    exif: &ExifInfo,
+    db: &Mutex<Database>,
+) -> Option<Location> {
+    let (latitude, longitude) = (exif.gps_latitude?, exif.gps_longitude?);
+    // 推定した座標はトラックの取り込み・時計の補正で変わるので保存しない（引くのは一瞬）
+    if exif.gps_inferred {
+        return reverse_geocode(latitude, longitude);
+    }
+    {
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(Some(location)) = db.get_geocode(image_path, modified_time) {
+            return location;
+        }
+    }
+
+    let location = reverse_geocode(latitude, longitude);
+    let db = db.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = db.upsert_geocode(image_path, modified_time, location.as_ref()) {
+        eprintln!("Failed to cache location: {e}");
+    }
+    location
+}
+
+/// GPS のない写真に、GPX トラックログから推定した座標を入れる（推定結果は DB に保存）
+fn with_inferred_gps(
+    image_path: &str,
+    modified_time: i64,
+    mut exif: ExifInfo,
+    db: &Mutex<Database>,
+) -> ExifInfo {
+    if exif.gps_latitude.is_some() && exif.gps_longitude.is_some() {
+        return exif;
+    }
+
+    // トラックポイントの検索は DB 内で済むので、ロックを持ったまま推定する
+    let db = db.lock().unwrap_or_else(|e| e.into_inner());
+    let clock_offset = db
+        .get_setting("gpx_clock_offset_seconds")
+        .ok()
+        .flatten()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+    let location = match db.get_inferred_location(image_path, modified_time, clock_offset) {
+        Ok(Some(location)) => location,
+        _ => {
+            let location = photo_time_utc(&exif, clock_offset).and_then(|time| {
+                let (before, after) = db.get_gpx_neighbors(time).ok()?;
+                interpolate(before.as_ref(), after.as_ref(), time)
+            });
+            if let Err(e) = db.upsert_inferred_location(
+                image_path,
+                modified_time,
+                clock_offset,
+                location.as_ref(),
+            ) {
+                eprintln!("Failed to cache inferred location: {e}");
+            }
+            location
+        }
+    };
+    drop(db);
+
+    if let Some(location) = location {
+        exif.gps_latitude = Some(location.latitude);
+        exif.gps_longitude = Some(location.longitude);
+        exif.gps_altitude = location.altitude;
+        exif.gps_inferred = true;
+    }
+    exif
+}
+
+/// 複数の画像を先読みしてキャッシュ作成・注目点解析（バックグラウンドで直列処理）
+fn prefetch_and_cache_multiple(
+    image_paths: Vec<String>,
+    cache_dir: PathBuf,
+    options: RenderOptions,
+    db: Arc<Mutex<Database>>,
+) {
     use std::thread;
 
     thread::spawn(move || {
         for image_path in image_paths {
             let path = Path::new(&image_path);
 
-            if !path.exists() {
+            let Ok(metadata) = fs::metadata(path) else {
                 continue;
-            }
+            };
+
+            let modified_time = modified_secs(&metadata);
 
-            // 動画ファイルは画像処理をスキップ
+            // 動画はポスターフレームだけ用意する（ffmpeg がなければスキップ）
             if is_video_file(path) {
+                let poster_file = poster_cache_file(&image_path, &cache_dir);
+                if poster_file.exists() || find_ffmpeg().is_none() {
+                    continue;
+                }
+                let duration_ms = get_cached_video_metadata(&image_path, modified_time, &db)
+                    .and_then(|v| v.duration_ms);
+                if let Err(e) = prepare_poster(&image_path, &poster_file, duration_ms) {
+                    eprintln!("Failed to extract poster frame for prefetch: {e}");
+                }
                 continue;
             }
 
             // 画像サイズを取得
-            let (width, height) = match get_image_dimensions(path) {
-                Ok(dims) => dims,
-                Err(_) => continue,
-            };
+            let dimensions = get_cached_dimensions(&image_path, modified_time, &db);
+            if dimensions == (0, 0) {
+                continue;
+            }
 
             // 4Kを超える場合、または回転が必要な場合はキャッシュ作成
-            if width > MAX_WIDTH_4K || height > MAX_HEIGHT_4K || apply_rotation {
-                let hash = format!(
-                    "{:x}",
-                    md5::compute(format!("{image_path}:{apply_rotation}"))
-                );
-                let cache_file = cache_dir.join(format!("{hash}.jpg"));
-
-                // キャッシュが既に存在する場合はスキップ
-                if !cache_file.exists() {
-                    match optimize_image_for_4k(path, apply_rotation) {
-                        Ok(optimized_data) => {
-                            if let Err(e) = fs::write(&cache_file, optimized_data) {
-                                eprintln!("Failed to write prefetched cache: {e}");
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to optimize for prefetch: {e}");
-                        }
-                    }
-                }
+            // キャッシュが既に存在する場合はスキップ
+            let animation = probe_animation(path);
+            let pending_cache = optimized_cache_file(
+                &image_path,
+                dimensions,
+                animation.as_ref(),
+                animation.is_none() && is_high_bit_depth_file(path),
+                options,
+                &cache_dir,
+            )
+            .filter(|f| !f.exists());
+
+            // 静止画で注目点が未解析なら解析する
+            let pending_focal = animation.is_none() && {
+                let db = db.lock().unwrap_or_else(|e| e.into_inner());
+                !matches!(db.get_focal_point(&image_path, modified_time), Ok(Some(_)))
+            };
+
+            if pending_cache.is_none() && !pending_focal {
+                continue;
+            }
+            if let Err(e) = prepare_image(
+                &image_path,
+                modified_time,
+                pending_cache.as_deref(),
+                options,
+                pending_focal,
+                &db,
+            ) {
+                eprintln!("Failed to optimize for prefetch: {e}");
             }
         }
     });
 }
+
+/// 動画のポスターフレームのキャッシュファイルパス
+/// キャッシュディレクトリは起動時に消えるので、動画の更新は次回起動時に反映される
+fn poster_cache_file(video_path: &str, cache_dir: &Path) -> PathBuf {
+    let hash = format!("{:x}", md5::compute(format!("{video_path}:poster")));
+    cache_dir.join(format!("{hash}.jpg"))
+}
+
+/// ポスターフレームを切り出してキャッシュする
+/// 一時ファイルに書いてから rename するので、書き込み途中のファイルを表示することはない
+fn prepare_poster(
+    video_path: &str,
+    poster_file: &Path,
+    duration_ms: Option<u64>,
+) -> Result<(), String> {
+    let temp_file = temp_output_path(poster_file);
+    extract_poster_frame(Path::new(video_path), &temp_file, duration_ms)?;
+    fs::rename(&temp_file, poster_file).map_err(|e| {
+        let _ = fs::remove_file(&temp_file);
+        format!("Failed to save poster frame: {e}")
+    })
+}
+
+/// 最適化キャッシュのファイルパスを決める（原本をそのまま表示する場合は None）
+///   - アニメーション画像: GIF が 4K 超のときだけ全フレームを縮小したアニメーション GIF を作る。
+///     4K 以下やアニメーション WebP（エンコーダがない）は原本のままにしてアニメーションを保つ
+///   - 静止画: 4K 超の場合は常にキャッシュ。4K 未満でも apply_rotation=true の場合はキャッシュ経由で回転を適用
+///   - ぼかし背景: 静止画は常にキャッシュ。表示アスペクト比ごとに別のキャッシュになる
+///   - 16bit / HDR: 常にキャッシュ経由でトーンマッピングする。方式ごとに別のキャッシュになる
+fn optimized_cache_file(
+    image_path: &str,
+    (width, height): (u32, u32),
+    animation: Option<&AnimationInfo>,
+    high_bit_depth: bool,
+    options: RenderOptions,
+    cache_dir: &Path,
+) -> Option<PathBuf> {
+    let apply_rotation = options.apply_rotation;
+    let tone_suffix = if high_bit_depth {
+        format!(":{:?}", options.tone_mapping)
+    } else {
+        String::new()
+    };
+    let exceeds_4k = width > MAX_WIDTH_4K || height > MAX_HEIGHT_4K;
+
+    // キャッシュファイル名を生成（元のファイル名のハッシュを使用）
+    match animation {
+        Some(info) if info.format == ImageFormat::Gif && exceeds_4k => {
+            let hash = format!("{:x}", md5::compute(format!("{image_path}:animated")));
+            Some(cache_dir.join(format!("{hash}.gif")))
+        }
+        Some(_) => None,
+        None if options.letterbox.is_some() => {
+            let aspect = options.letterbox?;
+            let hash = format!(
+                "{:x}",
+                md5::compute(format!(
+                    "{image_path}:{apply_rotation}:blur{}x{}{tone_suffix}",
+                    aspect.width, aspect.height
+                ))
+            );
+            Some(cache_dir.join(format!("{hash}.jpg")))
+        }
+        None if exceeds_4k || apply_rotation || high_bit_depth => {
+            let hash = format!(
+                "{:x}",
+                md5::compute(format!("{image_path}:{apply_rotation}{tone_suffix}"))
+            );
+            Some(cache_dir.join(format!("{hash}.jpg")))
+        }
+        None => None,
+    }
+}
+
+/// 最適化キャッシュの作成（.gif はアニメーション GIF、それ以外は JPEG）と注目点の解析をまとめて行う
+/// 静止画は一度だけデコードし、その結果をキャッシュ作成と注目点解析の両方に使う
+fn prepare_image(
+    image_path: &str,
+    modified_time: i64,
+    cache_file: Option<&Path>,
+    options: RenderOptions,
+    analyze_focal: bool,
+    db: &Mutex<Database>,
+) -> Result<(), String> {
+    let path = Path::new(image_path);
+
+    if let Some(cache_file) = cache_file.filter(|f| f.extension().is_some_and(|ext| ext == "gif")) {
+        let optimized_data = optimize_animated_gif_for_4k(path)?;
+        return fs::write(cache_file, optimized_data)
+            .map_err(|e| format!("Failed to write optimized image: {e}"));
+    }
+
+    if cache_file.is_none() && !analyze_focal {
+        return Ok(());
+    }
+
+    let img = tone_map(
+        load_image(path, options.apply_rotation)?,
+        options.tone_mapping,
+    );
+
+    // 注目点は常に EXIF 回転適用後の向きで保存する（表示の向きと揃える）
+    if analyze_focal {
+        let focal = detect_focal_point(path, &img, options.apply_rotation);
+        let db = db.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = db.upsert_focal_point(image_path, modified_time, focal.x, focal.y) {
+            eprintln!("Failed to cache focal point: {e}");
+        }
+    }
+
+    if let Some(cache_file) = cache_file {
+        let optimized_data = match options.letterbox {
+            Some(aspect) => encode_for_4k(compose_blurred_letterbox(img, aspect))?,
+            None => encode_for_4k(img)?,
+        };
+        fs::write(cache_file, optimized_data)
+            .map_err(|e| format!("Failed to write optimized image: {e}"))?;
+    }
+
+    Ok(())
+}
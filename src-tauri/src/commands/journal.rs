@@ -0,0 +1,206 @@
+use crate::commands::scan::{index_unignored_files_in_background, reapply_rules_to_playlist};
+use crate::commands::types::AppState;
+use crate::journal::{JournalAction, JournalEntry, JOURNAL_LIMIT};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+/// 操作を履歴に積む（履歴に残せなくても操作自体は成功として扱う）
+pub(crate) fn record_action(state: &AppState, action: JournalAction) {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let dropped = match db.push_journal_action(&action, JOURNAL_LIMIT) {
+        Ok(dropped) => dropped,
+        Err(e) => {
+            eprintln!("Failed to record action: {e}");
+            return;
+        }
+    };
+    drop(db);
+
+    // もう取り消せなくなったピックの削除は、退避しておいたファイルを本当に消す
+    for action in dropped {
+        if let JournalAction::DeletePicked { trash_path, .. } = action {
+            if let Err(e) = fs::remove_file(&trash_path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    eprintln!("Failed to remove trashed pick {trash_path}: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// 最後の操作を取り消す（取り消せる操作がなければ None）
+///
+/// 除外ルールを戻したときは、除外していた写真をプレイリストにすぐ戻す（`file_metadata` にない写真はバックグラウンドで拾う）。
+#[tauri::command]
+pub async fn undo_action(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<JournalEntry>, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = db
+        .last_undoable_action()
+        .map_err(|e| format!("Failed to read action journal: {e}"))?;
+    drop(db);
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+
+    let action =
+        undo(&app, &state, entry.action.clone()).inspect_err(|_| forget(&state, entry.id))?;
+    finish(&state, entry, action, true).map(Some)
+}
+
+/// 最後に取り消した操作をやり直す（やり直せる操作がなければ None）
+#[tauri::command]
+pub async fn redo_action(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<JournalEntry>, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = db
+        .next_redoable_action()
+        .map_err(|e| format!("Failed to read action journal: {e}"))?;
+    drop(db);
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+
+    let action =
+        redo(&app, &state, entry.action.clone()).inspect_err(|_| forget(&state, entry.id))?;
+    finish(&state, entry, action, false).map(Some)
+}
+
+/// 取り消し・やり直しの結果を履歴に書き戻す
+fn finish(
+    state: &AppState,
+    entry: JournalEntry,
+    action: JournalAction,
+    undone: bool,
+) -> Result<JournalEntry, String> {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    db.set_journal_action_undone(entry.id, &action, undone)
+        .map_err(|e| format!("Failed to update action journal: {e}"))?;
+    Ok(JournalEntry { action, ..entry })
+}
+
+/// 戻せなかった操作は履歴から外す（残すとその先の操作まで取り消せなくなる）
+fn forget(state: &AppState, id: i64) {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = db.remove_journal_action(id) {
+        eprintln!("Failed to remove journal entry {id}: {e}");
+    }
+}
+
+/// 操作を打ち消す（書き戻す操作を返す）
+fn undo(app: &AppHandle, state: &AppState, action: JournalAction) -> Result<JournalAction, String> {
+    match &action {
+        JournalAction::AddIgnoreRule { rule, previous } => {
+            let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+            match previous {
+                // 上書きしたルールは期限・ラベル・有効フラグと並び順を元に戻す
+                Some(previous) => db.restore_ignore_rule(previous),
+                None => db.remove_ignore_rule(&rule.pattern),
+            }
+            .map_err(|e| format!("Failed to restore ignore rules: {e}"))?;
+            drop(db);
+
+            reapply_rules_to_playlist(state)?;
+            index_unignored_files_in_background(app);
+        }
+        JournalAction::AddMetadataRule { id, .. } => {
+            let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+            db.remove_metadata_rule(*id)
+                .map_err(|e| format!("Failed to remove metadata rule: {e}"))?;
+            drop(db);
+
+            reapply_rules_to_playlist(state)?;
+        }
+        JournalAction::Pick { picked_path, .. } => {
+            // すでに手で消されていれば戻すものはない
+            if let Err(e) = fs::remove_file(picked_path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(format!("Failed to delete picked file: {e}"));
+                }
+            }
+        }
+        JournalAction::DeletePicked {
+            picked_path,
+            trash_path,
+        } => {
+            if Path::new(picked_path).exists() {
+                return Err(format!("A file already exists at {picked_path}"));
+            }
+            move_file(Path::new(trash_path), Path::new(picked_path))?;
+        }
+    }
+    Ok(action)
+}
+
+/// 取り消した操作をもう一度行う（書き戻す操作を返す）
+fn redo(app: &AppHandle, state: &AppState, action: JournalAction) -> Result<JournalAction, String> {
+    match action {
+        JournalAction::AddIgnoreRule { rule, previous } => {
+            let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+            db.add_ignore_rule(&rule)
+                .map_err(|e| format!("Failed to add ignore rule: {e}"))?;
+            drop(db);
+
+            reapply_rules_to_playlist(state)?;
+            if rule.pattern.trim().starts_with('!') {
+                index_unignored_files_in_background(app);
+            }
+            Ok(JournalAction::AddIgnoreRule { rule, previous })
+        }
+        JournalAction::AddMetadataRule { rule, .. } => {
+            let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+            let id = db
+                .add_metadata_rule(&rule)
+                .map_err(|e| format!("Failed to add metadata rule: {e}"))?;
+            drop(db);
+
+            reapply_rules_to_playlist(state)?;
+            Ok(JournalAction::AddMetadataRule { id, rule })
+        }
+        JournalAction::Pick {
+            source_path,
+            picked_path,
+        } => {
+            if Path::new(&picked_path).exists() {
+                return Err(format!("A file already exists at {picked_path}"));
+            }
+            if let Some(parent) = Path::new(&picked_path).parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create share directory: {e}"))?;
+            }
+            fs::copy(&source_path, &picked_path)
+                .map_err(|e| format!("Failed to copy file: {e}"))?;
+            Ok(JournalAction::Pick {
+                source_path,
+                picked_path,
+            })
+        }
+        JournalAction::DeletePicked {
+            picked_path,
+            trash_path,
+        } => {
+            move_file(Path::new(&picked_path), Path::new(&trash_path))?;
+            Ok(JournalAction::DeletePicked {
+                picked_path,
+                trash_path,
+            })
+        }
+    }
+}
+
+/// ファイルを移す（別のボリュームをまたぐときはコピーしてから消す）
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {e}"))?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| format!("Failed to move file: {e}"))?;
+    fs::remove_file(from).map_err(|e| format!("Failed to move file: {e}"))
+}
@@ -1,10 +1,13 @@
 // サブモジュール宣言
 pub mod file_operations;
+pub mod gpx;
 pub mod image;
+pub mod journal;
 pub mod scan;
 pub mod settings;
 pub mod stats;
 pub mod system;
+pub mod thumbnail;
 pub mod types;
 
 // 公開型の再エクスポート
@@ -1,9 +1,17 @@
+use crate::capture_date::resolve_capture_date;
 use crate::commands::types::{AppState, ScanProgress};
-use crate::ignore::IgnoreFilter;
+use crate::ignore::{IgnoreFilter, IgnoreRule, RuleSource};
+use crate::image_processor::{get_exif_info, get_image_dimensions, is_video_file};
+use crate::metadata_rule::is_excluded;
+use crate::path_norm::canonical_str;
 use crate::playlist::Playlist;
 use crate::scanner::ImageScanner;
-use std::path::PathBuf;
-use tauri::{Emitter, State};
+use crate::video_metadata::read_video_metadata;
+use crate::xmp::{metadata_modified_time, read_xmp_metadata, MetadataFilter};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 /// ~/.sssignore が存在する場合、内容を DB にインポートして .sssignore.bak にリネーム
 fn migrate_sssignore_to_db(db: &crate::database::Database) {
@@ -33,7 +41,8 @@ fn migrate_sssignore_to_db(db: &crate::database::Database) {
                 if line.is_empty() || line.starts_with('#') {
                     continue;
                 }
-                if let Err(e) = db.add_ignore_rule(line) {
+                let rule = IgnoreRule::new(line, Some(RuleSource::Migration));
+                if let Err(e) = db.add_ignore_rule(&rule) {
                     eprintln!("Failed to import ignore rule '{line}': {e}");
                 }
             }
@@ -50,6 +59,16 @@ fn migrate_sssignore_to_db(db: &crate::database::Database) {
     }
 }
 
+/// プレイリストの絞り込み設定（playlist_min_rating / playlist_keywords / playlist_label）を読む
+fn load_metadata_filter(db: &crate::database::Database) -> MetadataFilter {
+    let setting = |key: &str| db.get_setting(key).ok().flatten();
+    MetadataFilter::from_settings(
+        setting("playlist_min_rating").as_deref(),
+        setting("playlist_keywords").as_deref(),
+        setting("playlist_label").as_deref(),
+    )
+}
+
 /// ディレクトリをスキャンしてプレイリストを初期化
 #[tauri::command]
 pub async fn scan_directory(
@@ -69,12 +88,14 @@ pub async fn scan_directory(
         migrate_sssignore_to_db(&db);
     }
 
-    // DB から除外ルールを取得して IgnoreFilter を作成
-    let patterns = {
+    // DB から除外ルールを取得して IgnoreFilter を作成（固定パターンはスキャンするディレクトリ基準）
+    // 期限（スヌーズ）を過ぎたルールはここで読み飛ばす
+    let rules = {
         let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
         db.get_ignore_rules().unwrap_or_default()
     };
-    let ignore_filter = IgnoreFilter::from_patterns(&patterns);
+    let ignore_filter =
+        IgnoreFilter::from_rules(&rules, chrono::Utc::now().timestamp()).with_root(&directory);
 
     // スキャナーを作成
     let scanner = ImageScanner::new(ignore_filter);
@@ -100,6 +121,62 @@ pub async fn scan_directory(
         },
     )?;
 
+    // 新規・変更された動画のメタデータを読み取る（コンテナのヘッダのみ、並列）
+    let new_paths: HashSet<&String> = scan_result.new_files.iter().collect();
+    let video_metadata: Vec<_> = scan_result
+        .files
+        .par_iter()
+        .filter(|f| new_paths.contains(&f.path) && is_video_file(Path::new(&f.path)))
+        .filter_map(|f| read_video_metadata(Path::new(&f.path)).map(|m| (f, m)))
+        .collect();
+
+    // 撮影日時を解決する（新規・変更されたファイルと、まだ解決していないファイル。並列）
+    let resolved_times = {
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.get_capture_date_modified_times().unwrap_or_default()
+    };
+    let capture_dates: Vec<_> = scan_result
+        .files
+        .par_iter()
+        .filter(|f| resolved_times.get(&f.path) != Some(&f.modified_time))
+        .filter_map(|f| resolve_capture_date(Path::new(&f.path), Some(&directory)).map(|d| (f, d)))
+        .collect();
+
+    // XMP / IPTC メタデータを読む（画像かサイドカーが更新されたファイルと、未読のファイル。並列）
+    let xmp_times = {
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.get_xmp_modified_times().unwrap_or_default()
+    };
+    let xmp_metadata: Vec<_> = scan_result
+        .files
+        .par_iter()
+        .filter_map(|f| {
+            let path = Path::new(&f.path);
+            let modified_time = metadata_modified_time(path, f.modified_time);
+            (xmp_times.get(&f.path) != Some(&modified_time))
+                .then(|| (f, modified_time, read_xmp_metadata(path)))
+        })
+        .collect();
+
+    // カメラ機種と画素数を読む（メタデータの除外ルール用。画像が更新されたファイルと、未読のファイル。並列）
+    let camera_times = {
+        let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.get_camera_info_modified_times().unwrap_or_default()
+    };
+    let camera_info: Vec<_> = scan_result
+        .files
+        .par_iter()
+        .filter(|f| {
+            camera_times.get(&f.path) != Some(&f.modified_time)
+                && !is_video_file(Path::new(&f.path))
+        })
+        .map(|f| {
+            let path = Path::new(&f.path);
+            let exif = get_exif_info(path).unwrap_or_default();
+            (f, exif, get_image_dimensions(path).ok())
+        })
+        .collect();
+
     // データベースを更新
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
 
@@ -109,6 +186,35 @@ pub async fn scan_directory(
             .map_err(|e| format!("Database error: {e}"))?;
     }
 
+    for (file, metadata) in &video_metadata {
+        db.upsert_video_metadata(&file.path, file.modified_time, metadata)
+            .map_err(|e| format!("Database error: {e}"))?;
+    }
+
+    for (file, capture_date) in &capture_dates {
+        db.upsert_capture_date(&file.path, file.modified_time, capture_date)
+            .map_err(|e| format!("Database error: {e}"))?;
+    }
+
+    for (file, modified_time, metadata) in &xmp_metadata {
+        db.upsert_xmp_metadata(&file.path, *modified_time, metadata)
+            .map_err(|e| format!("Database error: {e}"))?;
+    }
+
+    for (file, exif, dimensions) in &camera_info {
+        db.upsert_camera_info(
+            &file.path,
+            file.modified_time,
+            exif.camera_make.as_deref(),
+            exif.camera_model.as_deref(),
+        )
+        .map_err(|e| format!("Database error: {e}"))?;
+        if let Some((width, height)) = dimensions {
+            db.upsert_image_dimensions(&file.path, file.modified_time, *width, *height)
+                .map_err(|e| format!("Database error: {e}"))?;
+        }
+    }
+
     // 削除されたファイルをマーク
     if !scan_result.deleted_files.is_empty() {
         db.mark_deleted(&scan_result.deleted_files)
@@ -117,7 +223,7 @@ pub async fn scan_directory(
 
     // スキャン履歴を記録
     db.record_scan_history(
-        &directory_path,
+        &canonical_str(&directory_path),
         scan_result.total_count as i32,
         scan_result.new_count as i32,
         scan_result.deleted_count as i32,
@@ -129,10 +235,13 @@ pub async fn scan_directory(
     db.trim_scan_history(100)
         .map_err(|e| format!("Database error: {e}"))?;
 
-    drop(db);
+    // プレイリストに入れるものを選ぶ（除外ルールはスキャナが適用済み）
+    let image_paths = filter_by_metadata(
+        &db,
+        scan_result.files.iter().map(|f| f.path.clone()).collect(),
+    );
 
-    // プレイリストを作成または更新
-    let image_paths: Vec<String> = scan_result.files.iter().map(|f| f.path.clone()).collect();
+    drop(db);
 
     let mut playlist_lock = state.playlist.lock().unwrap_or_else(|e| e.into_inner());
 
@@ -149,11 +258,9 @@ pub async fn scan_directory(
 
     if is_same_directory && playlist_lock.is_some() {
         // 同じディレクトリの場合のみ既存のプレイリストを更新
+        // 絞り込み条件の変更も反映できるよう、スキャン結果との差分で追加・削除する
         if let Some(ref mut playlist) = *playlist_lock {
-            playlist.update_images(
-                scan_result.new_files.clone(),
-                scan_result.deleted_files.clone(),
-            );
+            sync_playlist(playlist, &image_paths);
         }
     } else {
         // 別のディレクトリまたは初回の場合は新規プレイリストを作成
@@ -170,7 +277,7 @@ pub async fn scan_directory(
 
     // ディレクトリパスをデータベースに永続化
     let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
-    let _ = db.save_setting("last_directory_path", &directory_path);
+    let _ = db.save_setting("last_directory_path", &canonical_str(&directory_path));
     drop(db);
 
     Ok(ScanProgress {
@@ -180,3 +287,195 @@ pub async fn scan_directory(
         duration_ms: scan_result.duration_ms,
     })
 }
+
+/// レーティング等の絞り込み設定とメタデータの除外ルール（撮影日・カメラ・画素数など）で、
+/// プレイリストに入れるパスを選ぶ
+fn filter_by_metadata(db: &crate::database::Database, paths: Vec<String>) -> Vec<String> {
+    // レーティング・キーワード・ラベルの絞り込み設定があれば、条件を満たす画像だけをプレイリストに入れる
+    let metadata_filter = load_metadata_filter(db);
+    let metadata = if metadata_filter.is_active() {
+        db.get_all_xmp_metadata().unwrap_or_default()
+    } else {
+        Default::default()
+    };
+
+    // メタデータの除外ルールに当てはまるものは入れない
+    let metadata_rules: Vec<_> = db
+        .get_metadata_rules()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| entry.rule)
+        .collect();
+    let media_facts = if metadata_rules.is_empty() {
+        Default::default()
+    } else {
+        db.get_all_media_facts().unwrap_or_default()
+    };
+
+    paths
+        .into_iter()
+        .filter(|path| metadata_filter.matches(metadata.get(path)))
+        .filter(|path| {
+            media_facts
+                .get(path)
+                .is_none_or(|facts| !is_excluded(&metadata_rules, facts))
+        })
+        .collect()
+}
+
+/// プレイリストの中身を wanted に合わせる（差分だけ追加・削除し、周回の位置と履歴は保つ）
+fn sync_playlist(playlist: &mut Playlist, wanted: &[String]) -> (usize, usize) {
+    let current: HashSet<&String> = playlist.images().iter().collect();
+    let wanted_set: HashSet<&String> = wanted.iter().collect();
+    let added: Vec<String> = wanted
+        .iter()
+        .filter(|path| !current.contains(path))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = current
+        .iter()
+        .filter(|path| !wanted_set.contains(*path))
+        .map(|path| (*path).clone())
+        .collect();
+    let counts = (added.len(), removed.len());
+    playlist.update_images(added, removed);
+    counts
+}
+
+/// 除外ルールを変えたあと、再スキャンせずに動作中のプレイリストへ反映する
+///
+/// `file_metadata` の全パスに除外ルール（フォルダごとの `.sssignore` を含む）・絞り込み設定・
+/// メタデータの除外ルールをかけ直し、外れたものを抜いて戻ったものを足す。戻り値は（追加数, 削除数）。
+pub(crate) fn reapply_rules_to_playlist(state: &AppState) -> Result<(usize, usize), String> {
+    let Some(directory) = state
+        .directory_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    else {
+        return Ok((0, 0));
+    };
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let rules = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
+    let paths = db
+        .get_all_file_metadata()
+        .map_err(|e| format!("Database error: {e}"))?;
+    drop(db);
+
+    let mut ignore_filter =
+        IgnoreFilter::from_rules(&rules, chrono::Utc::now().timestamp()).with_root(&directory);
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|(path, _, _)| path)
+        .filter(|path| {
+            ignore_filter.load_ignore_files_along(Path::new(path));
+            !ignore_filter.is_ignored(Path::new(path))
+        })
+        .collect();
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let wanted = filter_by_metadata(&db, paths);
+    drop(db);
+
+    let mut playlist_lock = state.playlist.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(match playlist_lock.as_mut() {
+        Some(playlist) => sync_playlist(playlist, &wanted),
+        None => (0, 0),
+    })
+}
+
+/// 除外ルールを外した（または `!` を足した）あと、これまで除外されていて `file_metadata` にないファイルを
+/// バックグラウンドで登録し、見つかればプレイリストにも足す
+///
+/// ルールの変更そのものは `reapply_rules_to_playlist` で `file_metadata` に当て直してすぐ反映する。
+/// ライブラリを歩き直すこちらはコマンドを待たせないよう別スレッドで行う。
+pub(crate) fn index_unignored_files_in_background(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let result = restore_unignored_files(&state).and_then(|restored| {
+            if restored > 0 {
+                reapply_rules_to_playlist(&state)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to index unignored files: {e}");
+        }
+    });
+}
+
+/// これまで除外されていて `file_metadata` にないファイルを登録する
+///
+/// スキャナは除外されたまま初めて見つかったファイルを記録しないので、ライブラリを歩き直して見つかった分だけを足す
+/// （撮影日時・XMP などは次のスキャンで読む）。戻り値は登録した数。
+fn restore_unignored_files(state: &AppState) -> Result<usize, String> {
+    let Some(directory) = state
+        .directory_path
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    else {
+        return Ok(0);
+    };
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let rules = db
+        .get_ignore_rules()
+        .map_err(|e| format!("Failed to get ignore rules: {e}"))?;
+    let known: HashSet<String> = db
+        .get_all_file_metadata()
+        .map_err(|e| format!("Database error: {e}"))?
+        .into_iter()
+        .map(|(path, _, _)| path)
+        .collect();
+    drop(db);
+
+    let scanner = ImageScanner::new(
+        IgnoreFilter::from_rules(&rules, chrono::Utc::now().timestamp()).with_root(&directory),
+    );
+    let files = scanner.scan_directory_with_progress(&directory, |_, _| {})?;
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let mut restored = 0;
+    for file in files.iter().filter(|f| !known.contains(&f.path)) {
+        db.upsert_file_metadata(&file.path, file.modified_time, file.file_size)
+            .map_err(|e| format!("Database error: {e}"))?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// 前回の確認から期限（スヌーズ）が来たルールがあれば、除外していた写真をプレイリストに戻す
+///
+/// スライドショーが進むたびに呼ぶ。期限切れのルールは行を残したまま `IgnoreFilter::from_rules` が読み飛ばすので、
+/// ここでは `file_metadata` に当て直すだけ。スライドの切り替えを待たせないよう別スレッドで行う
+/// （`file_metadata` にまだないファイルは次の差分スキャンで拾う）。期限が来ていなければ DB を1回問い合わせるだけ。
+pub(crate) fn release_expired_snoozes(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let since = std::mem::replace(
+        &mut *state
+            .snoozes_checked_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()),
+        now,
+    );
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    let expired = db
+        .count_ignore_rules_expired_between(since, now)
+        .map_err(|e| format!("Failed to get expired ignore rules: {e}"))?;
+    drop(db);
+
+    if expired > 0 {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = reapply_rules_to_playlist(&app.state::<AppState>()) {
+                eprintln!("Failed to release expired snoozes: {e}");
+            }
+        });
+    }
+    Ok(())
+}
@@ -6,7 +6,7 @@ pub fn exit_app(app: AppHandle) {
     app.exit(0);
 }
 
-/// すべての設定とデータを初期化（データベースとキャッシュを削除）
+/// すべての設定とデータを初期化（データベース・キャッシュ・サムネイルを削除）
 #[tauri::command]
 pub async fn reset_all_data(app: AppHandle) -> Result<(), String> {
     // データベースファイルのパスを取得
@@ -17,6 +17,8 @@ pub async fn reset_all_data(app: AppHandle) -> Result<(), String> {
 
     let db_path = app_data_dir.join("sss.db");
     let cache_dir = app_data_dir.join("cache");
+    let thumbnail_dir = app_data_dir.join("thumbnails");
+    let trash_dir = app_data_dir.join("trash");
 
     // データベースファイルを削除
     if db_path.exists() {
@@ -29,5 +31,17 @@ pub async fn reset_all_data(app: AppHandle) -> Result<(), String> {
             .map_err(|e| format!("Failed to delete cache directory: {e}"))?;
     }
 
+    // サムネイルディレクトリを削除
+    if thumbnail_dir.exists() {
+        std::fs::remove_dir_all(&thumbnail_dir)
+            .map_err(|e| format!("Failed to delete thumbnail directory: {e}"))?;
+    }
+
+    // 取り消し用に退避したピックを削除（履歴ごと消えるので戻せなくなる）
+    if trash_dir.exists() {
+        std::fs::remove_dir_all(&trash_dir)
+            .map_err(|e| format!("Failed to delete trash directory: {e}"))?;
+    }
+
     Ok(())
 }
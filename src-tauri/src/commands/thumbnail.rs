@@ -0,0 +1,395 @@
+use crate::commands::file_operations::{
+    get_picked_directory, list_picked_images, load_ignore_filter,
+};
+use crate::commands::types::{AppState, ThumbnailItem, ThumbnailPage};
+use crate::image_processor::{
+    generate_thumbnail, is_video_file, ThumbnailMode, DEFAULT_THUMBNAIL_SIZE,
+};
+use crate::path_norm::canonical_str;
+use crate::playlist::Playlist;
+use crate::poster::{extract_poster_frame, find_ffmpeg, temp_output_path};
+use crate::video_metadata::read_video_metadata;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// 1ページあたりの最大件数
+const MAX_PAGE_SIZE: usize = 200;
+
+/// サムネイルキャッシュの上限（超えたら使われていない順に消す）
+pub const THUMBNAIL_CACHE_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 使ったサムネイルの更新日時を今にし直す間隔（ヒットのたびに書き込まないため）
+const TOUCH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// サムネイルを永続キャッシュから取得し、なければ生成して保存する
+/// キャッシュキーに mtime を含めるので、元画像が更新されれば自動的に作り直される
+/// 動画はポスターフレームから作る（ffmpeg がなければ None）
+pub fn ensure_thumbnail(
+    image_path: &str,
+    thumbnail_dir: &Path,
+    size: u32,
+    mode: ThumbnailMode,
+    apply_rotation: bool,
+) -> Option<PathBuf> {
+    let path = Path::new(image_path);
+    let is_video = is_video_file(path);
+
+    if is_video && find_ffmpeg().is_none() {
+        return None;
+    }
+
+    let modified_time = fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+
+    // 末尾の "srgb" は ICC 変換導入前に作られたサムネイルと区別するため
+    let hash = format!(
+        "{:x}",
+        md5::compute(format!(
+            "{image_path}:{modified_time}:{size}:{mode:?}:{apply_rotation}:srgb"
+        ))
+    );
+    let thumbnail_file = thumbnail_dir.join(format!("{hash}.jpg"));
+
+    if let Ok(metadata) = fs::metadata(&thumbnail_file) {
+        mark_used(&thumbnail_file, &metadata);
+        return Some(thumbnail_file);
+    }
+
+    let result = if is_video {
+        video_thumbnail(
+            path,
+            &thumbnail_dir.join(format!("{hash}.poster.jpg")),
+            size,
+            mode,
+        )
+    } else {
+        generate_thumbnail(path, size, mode, apply_rotation)
+    };
+
+    match result {
+        Ok(data) => {
+            // 並列に作っている別のスレッドが書きかけを読まないよう、一時ファイルに書いてから置き換える
+            let temp_file = temp_output_path(&thumbnail_file);
+            if let Err(e) =
+                fs::write(&temp_file, data).and_then(|()| fs::rename(&temp_file, &thumbnail_file))
+            {
+                eprintln!("Failed to write thumbnail: {e}");
+                let _ = fs::remove_file(&temp_file);
+                return None;
+            }
+            Some(thumbnail_file)
+        }
+        Err(e) => {
+            eprintln!("Failed to generate thumbnail: {e}");
+            None
+        }
+    }
+}
+
+/// 使ったサムネイルの更新日時を今にする（整理のときに使われていない順を判断するため。1日に1回まで）
+fn mark_used(thumbnail_file: &Path, metadata: &fs::Metadata) {
+    let now = SystemTime::now();
+    let stale = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age > TOUCH_INTERVAL);
+    if stale {
+        if let Ok(file) = fs::File::options().write(true).open(thumbnail_file) {
+            let _ = file.set_modified(now);
+        }
+    }
+}
+
+/// サムネイルキャッシュを整理する（起動時にバックグラウンドで呼ぶ）
+///
+/// キーに mtime を含むので、元画像を更新・削除した分は二度と使われずに残る。
+/// 合計が limit を超えていたら、更新日時（最後に使った日）の古い順に limit の 9 割まで消す。
+/// 前回の実行で書きかけのまま残った一時ファイルも消す。
+pub fn prune_thumbnail_cache(thumbnail_dir: &Path, limit: u64) {
+    let Ok(entries) = fs::read_dir(thumbnail_dir) else {
+        return;
+    };
+    let started = SystemTime::now();
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        if path.to_string_lossy().ends_with(".part.jpg") {
+            // いまの実行で書いている途中のものは残す
+            if modified + Duration::from_secs(60 * 60) < started {
+                let _ = fs::remove_file(&path);
+            }
+            continue;
+        }
+        files.push((modified, metadata.len(), path));
+    }
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= limit {
+        return;
+    }
+    files.sort_by_key(|(modified, _, _)| *modified);
+    let target = limit / 10 * 9;
+    for (_, len, path) in files {
+        if total <= target {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+/// 動画のポスターフレームを一時ファイルに切り出してサムネイルにする
+/// 回転は ffmpeg がポスター切り出し時に適用済み
+fn video_thumbnail(
+    video_path: &Path,
+    poster_file: &Path,
+    size: u32,
+    mode: ThumbnailMode,
+) -> Result<Vec<u8>, String> {
+    let duration_ms = read_video_metadata(video_path).and_then(|m| m.duration_ms);
+    let poster_file = temp_output_path(poster_file);
+    let result = extract_poster_frame(video_path, &poster_file, duration_ms)
+        .and_then(|()| generate_thumbnail(&poster_file, size, mode, false));
+    let _ = fs::remove_file(&poster_file);
+    result
+}
+
+/// サムネイルグリッドを1ページ分取得
+/// source: "library"（ライブラリ全体）, "folder"（folder 配下）, "recent"（最近表示）, "picked"（ピック済み）
+#[tauri::command]
+pub async fn get_thumbnail_grid(
+    source: String,
+    folder: Option<String>,
+    page: usize,
+    page_size: usize,
+    size: Option<u32>,
+    mode: Option<ThumbnailMode>,
+    state: State<'_, AppState>,
+) -> Result<ThumbnailPage, String> {
+    let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+    let offset = page
+        .checked_mul(page_size)
+        .ok_or("Page number is out of range")?;
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+
+    // apply_exif_rotation 設定を取得（デフォルト true）
+    let apply_rotation = db
+        .get_setting("apply_exif_rotation")
+        .ok()
+        .flatten()
+        .map(|v| v != "false")
+        .unwrap_or(true);
+
+    let (paths, total) = match source.as_str() {
+        "library" => {
+            drop(db);
+            paginate(&sorted_playlist(&state), offset, page_size)
+        }
+        "folder" => {
+            drop(db);
+            let folder = folder.ok_or("Folder path is required")?;
+            // プレイリストのパスと同じ正規形にし、フォルダ名の前方一致で兄弟フォルダ（photos と photos2 等）を
+            // 拾わないよう `/` で終える
+            let folder = canonical_str(&folder);
+            let prefix = if folder.ends_with('/') {
+                folder
+            } else {
+                format!("{folder}/")
+            };
+            paginate(
+                under_folder(&sorted_playlist(&state), &prefix),
+                offset,
+                page_size,
+            )
+        }
+        "recent" => {
+            // get_recent_images と同じく除外ルール適用後の最新100件を対象にする
+            let mut ignore_filter = load_ignore_filter(&db)?;
+            let recent: Vec<String> = db
+                .get_recent_images(500)
+                .map_err(|e| format!("Failed to get recent images: {e}"))?
+                .into_iter()
+                .map(|(path, _, _)| path)
+                .filter(|path| {
+                    ignore_filter.load_ignore_files_along(Path::new(path));
+                    !ignore_filter.is_ignored(Path::new(path))
+                })
+                .take(100)
+                .collect();
+            drop(db);
+            paginate(&recent, offset, page_size)
+        }
+        "picked" => {
+            let picked_dir = get_picked_directory(&db)?;
+            drop(db);
+            paginate(&list_picked_images(&picked_dir)?, offset, page_size)
+        }
+        _ => return Err("Invalid thumbnail source".to_string()),
+    };
+
+    let size = size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+    let mode = mode.unwrap_or(ThumbnailMode::Square);
+    let thumbnail_dir = &state.thumbnail_dir;
+
+    // ページ内のサムネイルを並列に用意（キャッシュ済みなら存在確認のみ）
+    let items = paths
+        .into_par_iter()
+        .map(|path| {
+            let thumbnail_path = ensure_thumbnail(&path, thumbnail_dir, size, mode, apply_rotation)
+                .map(|p| p.to_string_lossy().to_string());
+            ThumbnailItem {
+                is_video: is_video_file(Path::new(&path)),
+                path,
+                thumbnail_path,
+            }
+        })
+        .collect();
+
+    Ok(ThumbnailPage {
+        items,
+        total,
+        page,
+        page_size,
+    })
+}
+
+/// プレイリストの画像をパス順に並べた一覧（プレイリストの版が変わっていなければ前回のものを使う）
+///
+/// プレイリストは除外ルール・絞り込み設定・メタデータの除外ルールを適用済みなので、ページごとに
+/// ライブラリ全体へルールをかけ直さずに済む。プレイリストがまだなければ空。
+fn sorted_playlist(state: &AppState) -> Arc<Vec<String>> {
+    let playlist_lock = state.playlist.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(playlist) = playlist_lock.as_ref() else {
+        return Arc::default();
+    };
+    let mut cache = state.grid_paths.lock().unwrap_or_else(|e| e.into_inner());
+    sorted_images(playlist, &mut cache)
+}
+
+/// sorted_playlist の本体（cache はプレイリストの版と並べた一覧）
+fn sorted_images(
+    playlist: &Playlist,
+    cache: &mut Option<(u64, Arc<Vec<String>>)>,
+) -> Arc<Vec<String>> {
+    if let Some((generation, paths)) = cache.as_ref() {
+        if *generation == playlist.generation() {
+            return Arc::clone(paths);
+        }
+    }
+    let mut paths = playlist.images().to_vec();
+    paths.sort_unstable();
+    let paths = Arc::new(paths);
+    *cache = Some((playlist.generation(), Arc::clone(&paths)));
+    paths
+}
+
+/// パス順の一覧から prefix（`/` で終わるフォルダ）配下の範囲を二分探索で切り出す
+fn under_folder<'a>(sorted: &'a [String], prefix: &str) -> &'a [String] {
+    let start = sorted.partition_point(|path| path.as_str() < prefix);
+    let len = sorted[start..].partition_point(|path| path.starts_with(prefix));
+    &sorted[start..start + len]
+}
+
+/// 一覧から1ページ分を切り出す（戻り値: (ページ内のパス, 総数)）
+fn paginate(paths: &[String], offset: usize, page_size: usize) -> (Vec<String>, usize) {
+    let page = paths.iter().skip(offset).take(page_size).cloned().collect();
+    (page, paths.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_pages_follow_playlist() {
+        let images: Vec<String> = ["b/2.jpg", "a/1.jpg", "a2/1.jpg", "a/3.jpg", "a/2.jpg"]
+            .iter()
+            .map(|name| format!("/photos/{name}"))
+            .collect();
+        let mut playlist = Playlist::new(images);
+        let mut cache = None;
+
+        // ライブラリ全体はパス順にページを切り、同じ版ならキャッシュを使い回す
+        let sorted = sorted_images(&playlist, &mut cache);
+        assert_eq!(
+            paginate(&sorted, 2, 2),
+            (
+                vec![
+                    "/photos/a/3.jpg".to_string(),
+                    "/photos/a2/1.jpg".to_string()
+                ],
+                5
+            )
+        );
+        assert!(Arc::ptr_eq(&sorted, &sorted_images(&playlist, &mut cache)));
+        assert_eq!(paginate(&sorted, 10, 2), (vec![], 5));
+
+        // フォルダは兄弟フォルダ（a2）を含めない
+        let (page, total) = paginate(under_folder(&sorted, "/photos/a/"), 0, 2);
+        assert_eq!(page, ["/photos/a/1.jpg", "/photos/a/2.jpg"]);
+        assert_eq!(total, 3);
+        assert!(under_folder(&sorted, "/photos/c/").is_empty());
+
+        // 除外してプレイリストから外れた写真は、次のページ取得からグリッドにも出ない
+        playlist.update_images(vec![], vec!["/photos/a/2.jpg".to_string()]);
+        let sorted = sorted_images(&playlist, &mut cache);
+        assert_eq!(
+            under_folder(&sorted, "/photos/a/"),
+            ["/photos/a/1.jpg", "/photos/a/3.jpg"]
+        );
+        assert_eq!(sorted.len(), 4);
+    }
+
+    #[test]
+    fn test_prune_thumbnail_cache() {
+        let dir = std::env::temp_dir().join(format!("sss_thumbnail_{}_prune", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        let write = |name: &str, days_ago: u64| {
+            let path = dir.join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(days_ago * 24 * 60 * 60))
+                .unwrap();
+        };
+        write("old.jpg", 30);
+        write("used.jpg", 10);
+        write("recent.jpg", 0);
+        write("left.jpg.1-0.part.jpg", 1);
+        write("writing.jpg.2-0.part.jpg", 0);
+
+        // 上限内なら書きかけの残骸だけを消す
+        prune_thumbnail_cache(&dir, 1000);
+        assert!(!dir.join("left.jpg.1-0.part.jpg").exists());
+        assert!(dir.join("writing.jpg.2-0.part.jpg").exists());
+        assert!(dir.join("old.jpg").exists());
+
+        // 超えたら使われていない順に消す
+        prune_thumbnail_cache(&dir, 150);
+        assert!(!dir.join("old.jpg").exists());
+        assert!(!dir.join("used.jpg").exists());
+        assert!(dir.join("recent.jpg").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
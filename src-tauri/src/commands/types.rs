@@ -1,15 +1,20 @@
 use crate::database::Database;
+use crate::ignore::PatternError;
 use crate::playlist::Playlist;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 /// アプリケーション状態
 pub struct AppState {
-    pub db: Mutex<Database>,
+    pub db: Arc<Mutex<Database>>, // バックグラウンドの画像処理スレッドとも共有する
     pub playlist: Mutex<Option<Playlist>>,
     pub directory_path: Mutex<Option<PathBuf>>,
     pub cache_dir: PathBuf,
+    pub thumbnail_dir: PathBuf,
+    pub trash_dir: PathBuf, // 取り消せるように削除したピックを退避しておく場所
+    pub snoozes_checked_at: Mutex<i64>, // 期限切れのスヌーズを最後に確かめた時刻（UNIX 秒）
+    pub grid_paths: Mutex<Option<(u64, Arc<Vec<String>>)>>, // プレイリストの版とパス順に並べた画像（サムネイルグリッド用）
     pub _keep_awake: keepawake::AwakeHandle,
 }
 
@@ -30,3 +35,38 @@ pub struct Stats {
     pub total_images: i32,
     pub displayed_images: i32,
 }
+
+/// サムネイルグリッドの1件
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailItem {
+    pub path: String,
+    pub thumbnail_path: Option<String>, // 生成できなかった場合（動画・破損ファイル）は None
+    pub is_video: bool,
+}
+
+/// サムネイルグリッドの1ページ
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailPage {
+    pub items: Vec<ThumbnailItem>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// 除外ルールを手動追加できなかった理由（add_ignore_pattern 用）
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AddPatternError {
+    /// パターンとして読めない（入力中の確認と同じく位置と理由を返す）
+    Invalid { error: PatternError },
+    /// それ以外（スヌーズの期限が過ぎている・DB エラーなど）
+    Failed { message: String },
+}
+
+impl From<String> for AddPatternError {
+    fn from(message: String) -> Self {
+        AddPatternError::Failed { message }
+    }
+}
@@ -1,6 +1,32 @@
-use rusqlite::{Connection, Result};
+use crate::capture_date::{CaptureDate, CaptureDateSource};
+use crate::geocode::{Location, DATASET_VERSION};
+use crate::gpx::{GpxTrack, GpxTrackSummary, InferredLocation, TrackPoint};
+use crate::ignore::{IgnoreRule, RuleSource};
+use crate::image_processor::is_video_file;
+use crate::journal::{JournalAction, JournalEntry};
+use crate::metadata_rule::{MediaFacts, MetadataRule, MetadataRuleEntry};
+use crate::path_norm::{canonical_str, looks_like_windows_path};
+use crate::video_metadata::VideoMetadata;
+use crate::xmp::XmpMetadata;
+use rusqlite::{params, Connection, Result};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// 保存しているパスの形式（app_settings のキーと現在の版）
+const PATH_FORMAT_KEY: &str = "path_format";
+const PATH_FORMAT_VERSION: &str = "1";
+
+/// パス書き換えで書き換え先の行がすでにあったときの扱い
+#[derive(Clone, Copy)]
+enum PathConflict {
+    /// 書き換え先の行を残す
+    KeepExisting,
+    /// `modified_time` の新しい方を残す
+    KeepNewer,
+    /// 表示回数を合算する（image_stats）
+    MergeStats,
+}
+
 pub struct Database {
     conn: Connection,
 }
@@ -27,6 +53,127 @@ impl Database {
             [],
         )?;
 
+        // 画像寸法キャッシュ（ヘッダから読んだ表示上の寸法。modified_time が変わったら読み直す）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS image_dimensions (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // カメラのメーカー・機種（スキャン時に新規・変更された画像の EXIF から読む。メタデータの除外ルール用）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS camera_info (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                make TEXT,
+                model TEXT
+            )",
+            [],
+        )?;
+
+        // 注目点キャッシュ（0.0〜1.0 の相対座標。modified_time が変わったら解析し直す）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS image_focal_points (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                focal_x REAL NOT NULL,
+                focal_y REAL NOT NULL
+            )",
+            [],
+        )?;
+
+        // 動画メタデータ（スキャン時に新規・変更された動画から読み取る）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS video_metadata (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                duration_ms INTEGER,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                rotation INTEGER NOT NULL,
+                creation_time TEXT
+            )",
+            [],
+        )?;
+
+        // 撮影日時（EXIF → ファイル名 → フォルダ名 → サイドカー → 更新日時 の順に解決した結果と出どころ）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS capture_dates (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                date_time TEXT NOT NULL,
+                source TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // XMP / IPTC メタデータ（modified_time は画像とサイドカーのうち新しい方。keywords は JSON 配列）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS xmp_metadata (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                rating INTEGER,
+                keywords TEXT NOT NULL,
+                caption TEXT,
+                label TEXT
+            )",
+            [],
+        )?;
+
+        // GPS 座標から引いた地名（dataset は引いたときの地名データの版。city が NULL なら近くに地名なし）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS geocodes (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                dataset INTEGER NOT NULL,
+                city TEXT,
+                region TEXT,
+                country TEXT
+            )",
+            [],
+        )?;
+
+        // 取り込んだ GPX トラックログ（同じファイルを取り込み直したら置き換える）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS gpx_tracks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL UNIQUE,
+                name TEXT,
+                imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        // トラックポイント（time は UTC の UNIX ミリ秒）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS gpx_points (
+                track_id INTEGER NOT NULL,
+                time INTEGER NOT NULL,
+                latitude REAL NOT NULL,
+                longitude REAL NOT NULL,
+                elevation REAL
+            )",
+            [],
+        )?;
+
+        // トラックログから推定した撮影地（元の EXIF とは別に持つ。latitude が NULL なら該当なし。
+        // clock_offset は推定時のカメラ時計の補正秒数。トラックを増減したら全件消して推定し直す）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS inferred_locations (
+                path TEXT PRIMARY KEY,
+                modified_time INTEGER NOT NULL,
+                clock_offset INTEGER NOT NULL,
+                latitude REAL,
+                longitude REAL,
+                altitude REAL
+            )",
+            [],
+        )?;
+
         // 画像統計情報
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS image_stats (
@@ -54,11 +201,36 @@ impl Database {
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS ignore_rules (
                 pattern TEXT PRIMARY KEY,
+                added_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                expires_at INTEGER,
+                source TEXT,
+                label TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )?;
+
+        // メタデータの除外ルール（rule は MetadataRule の JSON）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS metadata_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule TEXT NOT NULL,
                 added_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )",
             [],
         )?;
 
+        // 取り消しのための操作履歴（action は JournalAction の JSON、undone は取り消し済み）
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS action_journal (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action TEXT NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
         // スキャン履歴
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS scan_history (
@@ -88,6 +260,17 @@ impl Database {
             "CREATE INDEX IF NOT EXISTS idx_modified_time ON file_metadata(modified_time)",
             [],
         )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_capture_date ON capture_dates(date_time)",
+            [],
+        )?;
+        // 前後の点はトラックごとに探すので (track_id, time)。時刻だけの旧インデックスは使わない
+        self.conn
+            .execute("DROP INDEX IF EXISTS idx_gpx_point_time", [])?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_gpx_point_track_time ON gpx_points(track_id, time)",
+            [],
+        )?;
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_display_count ON image_stats(display_count)",
             [],
@@ -122,6 +305,32 @@ impl Database {
                 .execute("ALTER TABLE file_metadata DROP COLUMN is_valid", [])?;
         }
 
+        // 旧スキーマからのマイグレーション: ignore_rules に期限（スヌーズ）・出どころ・ラベル・有効フラグのカラムを足す
+        for (column, definition) in [
+            ("expires_at", "INTEGER"),
+            ("source", "TEXT"),
+            ("label", "TEXT"),
+            ("enabled", "INTEGER NOT NULL DEFAULT 1"),
+        ] {
+            let exists: bool = self
+                .conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('ignore_rules') WHERE name = ?1",
+                    [column],
+                    |row| row.get::<_, i32>(0),
+                )
+                .unwrap_or(0)
+                > 0;
+            if !exists {
+                self.conn.execute(
+                    &format!("ALTER TABLE ignore_rules ADD COLUMN {column} {definition}"),
+                    [],
+                )?;
+            }
+        }
+
+        self.migrate_path_format()?;
+
         // ignore_rules が空の場合のみデフォルト除外ルールを挿入
         let rule_count: i32 = self
             .conn
@@ -136,17 +345,144 @@ impl Database {
                 "**/desktop.ini",
                 "**/.**/",
             ];
-            for rule in &default_rules {
-                self.conn.execute(
-                    "INSERT OR IGNORE INTO ignore_rules (pattern) VALUES (?1)",
-                    [rule],
-                )?;
+            for rule in default_rules {
+                self.add_ignore_rule(&IgnoreRule::new(rule, Some(RuleSource::Default)))?;
             }
         }
 
         Ok(())
     }
 
+    /// 旧形式（OS ごとの区切り文字のまま）で保存したパスを path_norm.rs の正規形に書き換える（初回のみ）
+    ///
+    /// 書き換え先の行がすでにあれば、キャッシュ系のテーブルは `modified_time` の新しい行を、
+    /// `image_stats` は表示回数を合算し最終表示日時の遅い方をとって 1 行にまとめる。除外ルールは `C:\...` のような Windows 形式の
+    /// 絶対パスだけを書き換える（glob の末尾 `/` などは意味があるので触らない）。
+    fn migrate_path_format(&self) -> Result<()> {
+        if self.get_setting(PATH_FORMAT_KEY)?.as_deref() == Some(PATH_FORMAT_VERSION) {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let rename = |table: &str,
+                      column: &str,
+                      conflict: PathConflict,
+                      canonical: fn(&str) -> Option<String>| {
+            let mut stmt = tx.prepare(&format!("SELECT {column} FROM {table}"))?;
+            let values = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>>>()?;
+            for value in values {
+                let Some(new_value) = canonical(&value).filter(|new_value| *new_value != value)
+                else {
+                    continue;
+                };
+                match conflict {
+                    PathConflict::KeepExisting => {}
+                    // 古い方のキャッシュを捨てる（同時刻なら書き換え先を残す）
+                    PathConflict::KeepNewer => {
+                        tx.execute(
+                            &format!(
+                                "DELETE FROM {table} WHERE {column} = ?2 AND modified_time <
+                                 (SELECT modified_time FROM {table} WHERE {column} = ?1)"
+                            ),
+                            [&value, &new_value],
+                        )?;
+                    }
+                    PathConflict::MergeStats => {
+                        tx.execute(
+                            "UPDATE image_stats SET
+                                 display_count = image_stats.display_count + old.display_count,
+                                 last_displayed = CASE
+                                     WHEN image_stats.last_displayed IS NULL
+                                         OR old.last_displayed > image_stats.last_displayed
+                                     THEN old.last_displayed
+                                     ELSE image_stats.last_displayed END,
+                                 created_at = MIN(image_stats.created_at, old.created_at)
+                             FROM (SELECT display_count, last_displayed, created_at
+                                   FROM image_stats WHERE path = ?1) AS old
+                             WHERE image_stats.path = ?2",
+                            [&value, &new_value],
+                        )?;
+                    }
+                }
+                tx.execute(
+                    &format!("UPDATE OR IGNORE {table} SET {column} = ?2 WHERE {column} = ?1"),
+                    [&value, &new_value],
+                )?;
+                // gpx_points が id で参照しているので、トラックは重複しても消さない
+                if table != "gpx_tracks" {
+                    tx.execute(
+                        &format!("DELETE FROM {table} WHERE {column} = ?1"),
+                        [&value],
+                    )?;
+                }
+            }
+            Ok::<_, rusqlite::Error>(())
+        };
+
+        for table in [
+            "file_metadata",
+            "image_dimensions",
+            "camera_info",
+            "image_focal_points",
+            "video_metadata",
+            "capture_dates",
+            "xmp_metadata",
+            "geocodes",
+            "inferred_locations",
+        ] {
+            rename(table, "path", PathConflict::KeepNewer, |path| {
+                Some(canonical_str(path))
+            })?;
+        }
+        rename("gpx_tracks", "path", PathConflict::KeepExisting, |path| {
+            Some(canonical_str(path))
+        })?;
+        rename("image_stats", "path", PathConflict::MergeStats, |path| {
+            Some(canonical_str(path))
+        })?;
+        rename(
+            "scan_history",
+            "directory_path",
+            PathConflict::KeepExisting,
+            |path| Some(canonical_str(path)),
+        )?;
+        rename(
+            "ignore_rules",
+            "pattern",
+            PathConflict::KeepExisting,
+            |pattern| {
+                looks_like_windows_path(pattern.trim()).then(|| canonical_str(pattern.trim()))
+            },
+        )?;
+        // ライブラリごとの階調変換（`tone_mapping:{ライブラリのパス}`）のキーも正規形にする
+        rename("app_settings", "key", PathConflict::KeepExisting, |key| {
+            key.strip_prefix("tone_mapping:")
+                .map(|path| format!("tone_mapping:{}", canonical_str(path)))
+        })?;
+        for key in ["last_directory_path", "share_directory_path"] {
+            let value: Option<String> = tx
+                .query_row(
+                    "SELECT value FROM app_settings WHERE key = ?1",
+                    [key],
+                    |row| row.get(0),
+                )
+                .ok();
+            if let Some(value) = value {
+                tx.execute(
+                    "UPDATE app_settings SET value = ?2 WHERE key = ?1",
+                    [key, &canonical_str(&value)],
+                )?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)",
+            [PATH_FORMAT_KEY, PATH_FORMAT_VERSION],
+        )?;
+        tx.commit()
+    }
+
     /// ファイルメタデータを挿入または更新
     pub fn upsert_file_metadata(
         &self,
@@ -182,11 +518,529 @@ impl Database {
         for path in paths {
             tx.execute("DELETE FROM file_metadata WHERE path = ?1", [path])?;
             tx.execute("DELETE FROM image_stats WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM image_dimensions WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM camera_info WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM image_focal_points WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM video_metadata WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM capture_dates WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM xmp_metadata WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM geocodes WHERE path = ?1", [path])?;
+            tx.execute("DELETE FROM inferred_locations WHERE path = ?1", [path])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// キャッシュ済みの画像寸法を取得（modified_time が一致しない場合は None）
+    pub fn get_image_dimensions(
+        &self,
+        path: &str,
+        modified_time: i64,
+    ) -> Result<Option<(u32, u32)>> {
+        let result = self.conn.query_row(
+            "SELECT width, height FROM image_dimensions WHERE path = ?1 AND modified_time = ?2",
+            params![path, modified_time],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+
+        match result {
+            Ok(dimensions) => Ok(Some(dimensions)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 画像寸法をキャッシュに保存
+    pub fn upsert_image_dimensions(
+        &self,
+        path: &str,
+        modified_time: i64,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO image_dimensions (path, modified_time, width, height)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![path, modified_time, width, height],
+        )?;
+        Ok(())
+    }
+
+    /// カメラのメーカー・機種を保存
+    pub fn upsert_camera_info(
+        &self,
+        path: &str,
+        modified_time: i64,
+        make: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO camera_info (path, modified_time, make, model)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![path, modified_time, make, model],
+        )?;
+        Ok(())
+    }
+
+    /// カメラ情報を読み取り済みのファイルと、読み取り時の modified_time（スキャン時の差分判定用）
+    pub fn get_camera_info_modified_times(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified_time FROM camera_info")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// メタデータの除外ルールの判定に使う索引を全ファイル分まとめて取得
+    /// （ファイルの modified_time と一致しない古い行は使わない）
+    pub fn get_all_media_facts(&self) -> Result<HashMap<String, MediaFacts>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.path, f.file_size, c.date_time, k.make, k.model,
+                    COALESCE(v.width, d.width), COALESCE(v.height, d.height)
+             FROM file_metadata f
+             LEFT JOIN capture_dates c ON c.path = f.path AND c.modified_time = f.modified_time
+             LEFT JOIN camera_info k ON k.path = f.path AND k.modified_time = f.modified_time
+             LEFT JOIN image_dimensions d ON d.path = f.path AND d.modified_time = f.modified_time
+             LEFT JOIN video_metadata v ON v.path = f.path AND v.modified_time = f.modified_time",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let path: String = row.get(0)?;
+            let date_time: Option<String> = row.get(2)?;
+            let facts = MediaFacts {
+                is_video: is_video_file(std::path::Path::new(&path)),
+                file_size: row.get::<_, i64>(1)?.max(0) as u64,
+                capture_date: date_time
+                    .map(|date_time| date_time.split(' ').next().unwrap_or_default().to_string()),
+                camera_make: row.get(3)?,
+                camera_model: row.get(4)?,
+                width: row.get(5)?,
+                height: row.get(6)?,
+            };
+            Ok((path, facts))
+        })?;
+        rows.collect()
+    }
+
+    /// キャッシュ済みの注目点を取得（modified_time が一致しない場合は None）
+    pub fn get_focal_point(&self, path: &str, modified_time: i64) -> Result<Option<(f32, f32)>> {
+        let result = self.conn.query_row(
+            "SELECT focal_x, focal_y FROM image_focal_points WHERE path = ?1 AND modified_time = ?2",
+            params![path, modified_time],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+
+        match result {
+            Ok(point) => Ok(Some(point)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 注目点をキャッシュに保存
+    pub fn upsert_focal_point(
+        &self,
+        path: &str,
+        modified_time: i64,
+        focal_x: f32,
+        focal_y: f32,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO image_focal_points (path, modified_time, focal_x, focal_y)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![path, modified_time, focal_x, focal_y],
+        )?;
+        Ok(())
+    }
+
+    /// 保存済みの動画メタデータを取得（modified_time が一致しない場合は None）
+    pub fn get_video_metadata(
+        &self,
+        path: &str,
+        modified_time: i64,
+    ) -> Result<Option<VideoMetadata>> {
+        let result = self.conn.query_row(
+            "SELECT duration_ms, width, height, rotation, creation_time
+             FROM video_metadata WHERE path = ?1 AND modified_time = ?2",
+            params![path, modified_time],
+            |row| {
+                Ok(VideoMetadata {
+                    duration_ms: row.get::<_, Option<i64>>(0)?.map(|d| d as u64),
+                    width: row.get(1)?,
+                    height: row.get(2)?,
+                    rotation: row.get(3)?,
+                    creation_time: row.get(4)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 動画メタデータを保存
+    pub fn upsert_video_metadata(
+        &self,
+        path: &str,
+        modified_time: i64,
+        metadata: &VideoMetadata,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO video_metadata
+             (path, modified_time, duration_ms, width, height, rotation, creation_time)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                path,
+                modified_time,
+                metadata.duration_ms.map(|d| d as i64),
+                metadata.width,
+                metadata.height,
+                metadata.rotation,
+                metadata.creation_time,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 保存済みの撮影日時を取得（modified_time が一致しない場合は None）
+    pub fn get_capture_date(&self, path: &str, modified_time: i64) -> Result<Option<CaptureDate>> {
+        let result = self.conn.query_row(
+            "SELECT date_time, source FROM capture_dates WHERE path = ?1 AND modified_time = ?2",
+            params![path, modified_time],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        );
+
+        match result {
+            Ok((date_time, source)) => {
+                Ok(CaptureDateSource::parse(&source)
+                    .map(|source| CaptureDate { date_time, source }))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 撮影日時を保存
+    pub fn upsert_capture_date(
+        &self,
+        path: &str,
+        modified_time: i64,
+        capture_date: &CaptureDate,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO capture_dates (path, modified_time, date_time, source)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                path,
+                modified_time,
+                capture_date.date_time,
+                capture_date.source.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 撮影日時を解決済みのファイルと、解決時の modified_time（スキャン時の差分判定用）
+    pub fn get_capture_date_modified_times(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified_time FROM capture_dates")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 保存済みの XMP メタデータを取得（modified_time が一致しない場合は None）
+    pub fn get_xmp_metadata(&self, path: &str, modified_time: i64) -> Result<Option<XmpMetadata>> {
+        let result = self.conn.query_row(
+            "SELECT rating, keywords, caption, label
+             FROM xmp_metadata WHERE path = ?1 AND modified_time = ?2",
+            params![path, modified_time],
+            |row| xmp_metadata_from_row(row, 0),
+        );
+
+        match result {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
         }
+    }
+
+    /// XMP メタデータを保存（メタデータのない画像も空の行として保存し、読み直しを避ける）
+    pub fn upsert_xmp_metadata(
+        &self,
+        path: &str,
+        modified_time: i64,
+        metadata: &XmpMetadata,
+    ) -> Result<()> {
+        let keywords = serde_json::to_string(&metadata.keywords).unwrap_or_else(|_| "[]".into());
+        self.conn.execute(
+            "INSERT OR REPLACE INTO xmp_metadata
+             (path, modified_time, rating, keywords, caption, label)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                path,
+                modified_time,
+                metadata.rating,
+                keywords,
+                metadata.caption,
+                metadata.label,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// XMP メタデータを読み取り済みのファイルと、読み取り時の modified_time（スキャン時の差分判定用）
+    pub fn get_xmp_modified_times(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, modified_time FROM xmp_metadata")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 全ファイルの XMP メタデータ（プレイリストの絞り込み用）
+    pub fn get_all_xmp_metadata(&self) -> Result<HashMap<String, XmpMetadata>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, rating, keywords, caption, label FROM xmp_metadata")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, xmp_metadata_from_row(row, 1)?)))?;
+        rows.collect()
+    }
+
+    /// 保存済みの地名を取得（未保存・modified_time や地名データの版が一致しない場合は None、
+    /// 近くに地名がないと保存されている場合は Some(None)）
+    pub fn get_geocode(&self, path: &str, modified_time: i64) -> Result<Option<Option<Location>>> {
+        let result = self.conn.query_row(
+            "SELECT city, region, country FROM geocodes
+             WHERE path = ?1 AND modified_time = ?2 AND dataset = ?3",
+            params![path, modified_time, *DATASET_VERSION],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        );
+
+        match result {
+            Ok((city, region, country)) => Ok(Some(city.map(|city| Location {
+                city,
+                region,
+                country: country.unwrap_or_default(),
+            }))),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 地名を保存（近くに地名がなかった場合も None として保存し、引き直しを避ける）
+    pub fn upsert_geocode(
+        &self,
+        path: &str,
+        modified_time: i64,
+        location: Option<&Location>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO geocodes (path, modified_time, dataset, city, region, country)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                path,
+                modified_time,
+                *DATASET_VERSION,
+                location.map(|l| &l.city),
+                location.and_then(|l| l.region.as_ref()),
+                location.map(|l| &l.country),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// GPX トラックを保存（同じパスのトラックは置き換え、推定済みの撮影地は破棄する）
+    pub fn insert_gpx_track(&self, path: &str, track: &GpxTrack) -> Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM gpx_points WHERE track_id IN (SELECT id FROM gpx_tracks WHERE path = ?1)",
+            [path],
+        )?;
+        tx.execute("DELETE FROM gpx_tracks WHERE path = ?1", [path])?;
+        tx.execute(
+            "INSERT INTO gpx_tracks (path, name) VALUES (?1, ?2)",
+            params![path, track.name],
+        )?;
+        let track_id = tx.last_insert_rowid();
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO gpx_points (track_id, time, latitude, longitude, elevation)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for point in &track.points {
+                stmt.execute(params![
+                    track_id,
+                    point.time,
+                    point.latitude,
+                    point.longitude,
+                    point.elevation
+                ])?;
+            }
+        }
+        tx.execute("DELETE FROM inferred_locations", [])?;
+        tx.commit()?;
+        Ok(track_id)
+    }
+
+    /// 取り込み済みの GPX トラック一覧（記録の古い順）
+    pub fn get_gpx_tracks(&self) -> Result<Vec<GpxTrackSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.path, t.name, COUNT(p.time), MIN(p.time), MAX(p.time)
+             FROM gpx_tracks t LEFT JOIN gpx_points p ON p.track_id = t.id
+             GROUP BY t.id ORDER BY MIN(p.time)",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(GpxTrackSummary {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                name: row.get(2)?,
+                point_count: row.get(3)?,
+                start_time: row.get(4)?,
+                end_time: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// GPX トラックを削除（推定済みの撮影地は破棄する）
+    pub fn delete_gpx_track(&self, id: i64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM gpx_points WHERE track_id = ?1", [id])?;
+        tx.execute("DELETE FROM gpx_tracks WHERE id = ?1", [id])?;
+        tx.execute("DELETE FROM inferred_locations", [])?;
         tx.commit()?;
         Ok(())
     }
 
+    /// 指定時刻の直前（以前）と直後（以降）のトラックポイント（同じトラックの中から探す）
+    ///
+    /// スマホとロガーのように時間の重なるトラックがあっても、別の機器の点どうしで補間しないよう
+    /// 1本のトラックを選ぶ。時刻をはさむトラックがあればそのうち前後の点の間隔が最も短いもの、
+    /// なければ時刻に最も近い点を持つトラック。
+    pub fn get_gpx_neighbors(&self, time: i64) -> Result<(Option<TrackPoint>, Option<TrackPoint>)> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id,
+                    (SELECT MAX(time) FROM gpx_points p WHERE p.track_id = t.track_id AND p.time <= ?1),
+                    (SELECT MIN(time) FROM gpx_points p WHERE p.track_id = t.track_id AND p.time >= ?1)
+             FROM (SELECT DISTINCT track_id FROM gpx_points) t",
+        )?;
+        let candidates = stmt
+            .query_map([time], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        // (時刻をはさまない, 前後の間隔または最寄りの点までの距離) が小さいトラック
+        let best = candidates
+            .into_iter()
+            .min_by_key(|&(_, before, after)| match (before, after) {
+                (Some(before), Some(after)) => (false, after - before),
+                (Some(before), None) => (true, time - before),
+                (None, Some(after)) => (true, after - time),
+                (None, None) => (true, i64::MAX),
+            });
+        let Some((track_id, before, after)) = best else {
+            return Ok((None, None));
+        };
+
+        let point_at = |point_time: Option<i64>| -> Result<Option<TrackPoint>> {
+            let Some(point_time) = point_time else {
+                return Ok(None);
+            };
+            let result = self.conn.query_row(
+                "SELECT time, latitude, longitude, elevation FROM gpx_points
+                 WHERE track_id = ?1 AND time = ?2 LIMIT 1",
+                [track_id, point_time],
+                |row| {
+                    Ok(TrackPoint {
+                        time: row.get(0)?,
+                        latitude: row.get(1)?,
+                        longitude: row.get(2)?,
+                        elevation: row.get(3)?,
+                    })
+                },
+            );
+            match result {
+                Ok(point) => Ok(Some(point)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+
+        Ok((point_at(before)?, point_at(after)?))
+    }
+
+    /// 推定済みの撮影地を取得（未推定・modified_time や時計の補正が一致しない場合は None、
+    /// 該当するトラックポイントがないと保存されている場合は Some(None)）
+    pub fn get_inferred_location(
+        &self,
+        path: &str,
+        modified_time: i64,
+        clock_offset: i64,
+    ) -> Result<Option<Option<InferredLocation>>> {
+        let result = self.conn.query_row(
+            "SELECT latitude, longitude, altitude FROM inferred_locations
+             WHERE path = ?1 AND modified_time = ?2 AND clock_offset = ?3",
+            params![path, modified_time, clock_offset],
+            |row| {
+                Ok((
+                    row.get::<_, Option<f64>>(0)?,
+                    row.get::<_, Option<f64>>(1)?,
+                    row.get::<_, Option<f64>>(2)?,
+                ))
+            },
+        );
+
+        match result {
+            Ok((latitude, longitude, altitude)) => Ok(Some(latitude.zip(longitude).map(
+                |(latitude, longitude)| InferredLocation {
+                    latitude,
+                    longitude,
+                    altitude,
+                },
+            ))),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 推定した撮影地を保存（該当なしも None として保存し、推定し直しを避ける）
+    pub fn upsert_inferred_location(
+        &self,
+        path: &str,
+        modified_time: i64,
+        clock_offset: i64,
+        location: Option<&InferredLocation>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO inferred_locations
+             (path, modified_time, clock_offset, latitude, longitude, altitude)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                path,
+                modified_time,
+                clock_offset,
+                location.map(|l| l.latitude),
+                location.map(|l| l.longitude),
+                location.and_then(|l| l.altitude),
+            ],
+        )?;
+        Ok(())
+    }
+
     /// 画像の表示回数を増やす
     pub fn increment_display_count(&self, path: &str) -> Result<()> {
         self.conn.execute(
@@ -285,28 +1139,139 @@ impl Database {
         Ok(())
     }
 
-    /// 除外ルール一覧を取得
-    pub fn get_ignore_rules(&self) -> Result<Vec<String>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT pattern FROM ignore_rules ORDER BY added_at ASC")?;
-        let rows = stmt.query_map([], |row| row.get(0))?;
-        let mut patterns = Vec::new();
+    /// 除外ルール一覧を追加順に取得（期限切れのものも含む。判定には `IgnoreFilter::from_rules` を使う）
+    ///
+    /// 後の行が勝つので順序が判定を左右する。`added_at` は秒単位で、既定ルール・移行・読み込みのように
+    /// 同じ秒にまとめて入れた行は並びが決まらないため、挿入順の rowid で確定させる。
+    pub fn get_ignore_rules(&self) -> Result<Vec<IgnoreRule>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pattern, expires_at, added_at, source, label, enabled
+             FROM ignore_rules ORDER BY added_at ASC, rowid ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let source: Option<String> = row.get(3)?;
+            Ok(IgnoreRule {
+                pattern: row.get(0)?,
+                expires_at: row.get(1)?,
+                added_at: row.get(2)?,
+                // 読めない出どころは記録なしとして扱う
+                source: source.and_then(|json| serde_json::from_str(&json).ok()),
+                label: row.get(4)?,
+                enabled: row.get(5)?,
+            })
+        })?;
+        let mut rules = Vec::new();
         for row in rows {
-            patterns.push(row?);
+            rules.push(row?);
         }
-        Ok(patterns)
+        Ok(rules)
+    }
+
+    /// 除外ルールを追加（同じパターンがあれば期限と有効フラグを更新し、ラベルは指定があるときだけ上書き。出どころは最初のまま）
+    ///
+    /// 後のルールが優先されるので、追加し直したルールは末尾に移す（行を置き換えて追加日時を今にする）。
+    pub fn add_ignore_rule(&self, rule: &IgnoreRule) -> Result<()> {
+        let source = rule
+            .source
+            .as_ref()
+            .map(|source| serde_json::to_string(source).unwrap_or_default());
+        self.conn.execute(
+            "INSERT OR REPLACE INTO ignore_rules (pattern, expires_at, source, label, enabled)
+             VALUES (
+                 ?1,
+                 ?2,
+                 COALESCE((SELECT source FROM ignore_rules WHERE pattern = ?1), ?3),
+                 COALESCE(?4, (SELECT label FROM ignore_rules WHERE pattern = ?1)),
+                 ?5
+             )",
+            params![
+                rule.pattern,
+                rule.expires_at,
+                source,
+                rule.label,
+                rule.enabled
+            ],
+        )?;
+        Ok(())
     }
 
-    /// 除外ルールを追加
-    pub fn add_ignore_rule(&self, pattern: &str) -> Result<()> {
+    /// 除外ルールを保存していたときのまま書き戻す（取り消し用。追加日時も戻すので並び順も元に近い位置に戻る）
+    pub fn restore_ignore_rule(&self, rule: &IgnoreRule) -> Result<()> {
+        let source = rule
+            .source
+            .as_ref()
+            .map(|source| serde_json::to_string(source).unwrap_or_default());
         self.conn.execute(
-            "INSERT OR IGNORE INTO ignore_rules (pattern) VALUES (?1)",
-            [pattern],
+            "INSERT OR REPLACE INTO ignore_rules
+                 (pattern, added_at, expires_at, source, label, enabled)
+             VALUES (?1, COALESCE(?2, CURRENT_TIMESTAMP), ?3, ?4, ?5, ?6)",
+            params![
+                rule.pattern,
+                rule.added_at,
+                rule.expires_at,
+                source,
+                rule.label,
+                rule.enabled
+            ],
         )?;
         Ok(())
     }
 
+    /// 除外ルールを止める・再開する（パターンがなければ false）
+    pub fn set_ignore_rule_enabled(&self, pattern: &str, enabled: bool) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE ignore_rules SET enabled = ?2 WHERE pattern = ?1",
+            params![pattern, enabled],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// 除外ルールのラベルを変える（None で消す。パターンがなければ false）
+    pub fn set_ignore_rule_label(&self, pattern: &str, label: Option<&str>) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE ignore_rules SET label = ?2 WHERE pattern = ?1",
+            params![pattern, label],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// ルールファイルから読み込んだ除外ルールを入れる（replace なら既存のルールをすべて消してから）
+    pub fn import_ignore_rules(&self, rules: &[IgnoreRule], replace: bool) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        if replace {
+            tx.execute("DELETE FROM ignore_rules", [])?;
+        }
+        for rule in rules {
+            let source = rule
+                .source
+                .as_ref()
+                .map(|source| serde_json::to_string(source).unwrap_or_default());
+            tx.execute(
+                "INSERT OR IGNORE INTO ignore_rules (pattern, expires_at, source, label, enabled)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    rule.pattern,
+                    rule.expires_at,
+                    source,
+                    rule.label,
+                    rule.enabled
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 期限が since より後、now 以前に来た除外ルールの数（期限切れのルールも出どころ・ラベルごと残す）
+    pub fn count_ignore_rules_expired_between(&self, since: i64, now: i64) -> Result<usize> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM ignore_rules
+             WHERE enabled = 1 AND expires_at > ?1 AND expires_at <= ?2",
+            [since, now],
+            |row| row.get(0),
+        )
+    }
+
     /// 除外ルールを削除
     pub fn remove_ignore_rule(&self, pattern: &str) -> Result<()> {
         self.conn
@@ -314,6 +1279,156 @@ impl Database {
         Ok(())
     }
 
+    /// メタデータの除外ルール一覧を取得（追加順。読めない行は飛ばす）
+    pub fn get_metadata_rules(&self) -> Result<Vec<MetadataRuleEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, rule FROM metadata_rules ORDER BY id ASC")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut rules = Vec::new();
+        for row in rows {
+            let (id, rule) = row?;
+            match serde_json::from_str(&rule) {
+                Ok(rule) => rules.push(MetadataRuleEntry { id, rule }),
+                Err(e) => eprintln!("Skipping unreadable metadata rule {id}: {e}"),
+            }
+        }
+        Ok(rules)
+    }
+
+    /// メタデータの除外ルールを追加（同じ内容のルールがあればその id を返す）
+    pub fn add_metadata_rule(&self, rule: &MetadataRule) -> Result<i64> {
+        let json = serde_json::to_string(rule).unwrap_or_default();
+        let existing = self.conn.query_row(
+            "SELECT id FROM metadata_rules WHERE rule = ?1",
+            [&json],
+            |row| row.get(0),
+        );
+        match existing {
+            Ok(id) => return Ok(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e),
+        }
+        self.conn
+            .execute("INSERT INTO metadata_rules (rule) VALUES (?1)", [&json])?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// メタデータの除外ルールを削除
+    pub fn remove_metadata_rule(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM metadata_rules WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// 操作を履歴に積む
+    ///
+    /// 取り消し済みの操作（やり直し待ち）と、limit 件を超えた古い操作は捨てて返す（退避したファイルの後始末用）。
+    pub fn push_journal_action(
+        &self,
+        action: &JournalAction,
+        limit: i64,
+    ) -> Result<Vec<JournalAction>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut dropped = Vec::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT action FROM action_journal
+                 WHERE undone = 1 OR id NOT IN (
+                     SELECT id FROM action_journal WHERE undone = 0 ORDER BY id DESC LIMIT ?1
+                 )",
+            )?;
+            // 新しく積む1件の分だけ枠を空ける
+            let rows = stmt.query_map([limit - 1], |row| row.get::<_, String>(0))?;
+            for row in rows {
+                if let Ok(action) = serde_json::from_str(&row?) {
+                    dropped.push(action);
+                }
+            }
+        }
+        tx.execute(
+            "DELETE FROM action_journal
+             WHERE undone = 1 OR id NOT IN (
+                 SELECT id FROM action_journal WHERE undone = 0 ORDER BY id DESC LIMIT ?1
+             )",
+            [limit - 1],
+        )?;
+        let json = serde_json::to_string(action).unwrap_or_default();
+        tx.execute("INSERT INTO action_journal (action) VALUES (?1)", [&json])?;
+        tx.commit()?;
+        Ok(dropped)
+    }
+
+    /// 次に取り消す操作（取り消していない最新のもの）
+    pub fn last_undoable_action(&self) -> Result<Option<JournalEntry>> {
+        self.query_journal_entry(
+            "SELECT id, action, created_at FROM action_journal
+             WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+        )
+    }
+
+    /// 次にやり直す操作（取り消し済みのうち最も古いもの = 最後に取り消したもの）
+    pub fn next_redoable_action(&self) -> Result<Option<JournalEntry>> {
+        self.query_journal_entry(
+            "SELECT id, action, created_at FROM action_journal
+             WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+        )
+    }
+
+    fn query_journal_entry(&self, sql: &str) -> Result<Option<JournalEntry>> {
+        let row = self.conn.query_row(sql, [], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        });
+        let (id, action, created_at) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match serde_json::from_str::<JournalAction>(&action) {
+            Ok(action) => Ok(Some(JournalEntry {
+                id,
+                summary: action.summary(),
+                action,
+                created_at,
+            })),
+            Err(e) => {
+                // 読めない操作は戻しようがないので捨てて、その先を見る
+                eprintln!("Dropping unreadable journal entry {id}: {e}");
+                self.conn
+                    .execute("DELETE FROM action_journal WHERE id = ?1", [id])?;
+                self.query_journal_entry(sql)
+            }
+        }
+    }
+
+    /// 操作を履歴から外す
+    pub fn remove_journal_action(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM action_journal WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// 操作を取り消し済み・未取り消しにする（やり直しで振り直した id などを反映するため action も書き直す）
+    pub fn set_journal_action_undone(
+        &self,
+        id: i64,
+        action: &JournalAction,
+        undone: bool,
+    ) -> Result<()> {
+        let json = serde_json::to_string(action).unwrap_or_default();
+        self.conn.execute(
+            "UPDATE action_journal SET action = ?2, undone = ?3 WHERE id = ?1",
+            params![id, json, undone],
+        )?;
+        Ok(())
+    }
+
     /// 最近表示した画像一覧を取得（last_displayed 降順、limit件）
     pub fn get_recent_images(&self, limit: i32) -> Result<Vec<(String, i32, String)>> {
         let mut stmt = self.conn.prepare(
@@ -358,3 +1473,165 @@ impl Database {
         Ok(results)
     }
 }
+
+/// xmp_metadata の (rating, keywords, caption, label) 列を start 列目から読む
+fn xmp_metadata_from_row(row: &rusqlite::Row, start: usize) -> Result<XmpMetadata> {
+    let keywords: String = row.get(start + 1)?;
+    Ok(XmpMetadata {
+        rating: row.get(start)?,
+        keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+        caption: row.get(start + 2)?,
+        label: row.get(start + 3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore::IgnoreFilter;
+    use std::path::Path;
+
+    #[test]
+    fn test_ignore_rules_keep_insertion_order() {
+        let dir = std::env::temp_dir().join(format!("sss_database_{}_order", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.join("sss.db")).unwrap();
+
+        // 1つのトランザクションで同じ秒に入る打ち消しの組（パターン順では `!keep.png` が先になる）
+        let rules = [
+            IgnoreRule::new("*.png", None),
+            IgnoreRule::new("!keep.png", None),
+        ];
+        db.import_ignore_rules(&rules, true).unwrap();
+
+        let stored = db.get_ignore_rules().unwrap();
+        let patterns: Vec<&str> = stored.iter().map(|rule| rule.pattern.as_str()).collect();
+        assert_eq!(patterns, ["*.png", "!keep.png"]);
+
+        let filter = IgnoreFilter::from_rules(&stored, 0).with_root(Path::new("/photos"));
+        assert!(filter.is_ignored(Path::new("/photos/a.png")));
+        assert!(!filter.is_ignored(Path::new("/photos/keep.png")));
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_readded_ignore_rule_moves_to_end() {
+        let dir = std::env::temp_dir().join(format!("sss_database_{}_readd", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.join("sss.db")).unwrap();
+
+        let labeled = IgnoreRule {
+            label: Some("スクショ".to_string()),
+            ..IgnoreRule::new("*.png", Some(RuleSource::Settings))
+        };
+        db.import_ignore_rules(&[labeled, IgnoreRule::new("!keep.png", None)], true)
+            .unwrap();
+        db.conn
+            .execute_batch(
+                "UPDATE ignore_rules SET added_at = '2024-01-01 00:00:00' WHERE pattern = '*.png';
+                 UPDATE ignore_rules SET added_at = '2024-01-01 00:00:01' WHERE pattern = '!keep.png';",
+            )
+            .unwrap();
+        let previous = db.get_ignore_rules().unwrap().remove(0);
+
+        // 追加し直すと `!keep.png` より後になり、keep.png も除外される（出どころ・ラベルは残る）
+        db.add_ignore_rule(&IgnoreRule::new("*.png", None)).unwrap();
+        let stored = db.get_ignore_rules().unwrap();
+        let patterns: Vec<&str> = stored.iter().map(|rule| rule.pattern.as_str()).collect();
+        assert_eq!(patterns, ["!keep.png", "*.png"]);
+        assert_eq!(stored[1].label.as_deref(), Some("スクショ"));
+        assert_eq!(stored[1].source, Some(RuleSource::Settings));
+        let filter = IgnoreFilter::from_rules(&stored, 0).with_root(Path::new("/photos"));
+        assert!(filter.is_ignored(Path::new("/photos/keep.png")));
+
+        // 取り消しで書き戻すと元の位置に戻る
+        db.restore_ignore_rule(&previous).unwrap();
+        let patterns: Vec<String> = db
+            .get_ignore_rules()
+            .unwrap()
+            .into_iter()
+            .map(|rule| rule.pattern)
+            .collect();
+        assert_eq!(patterns, ["*.png", "!keep.png"]);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_expired_snoozes_are_kept() {
+        let dir = std::env::temp_dir().join(format!("sss_database_{}_snooze", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.join("sss.db")).unwrap();
+
+        let snooze = IgnoreRule {
+            expires_at: Some(100),
+            label: Some("旅行".to_string()),
+            ..IgnoreRule::new("trip/", Some(RuleSource::Settings))
+        };
+        db.add_ignore_rule(&snooze).unwrap();
+
+        // 期限の前後で数え、期限が来た行は出どころ・ラベルごと残る（フィルタが読み飛ばす）
+        assert_eq!(db.count_ignore_rules_expired_between(0, 99).unwrap(), 0);
+        assert_eq!(db.count_ignore_rules_expired_between(99, 100).unwrap(), 1);
+        assert_eq!(db.count_ignore_rules_expired_between(100, 200).unwrap(), 0);
+        let stored = db.get_ignore_rules().unwrap();
+        let kept = stored.iter().find(|rule| rule.pattern == "trip/").unwrap();
+        assert_eq!(kept.label.as_deref(), Some("旅行"));
+        assert_eq!(kept.source, Some(RuleSource::Settings));
+        let filter = IgnoreFilter::from_rules(&stored, 100).with_root(Path::new("/photos"));
+        assert!(!filter.is_ignored(Path::new("/photos/trip/a.jpg")));
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_path_migration_merges_colliding_rows() {
+        let dir = std::env::temp_dir().join(format!("sss_database_{}_migrate", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.join("sss.db")).unwrap();
+
+        // 旧形式と正規形の両方に行がある状態を作って移行をやり直す
+        db.conn
+            .execute_batch(
+                "INSERT INTO image_stats (path, display_count, last_displayed) VALUES
+                     ('/photos//a.jpg', 3, '2024-05-01 10:00:00'),
+                     ('/photos/a.jpg', 2, '2024-03-01 10:00:00');
+                 INSERT INTO file_metadata (path, modified_time, file_size) VALUES
+                     ('/photos//a.jpg', 200, 20),
+                     ('/photos/a.jpg', 100, 10),
+                     ('/photos//b.jpg', 100, 10),
+                     ('/photos/b.jpg', 200, 20);
+                 DELETE FROM app_settings WHERE key = 'path_format';",
+            )
+            .unwrap();
+        db.migrate_path_format().unwrap();
+
+        assert_eq!(
+            db.get_image_stats("/photos/a.jpg").unwrap(),
+            (5, Some("2024-05-01 10:00:00".to_string()))
+        );
+        assert_eq!(db.get_image_stats("/photos//a.jpg").unwrap().0, 0);
+        let sizes: Vec<(String, i64)> = db
+            .conn
+            .prepare("SELECT path, file_size FROM file_metadata ORDER BY path")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            sizes,
+            [
+                ("/photos/a.jpg".to_string(), 20),
+                ("/photos/b.jpg".to_string(), 20)
+            ]
+        );
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,236 @@
+//! オフラインの逆ジオコーディング（GPS 座標 → 市区町村・地域・国）。
+//!
+//! GeoNames の地名データ（`resources/geocode/cities.tsv`）をバイナリに埋め込み、
+//! 緯度・経度 1 度ごとの格子に振り分けて最寄りの地名を引く。ネットワークには一切アクセスしない。
+//! データの作り直しは `resources/geocode/build-cities.sh` を参照。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// 埋め込みの地名データ（name, region, country, latitude, longitude のタブ区切り。`#` 行はコメント）
+const CITIES: &str = include_str!("../resources/geocode/cities.tsv");
+/// 地名データの版（データを差し替えたら DB のキャッシュを引き直すための目印。中身のハッシュ）
+pub static DATASET_VERSION: LazyLock<i64> = LazyLock::new(|| content_hash(CITIES));
+/// これより遠い地名しかなければ「場所不明」とする（海上・僻地で遠くの街を表示しないように）
+const MAX_DISTANCE_KM: f64 = 100.0;
+/// 地球の平均半径
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// 緯度 1 度あたりの距離
+const KM_PER_DEGREE: f64 = EARTH_RADIUS_KM * std::f64::consts::PI / 180.0;
+
+static INDEX: LazyLock<GeoIndex> = LazyLock::new(|| GeoIndex::parse(CITIES));
+
+/// 座標から引いた場所
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    /// 市区町村名（"Kyoto"）
+    pub city: String,
+    /// 都道府県・州など（データにない国は None）
+    pub region: Option<String>,
+    /// 国名（"Japan"）
+    pub country: String,
+}
+
+/// 文字列の FNV-1a ハッシュ（DB に入れられるよう 63 ビットに収める）
+///
+/// 大きさだけでは同じ長さの別のデータを見分けられないので、中身全体から求める。
+fn content_hash(text: &str) -> i64 {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    (hash >> 1) as i64
+}
+
+/// 座標に最も近い地名を返す（MAX_DISTANCE_KM 以内になければ None）
+pub fn reverse_geocode(latitude: f64, longitude: f64) -> Option<Location> {
+    INDEX.nearest(latitude, longitude, MAX_DISTANCE_KM)
+}
+
+struct Place {
+    location: Location,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// 緯度・経度 1 度四方の格子ごとに地名を振り分けた索引
+struct GeoIndex {
+    places: Vec<Place>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl GeoIndex {
+    /// TSV を読み込む（列が足りない行・座標が読めない行は飛ばす）
+    fn parse(data: &str) -> Self {
+        let mut places = Vec::new();
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+
+        for line in data.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let (Some(city), Some(region), Some(country), Some(lat), Some(lon)) = (
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+            ) else {
+                continue;
+            };
+            let (Ok(latitude), Ok(longitude)) = (lat.parse::<f64>(), lon.parse::<f64>()) else {
+                continue;
+            };
+            if city.is_empty() || !is_valid_coordinate(latitude, longitude) {
+                continue;
+            }
+
+            cells
+                .entry(cell_of(latitude, longitude))
+                .or_default()
+                .push(places.len());
+            places.push(Place {
+                location: Location {
+                    city: city.to_string(),
+                    region: (!region.is_empty()).then(|| region.to_string()),
+                    country: country.to_string(),
+                },
+                latitude,
+                longitude,
+            });
+        }
+
+        GeoIndex { places, cells }
+    }
+
+    /// max_km 以内で最も近い地名
+    fn nearest(&self, latitude: f64, longitude: f64, max_km: f64) -> Option<Location> {
+        if !is_valid_coordinate(latitude, longitude) {
+            return None;
+        }
+
+        // max_km の円を囲む格子の範囲（経度方向は高緯度ほど 1 度が短いので広く取る）
+        let lat_span = max_km / KM_PER_DEGREE;
+        let lat_min = (latitude - lat_span).max(-90.0).floor() as i32;
+        let lat_max = (latitude + lat_span).min(89.999).floor() as i32;
+        let widest_latitude = (latitude.abs() + lat_span).min(90.0);
+        let lon_span = max_km / (KM_PER_DEGREE * widest_latitude.to_radians().cos());
+        let lon_cells: Vec<i32> = if !lon_span.is_finite() || lon_span >= 180.0 {
+            (0..360).collect()
+        } else {
+            let lon_min = (longitude - lon_span).floor() as i32;
+            let lon_max = (longitude + lon_span).floor() as i32;
+            (lon_min..=lon_max).map(|lon| lon.rem_euclid(360)).collect()
+        };
+
+        let mut best: Option<(f64, usize)> = None;
+        for lat in lat_min..=lat_max {
+            for &lon in &lon_cells {
+                let Some(indices) = self.cells.get(&(lat, lon)) else {
+                    continue;
+                };
+                for &index in indices {
+                    let place = &self.places[index];
+                    let distance =
+                        haversine_km(latitude, longitude, place.latitude, place.longitude);
+                    if distance <= max_km && best.is_none_or(|(d, _)| distance < d) {
+                        best = Some((distance, index));
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, index)| self.places[index].location.clone())
+    }
+}
+
+fn is_valid_coordinate(latitude: f64, longitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+/// 座標が属する格子（経度は 0〜359 に揃えて日付変更線をまたいでも隣り合うようにする）
+fn cell_of(latitude: f64, longitude: f64) -> (i32, i32) {
+    (
+        (latitude.floor() as i32).min(89),
+        (longitude.floor() as i32).rem_euclid(360),
+    )
+}
+
+/// 2点間の大円距離（km）
+fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_geocode_bundled_dataset() {
+        // 清水寺付近 → 京都
+        let location = reverse_geocode(34.9949, 135.7850).unwrap();
+        assert_eq!(location.city, "Kyoto");
+        assert_eq!(location.region.as_deref(), Some("Kyoto"));
+        assert_eq!(location.country, "Japan");
+
+        // 京都以外（大通公園 → 札幌、フィッツロイ → メルボルン、パイク・プレイス → シアトル）
+        assert_eq!(reverse_geocode(43.0598, 141.3474).unwrap().city, "Sapporo");
+        let location = reverse_geocode(-37.7982, 144.9784).unwrap();
+        assert_eq!(location.city, "Melbourne");
+        assert_eq!(location.region.as_deref(), Some("Victoria"));
+        assert_eq!(location.country, "Australia");
+        assert_eq!(reverse_geocode(47.6097, -122.3422).unwrap().city, "Seattle");
+
+        // 街と街の間（ネバダ州の砂漠。ラスベガスから 300km 以上）は場所不明
+        assert_eq!(reverse_geocode(39.5, -117.0), None);
+
+        // 太平洋の真ん中・範囲外の座標は場所不明
+        assert_eq!(reverse_geocode(30.0, -150.0), None);
+        assert_eq!(reverse_geocode(f64::NAN, 0.0), None);
+        assert_eq!(reverse_geocode(91.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_dataset_version_follows_content() {
+        // 同じ長さでも中身が違えば別の版になる
+        assert_ne!(
+            content_hash("Kyoto\tKyoto\tJapan\t35.0\t135.7\n"),
+            content_hash("Kyoto\tKyoto\tJapan\t35.1\t135.7\n")
+        );
+        assert_eq!(*DATASET_VERSION, content_hash(CITIES));
+        assert!(*DATASET_VERSION >= 0);
+    }
+
+    #[test]
+    fn test_nearest_picks_closest_within_limit() {
+        let index = GeoIndex::parse(
+            "# comment\n\
+             Near\t\tNowhere\t10.0\t10.5\n\
+             Far\tRegion\tNowhere\t10.0\t11.5\n\
+             broken line\n\
+             Bad\t\tNowhere\tabc\t10.0\n",
+        );
+        assert_eq!(index.places.len(), 2);
+
+        let location = index.nearest(10.0, 10.9, 100.0).unwrap();
+        assert_eq!(location.city, "Near");
+        assert_eq!(location.region, None);
+        // 最寄りでも上限より遠ければ None
+        assert_eq!(index.nearest(10.0, 13.0, 100.0), None);
+    }
+
+    #[test]
+    fn test_nearest_across_antimeridian() {
+        let index = GeoIndex::parse("East\t\tFiji\t-16.5\t179.95\n");
+        // 経度 -179.95 は 179.95 から約 10km
+        let location = index.nearest(-16.5, -179.95, 50.0).unwrap();
+        assert_eq!(location.city, "East");
+        assert!((haversine_km(-16.5, -179.95, -16.5, 179.95) - 10.7).abs() < 0.5);
+    }
+}
@@ -0,0 +1,277 @@
+//! GPX トラックログによる撮影地の推定。
+//!
+//! GPS を持たないカメラの写真を、同じ時刻に GPS ロガーが記録した位置と突き合わせる。
+//! 撮影時刻の前後のトラックポイントから線形補間し、カメラの時計のずれは設定
+//! （`gpx_clock_offset_seconds`）で補正する。写真ファイルは書き換えず、
+//! 推定した座標は DB の `inferred_locations` に元の EXIF とは別に保存する。
+//!
+//! GPX は XML だが、必要なのは `<trkpt lat lon>` と `<time>` / `<ele>` だけなので
+//! xmp.rs と同じく XML パーサは使わずに正規表現で拾う。
+
+use crate::image_processor::ExifInfo;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// 前後のトラックポイントの間隔がこれ以内なら補間する（ロガーの電源を切っていた区間は補間しない）
+const MAX_INTERPOLATION_GAP_MS: i64 = 10 * 60 * 1000;
+/// 補間できないとき、これ以内に記録があればその地点を使う
+const MAX_SNAP_MS: i64 = 2 * 60 * 1000;
+
+static TRACK_POINT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(?:\w+:)?trkpt\b([^>]*)>(.*?)</(?:\w+:)?trkpt>").expect("valid regex")
+});
+static LATITUDE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\blat\s*=\s*["']([^"']+)["']"#).expect("valid regex"));
+static LONGITUDE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\blon\s*=\s*["']([^"']+)["']"#).expect("valid regex"));
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<(?:\w+:)?time>\s*([^<]+?)\s*</(?:\w+:)?time>").expect("valid regex")
+});
+static ELEVATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<(?:\w+:)?ele>\s*([^<]+?)\s*</(?:\w+:)?ele>").expect("valid regex")
+});
+static TRACK_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(?:\w+:)?trk\b[^>]*>.*?<(?:\w+:)?name>\s*([^<]+?)\s*</(?:\w+:)?name>")
+        .expect("valid regex")
+});
+
+/// GPX から読み取ったトラック（複数の trk / trkseg はまとめて時刻順に並べる）
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpxTrack {
+    /// 最初の trk の name（なければ None）
+    pub name: Option<String>,
+    pub points: Vec<TrackPoint>,
+}
+
+/// トラックポイント
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    /// 記録時刻（UNIX ミリ秒、UTC）
+    pub time: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// 海抜高度（m）
+    pub elevation: Option<f64>,
+}
+
+/// 取り込み済みトラックの一覧表示用の要約
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpxTrackSummary {
+    pub id: i64,
+    /// 取り込んだ GPX ファイルのパス
+    pub path: String,
+    pub name: Option<String>,
+    pub point_count: i64,
+    /// 最初・最後のトラックポイントの時刻（UNIX ミリ秒、UTC）
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+/// トラックログから推定した撮影地
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InferredLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// GPX を読み込む（時刻のないポイントは突き合わせに使えないので捨てる）
+pub fn parse_gpx(xml: &str) -> Result<GpxTrack, String> {
+    let mut points: Vec<TrackPoint> = TRACK_POINT
+        .captures_iter(xml)
+        .filter_map(|caps| {
+            let attributes = &caps[1];
+            let body = &caps[2];
+            let latitude: f64 = LATITUDE.captures(attributes)?[1].parse().ok()?;
+            let longitude: f64 = LONGITUDE.captures(attributes)?[1].parse().ok()?;
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return None;
+            }
+            let time = DateTime::parse_from_rfc3339(&TIME.captures(body)?[1]).ok()?;
+            let elevation = ELEVATION
+                .captures(body)
+                .and_then(|caps| caps[1].parse().ok());
+            Some(TrackPoint {
+                time: time.timestamp_millis(),
+                latitude,
+                longitude,
+                elevation,
+            })
+        })
+        .collect();
+
+    if points.is_empty() {
+        return Err("No timestamped track points found".to_string());
+    }
+    points.sort_by_key(|point| point.time);
+    points.dedup_by_key(|point| point.time);
+
+    let name = TRACK_NAME.captures(xml).map(|caps| caps[1].to_string());
+    Ok(GpxTrack { name, points })
+}
+
+/// 写真の撮影時刻（UNIX ミリ秒、UTC）
+///
+/// EXIF に UTC オフセットがあればそれを、なければこの端末のタイムゾーンで撮ったものとみなす。
+/// clock_offset_seconds はカメラの時計のずれの補正（カメラの時刻 + この値 = 正しい時刻）。
+pub fn photo_time_utc(exif: &ExifInfo, clock_offset_seconds: i64) -> Option<i64> {
+    let time = match exif.capture_time.as_deref() {
+        Some(capture_time) => match DateTime::parse_from_rfc3339(capture_time) {
+            Ok(time) => time.timestamp_millis(),
+            Err(_) => local_millis(
+                NaiveDateTime::parse_from_str(capture_time, "%Y-%m-%dT%H:%M:%S%.f").ok()?,
+            )?,
+        },
+        None => local_millis(
+            NaiveDateTime::parse_from_str(exif.date_time.as_deref()?, "%Y-%m-%d %H:%M:%S").ok()?,
+        )?,
+    };
+    Some(time + clock_offset_seconds * 1000)
+}
+
+fn local_millis(date_time: NaiveDateTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&date_time)
+        .earliest()
+        .map(|time| time.timestamp_millis())
+}
+
+/// 撮影時刻の直前・直後のトラックポイントから位置を推定する
+///
+/// 両方が MAX_INTERPOLATION_GAP_MS 以内に並んでいれば線形補間し、
+/// そうでなければ MAX_SNAP_MS 以内の近い方の地点を使う。どちらもなければ None。
+pub fn interpolate(
+    before: Option<&TrackPoint>,
+    after: Option<&TrackPoint>,
+    time: i64,
+) -> Option<InferredLocation> {
+    if let (Some(before), Some(after)) = (before, after) {
+        let gap = after.time - before.time;
+        if gap == 0 {
+            return Some(location_of(before));
+        }
+        if gap <= MAX_INTERPOLATION_GAP_MS {
+            let t = (time - before.time) as f64 / gap as f64;
+            // 日付変更線をまたぐ区間は近い側を回って補間する
+            let mut d_lon = after.longitude - before.longitude;
+            if d_lon > 180.0 {
+                d_lon -= 360.0;
+            } else if d_lon < -180.0 {
+                d_lon += 360.0;
+            }
+            let mut longitude = before.longitude + d_lon * t;
+            if longitude > 180.0 {
+                longitude -= 360.0;
+            } else if longitude < -180.0 {
+                longitude += 360.0;
+            }
+            return Some(InferredLocation {
+                latitude: before.latitude + (after.latitude - before.latitude) * t,
+                longitude,
+                altitude: match (before.elevation, after.elevation) {
+                    (Some(a), Some(b)) => Some(a + (b - a) * t),
+                    (a, b) => a.or(b),
+                },
+            });
+        }
+    }
+
+    [before, after]
+        .into_iter()
+        .flatten()
+        .map(|point| ((point.time - time).abs(), point))
+        .filter(|(distance, _)| *distance <= MAX_SNAP_MS)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, point)| location_of(point))
+}
+
+fn location_of(point: &TrackPoint) -> InferredLocation {
+    InferredLocation {
+        latitude: point.latitude,
+        longitude: point.longitude,
+        altitude: point.elevation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gpx() {
+        let xml = r#"<?xml version="1.0"?>
+            <gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+              <metadata><time>2024-05-01T00:00:00Z</time></metadata>
+              <trk><name>Kyoto walk</name><trkseg>
+                <trkpt lon="135.7700" lat="35.0000"><ele>50.5</ele><time>2024-05-01T01:01:00Z</time></trkpt>
+                <trkpt lat='35.0100' lon='135.7800'><time>2024-05-01T10:00:00+09:00</time></trkpt>
+                <trkpt lat="35.0200" lon="135.7900"></trkpt>
+                <trkpt lat="95.0" lon="135.0"><time>2024-05-01T01:02:00Z</time></trkpt>
+              </trkseg></trk>
+            </gpx>"#;
+
+        let track = parse_gpx(xml).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Kyoto walk"));
+        // 時刻なし・範囲外の座標は捨て、時刻順に並べる
+        assert_eq!(track.points.len(), 2);
+        assert_eq!(track.points[0].latitude, 35.01);
+        assert_eq!(track.points[0].elevation, None);
+        assert_eq!(track.points[1].elevation, Some(50.5));
+        assert_eq!(track.points[1].time - track.points[0].time, 60_000);
+
+        assert!(parse_gpx("<gpx></gpx>").is_err());
+    }
+
+    #[test]
+    fn test_photo_time_utc() {
+        let exif = ExifInfo {
+            capture_time: Some("2024-05-01T10:00:00.500+09:00".into()),
+            ..Default::default()
+        };
+        let utc = DateTime::parse_from_rfc3339("2024-05-01T01:00:00.500Z")
+            .unwrap()
+            .timestamp_millis();
+        assert_eq!(photo_time_utc(&exif, 0), Some(utc));
+        // カメラの時計が 90 秒遅れている
+        assert_eq!(photo_time_utc(&exif, 90), Some(utc + 90_000));
+
+        let no_time = ExifInfo::default();
+        assert_eq!(photo_time_utc(&no_time, 0), None);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let point = |time: i64, latitude: f64, longitude: f64| TrackPoint {
+            time,
+            latitude,
+            longitude,
+            elevation: Some(100.0),
+        };
+        let a = point(0, 35.0, 135.0);
+        let b = point(60_000, 35.1, 135.2);
+
+        let middle = interpolate(Some(&a), Some(&b), 30_000).unwrap();
+        assert!((middle.latitude - 35.05).abs() < 1e-9);
+        assert!((middle.longitude - 135.1).abs() < 1e-9);
+        assert_eq!(middle.altitude, Some(100.0));
+
+        // 間隔が空きすぎた区間は補間せず、近くの記録があればそこに寄せる
+        let far = point(3_600_000, 36.0, 136.0);
+        assert_eq!(
+            interpolate(Some(&a), Some(&far), 60_000).unwrap().latitude,
+            35.0
+        );
+        assert_eq!(interpolate(Some(&a), Some(&far), 1_800_000), None);
+        assert_eq!(interpolate(None, Some(&b), 0).unwrap().latitude, 35.1);
+        assert_eq!(interpolate(None, None, 0), None);
+
+        // 日付変更線をまたぐ
+        let west = point(0, -16.5, 179.9);
+        let east = point(60_000, -16.5, -179.9);
+        let crossing = interpolate(Some(&west), Some(&east), 15_000).unwrap();
+        assert!((crossing.longitude - 179.95).abs() < 1e-9);
+    }
+}
@@ -1,85 +1,882 @@
-use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::Path;
+//! gitignore 形式の除外フィルタ。
+//!
+//! 各行を gitignore と同じ規則で解釈し、ライブラリのルートからの相対パスに対して判定する。
+//! - `#` で始まる行はコメント、`!` で始まる行は除外の取り消し（`\#` `\!` で文字どおり）
+//! - 末尾が `/` のパターンはディレクトリにだけマッチする
+//! - 途中（先頭を含む）に `/` を含むパターンはルートからの位置に固定、含まないものはどの階層の名前にもマッチ
+//! - `*` `?` `[...]` は `/` をまたがない。`**` は 0 個以上の階層
+//! - 後の行ほど優先（最後にマッチした行で決まる）。除外されたディレクトリの中身は取り消せない
+//!
+//! DB のルールに加えて、ライブラリ内の各フォルダに置いた `.sssignore` がそのフォルダ以下に効く
+//! （書式は同じで、パターンはそのフォルダからの相対パス）。深いフォルダのファイルほど優先し、
+//! DB のルールはライブラリのルートに置いた一番浅いファイルとして扱う。
+//!
+//! `exclude_image` が保存する絶対パスのパターン（旧形式）もそのまま使えるように、
+//! ルート配下を指す絶対パスはルートからの固定パターンに読み替え、
+//! ルート外のパスはファイルシステムのルートを起点に判定する。
+//!
+//! DB のルールには期限（スヌーズ）を付けられる。期限を過ぎたルールはフィルタを組むときに読み飛ばす。
+//!
+//! パスとパターンは path_norm.rs の正規形（`/` 区切り・NFC）に揃えてから比べ、大文字小文字は
+//! `CasePolicy`（既定はこの OS のファイルシステムに合わせる）に従う。
 
+use crate::path_norm::{canonical_path, canonical_str, nfc, strip_path_prefix, CasePolicy};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// フォルダごとの除外ルールファイル名
+pub const IGNORE_FILE_NAME: &str = ".sssignore";
+
+/// 除外ルール1行
+#[derive(Clone)]
+struct Rule {
+    matcher: GlobMatcher,
+    /// `!` 付き（マッチしたら除外を取り消す）
+    negated: bool,
+    /// 末尾 `/` 付き（ディレクトリにだけマッチ）
+    dir_only: bool,
+}
+
+/// DB に保存した除外ルール
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreRule {
+    pub pattern: String,
+    /// この時刻（UNIX 秒）を過ぎたら効かなくなる。None は無期限
+    pub expires_at: Option<i64>,
+    /// 追加日時（DB の `added_at`、UTC の "YYYY-MM-DD HH:MM:SS"）。書き出しの由来コメントに使う
+    pub added_at: Option<String>,
+    /// どこで追加されたか（記録を始める前からあるルールは None）
+    pub source: Option<RuleSource>,
+    /// 利用者が付けた覚え書き
+    pub label: Option<String>,
+    /// false なら消さずに一時的に止めている
+    pub enabled: bool,
+}
+
+/// 除外ルールの出どころ（DB の `ignore_rules.source` に JSON で保存）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum RuleSource {
+    /// 初回起動時に入れた既定のルール
+    Default,
+    /// 設定画面の「除外ルール」タブで手動追加
+    Settings,
+    /// オーバーレイの「…」メニューで写真から除外（exclude_type は "file" / "directory"）
+    Menu {
+        image_path: String,
+        exclude_type: String,
+    },
+    /// 旧 `~/.sssignore` からの移行
+    Migration,
+    /// ルールファイルの読み込み
+    Import { file_path: String },
+}
+
+impl IgnoreRule {
+    /// 有効で期限のないルール
+    pub fn new(pattern: impl Into<String>, source: Option<RuleSource>) -> Self {
+        IgnoreRule {
+            pattern: pattern.into(),
+            expires_at: None,
+            added_at: None,
+            source,
+            label: None,
+            enabled: true,
+        }
+    }
+
+    /// 時刻 now（UNIX 秒）にまだ効いているか（止めているルールは効かない）
+    pub fn is_active(&self, now: i64) -> bool {
+        self.enabled && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// 時刻 now に効いているルールのパターンだけを取り出す（順序は保つ）
+pub fn active_patterns(rules: &[IgnoreRule], now: i64) -> Vec<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.is_active(now))
+        .map(|rule| rule.pattern.clone())
+        .collect()
+}
+
+/// 追加前のルールを試した結果（preview_ignore_pattern 用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnorePreview {
+    /// 判定したファイル数
+    pub total_files: usize,
+    /// パターンにマッチしたファイル数
+    pub matched_count: usize,
+    /// 追加すると新たに除外されるファイル数
+    pub excluded_count: usize,
+    /// マッチしたが既存のルールですでに除外されているファイル数
+    pub already_excluded_count: usize,
+    /// 新たに除外されるファイルの例（パス順）
+    pub sample: Vec<String>,
+    /// マッチしたファイルをすでに除外している既存のルールと、その件数（件数の多い順）
+    pub covering_rules: Vec<RuleCoverage>,
+}
+
+/// ルールファイルの読み込み結果（import_ignore_rules 用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreImportReport {
+    /// 追加した（置き換えでは読み込んだ）ルール数
+    pub added: usize,
+    /// 同じ内容のルールがすでにあった数
+    pub unchanged: usize,
+    /// 既存のルールとぶつかって読み込まなかった行
+    pub conflicts: Vec<ImportIssue>,
+    /// パターンや期限が読めなかった行
+    pub invalid: Vec<ImportIssue>,
+}
+
+/// 読み込めなかった行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    /// 行番号（1 始まり）
+    pub line: usize,
+    pub text: String,
+    pub message: String,
+}
+
+/// 書けないパターンの理由（validate_ignore_pattern 用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternError {
+    pub pattern: String,
+    /// 問題のある文字の位置（パターンの先頭からの文字数、0 始まり）。特定できなければ None
+    pub position: Option<usize>,
+    pub reason: String,
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "Invalid pattern at position {position}: {}", self.reason),
+            None => write!(f, "Invalid pattern: {}", self.reason),
+        }
+    }
+}
+
+/// 保存済みのルールの問題（check_ignore_rules 用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RuleProblem {
+    /// パターンとして読めない（フィルタを組むときに読み飛ばされている）
+    Invalid { error: PatternError },
+    /// ライブラリのどのファイル・フォルダにもマッチしない
+    Unmatched,
+}
+
+/// 問題のある保存済みのルール
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleHealth {
+    pub pattern: String,
+    pub problem: RuleProblem,
+}
+
+/// 既存のルールが覆っているファイル数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleCoverage {
+    pub pattern: String,
+    pub count: usize,
+}
+
+#[derive(Clone)]
 pub struct IgnoreFilter {
-    globset: Option<GlobSet>,
+    /// 元のパターン（with_root でルートを変えたときに組み直す）
+    patterns: Vec<String>,
+    /// ルート（判定に使う形。`match_key`）
+    root_key: Option<String>,
+    case: CasePolicy,
+    rules: Vec<Rule>,
+    /// フォルダごとの `.sssignore`（判定用の形のフォルダ、浅い順）
+    nested: Vec<(String, Vec<Rule>)>,
+    /// `.sssignore` を探し済みのフォルダ（ファイルがなかったフォルダも含む）
+    visited: HashSet<PathBuf>,
 }
 
 impl IgnoreFilter {
     /// DBから取得したパターン一覧から除外ルールを作成
+    ///
+    /// ルートを指定しない場合はファイルシステムのルートを起点に判定する（`with_root` を参照）。
     pub fn from_patterns(patterns: &[String]) -> Self {
-        let mut builder = GlobSetBuilder::new();
-        let mut has_patterns = false;
+        let patterns = patterns.to_vec();
+        let case = CasePolicy::platform();
+        let rules = compile_rules(&patterns, None, case);
+        IgnoreFilter {
+            patterns,
+            root_key: None,
+            case,
+            rules,
+            nested: Vec::new(),
+            visited: HashSet::new(),
+        }
+    }
 
-        for pattern in patterns {
-            let line = pattern.trim();
+    /// DB のルールから除外フィルタを作成（時刻 now（UNIX 秒）で期限切れのルールは除く）
+    pub fn from_rules(rules: &[IgnoreRule], now: i64) -> Self {
+        Self::from_patterns(&active_patterns(rules, now))
+    }
 
-            // 空行をスキップ
-            if line.is_empty() {
-                continue;
+    /// ライブラリのルートを指定する（固定パターンはこのディレクトリからの相対パスとして判定）
+    pub fn with_root(self, root: &Path) -> Self {
+        let root_key = match_key(root);
+        let rules = compile_rules(&self.patterns, Some(&root_key), self.case);
+        IgnoreFilter {
+            root_key: Some(root_key),
+            rules,
+            ..self
+        }
+    }
+
+    /// 大文字小文字の扱いを変える（既定はこの OS の扱い。読み込み済みの `.sssignore` は読み直す）
+    pub fn with_case_policy(self, case: CasePolicy) -> Self {
+        let rules = compile_rules(&self.patterns, self.root_key.as_deref(), case);
+        IgnoreFilter {
+            case,
+            rules,
+            nested: Vec::new(),
+            visited: HashSet::new(),
+            ..self
+        }
+    }
+
+    /// フォルダの `.sssignore` を読み込む（なければ何もしない。同じフォルダは一度だけ読む）
+    ///
+    /// スキャナはフォルダに降りるたびに呼ぶ。除外されたフォルダの中のファイルは読まない（gitignore と同じ）。
+    pub fn load_ignore_file(&mut self, directory: &Path) {
+        if !self.visited.insert(directory.to_path_buf()) {
+            return;
+        }
+        let content = match std::fs::read_to_string(directory.join(IGNORE_FILE_NAME)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                eprintln!("Failed to read {IGNORE_FILE_NAME} in {directory:?}: {e}");
+                return;
             }
+        };
 
-            // パターンを追加
-            match Glob::new(line) {
-                Ok(glob) => {
-                    builder.add(glob);
-                    has_patterns = true;
-                }
-                Err(e) => {
-                    eprintln!("Invalid pattern '{line}': {e}");
-                }
+        let patterns: Vec<String> = content.lines().map(str::to_string).collect();
+        let directory = match_key(directory);
+        let rules = compile_rules(&patterns, Some(&directory), self.case);
+        if rules.is_empty() {
+            return;
+        }
+        self.nested.push((directory, rules));
+        self.nested
+            .sort_by_key(|(directory, _)| directory.split('/').count());
+    }
+
+    /// ルートからファイルまでの各フォルダの `.sssignore` を読み込む（スキャン以外でパスを判定する前に使う）
+    pub fn load_ignore_files_along(&mut self, path: &Path) {
+        let mut directories: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| {
+                self.root_key.as_deref().is_some_and(|root| {
+                    strip_path_prefix(&match_key(ancestor), root, self.case).is_some()
+                })
+            })
+            .map(Path::to_path_buf)
+            .collect();
+        directories.reverse();
+        for directory in directories {
+            if self.is_ignored_dir(&directory) {
+                return;
             }
+            self.load_ignore_file(&directory);
         }
+    }
+
+    /// ファイルが除外対象かチェック（親ディレクトリが除外されている場合も除外）
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.is_path_ignored(path, false)
+    }
+
+    /// ディレクトリが除外対象かチェック（スキャン時に中へ降りるかの判定用）
+    pub fn is_ignored_dir(&self, path: &Path) -> bool {
+        self.is_path_ignored(path, true)
+    }
 
-        if !has_patterns {
-            return IgnoreFilter { globset: None };
+    fn is_path_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() && self.nested.is_empty() {
+            return false;
+        }
+        if self.relative_components(path).is_empty() {
+            return false;
         }
 
-        match builder.build() {
-            Ok(globset) => IgnoreFilter {
-                globset: Some(globset),
-            },
-            Err(e) => {
-                eprintln!("Failed to build globset: {e}");
-                IgnoreFilter { globset: None }
+        // 親ディレクトリを浅い順に見る（除外されたディレクトリの中身は `!` でも戻せない）
+        let mut parents: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| !self.relative_components(ancestor).is_empty())
+            .collect();
+        parents.reverse();
+        if parents.into_iter().any(|parent| self.matches(parent, true)) {
+            return true;
+        }
+
+        self.matches(path, is_dir)
+    }
+
+    /// 深いフォルダの `.sssignore` → DB のルールの順に、最後にマッチした行で除外かどうかを決める
+    /// （どれにもマッチしなければ除外しない）
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let key = match_key(path);
+        for (directory, rules) in self.nested.iter().rev() {
+            let Some(relative) = strip_path_prefix(&key, directory, self.case) else {
+                continue;
+            };
+            let relative = split_components(relative).join("/");
+            if relative.is_empty() {
+                continue;
+            }
+            if let Some(rule) = last_match(rules, &relative, is_dir) {
+                return !rule.negated;
             }
         }
+
+        let relative = self.relative_components(path).join("/");
+        last_match(&self.rules, &relative, is_dir).is_some_and(|rule| !rule.negated)
     }
 
-    /// ファイルパスが除外対象かチェック
-    pub fn is_ignored(&self, path: &Path) -> bool {
-        if let Some(ref globset) = self.globset {
-            // フルパスでチェック
-            if globset.is_match(path) {
-                return true;
+    /// 判定に使う相対パスの各要素（ルート外・ルート未指定ならファイルシステムのルートから）
+    fn relative_components(&self, path: &Path) -> Vec<String> {
+        let key = match_key(path);
+        let relative = self
+            .root_key
+            .as_deref()
+            .and_then(|root| strip_path_prefix(&key, root, self.case))
+            .unwrap_or(&key);
+        split_components(relative)
+    }
+
+    /// パターンが設定されているかチェック（テスト用）
+    #[cfg(test)]
+    pub fn has_patterns(&self) -> bool {
+        !self.rules.is_empty()
+    }
+}
+
+/// パターンを既存のルールの後ろに追加したら、paths のうちどれが除外されるかを試す（DB は変えない）
+///
+/// フォルダごとの `.sssignore` も読み込んで判定するので、深いフォルダの `!` で打ち消される分は除外に数えない。
+/// パターンとして使えない行（文法エラー・空行・コメント）は Err。
+pub fn preview_pattern(
+    existing: &[String],
+    pattern: &str,
+    root: Option<&Path>,
+    paths: &[String],
+    sample_size: usize,
+) -> Result<IgnorePreview, String> {
+    validate_pattern(pattern).map_err(|e| e.to_string())?;
+
+    let with_root = |filter: IgnoreFilter| match root {
+        Some(root) => filter.with_root(root),
+        None => filter,
+    };
+    let mut current = with_root(IgnoreFilter::from_patterns(existing));
+    let mut combined = {
+        let mut patterns = existing.to_vec();
+        patterns.push(pattern.to_string());
+        with_root(IgnoreFilter::from_patterns(&patterns))
+    };
+    let candidate = with_root(IgnoreFilter::from_patterns(&[pattern.to_string()]));
+    // 既存のルールを1行ずつ（どのルールが覆っているかを数える用）
+    let singles: Vec<(&String, IgnoreFilter)> = existing
+        .iter()
+        .map(|p| {
+            (
+                p,
+                with_root(IgnoreFilter::from_patterns(std::slice::from_ref(p))),
+            )
+        })
+        .filter(|(_, filter)| !filter.rules.is_empty())
+        .collect();
+
+    let mut preview = IgnorePreview {
+        total_files: paths.len(),
+        matched_count: 0,
+        excluded_count: 0,
+        already_excluded_count: 0,
+        sample: Vec::new(),
+        covering_rules: Vec::new(),
+    };
+    let mut coverage = vec![0usize; singles.len()];
+    let mut sorted: Vec<&String> = paths.iter().collect();
+    sorted.sort();
+
+    for path in sorted {
+        let path_ref = Path::new(path);
+        if !candidate.is_ignored(path_ref) {
+            continue;
+        }
+        preview.matched_count += 1;
+
+        current.load_ignore_files_along(path_ref);
+        combined.load_ignore_files_along(path_ref);
+        if current.is_ignored(path_ref) {
+            preview.already_excluded_count += 1;
+            for (count, (_, filter)) in coverage.iter_mut().zip(&singles) {
+                if filter.is_ignored(path_ref) {
+                    *count += 1;
+                }
+            }
+        } else if combined.is_ignored(path_ref) {
+            preview.excluded_count += 1;
+            if preview.sample.len() < sample_size {
+                preview.sample.push(path.clone());
             }
+        }
+    }
 
-            // 各コンポーネントでチェック（フォルダ名のマッチング用）
-            for component in path.components() {
-                if let Some(s) = component.as_os_str().to_str() {
-                    if globset.is_match(s) {
-                        return true;
-                    }
+    preview.covering_rules = singles
+        .iter()
+        .zip(coverage)
+        .filter(|(_, count)| *count > 0)
+        .map(|((pattern, _), count)| RuleCoverage {
+            pattern: (*pattern).clone(),
+            count,
+        })
+        .collect();
+    preview
+        .covering_rules
+        .sort_by_key(|coverage| std::cmp::Reverse(coverage.count));
+    Ok(preview)
+}
+
+/// 最後にマッチしたルール
+fn last_match<'a>(rules: &'a [Rule], relative: &str, is_dir: bool) -> Option<&'a Rule> {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(relative))
+}
+
+/// 判定に使う形のパス（保存する形を、どの OS でも NFC に揃えたもの）
+fn match_key(path: &Path) -> String {
+    nfc(&canonical_path(path)).into_owned()
+}
+
+/// 判定に使う形のパスを要素に分ける（Windows のドライブ名は1要素目として残す）
+fn split_components(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(str::to_string)
+        .collect()
+}
+
+/// 由来コメントのキー（書き出しで付け、読み込みで期限・ラベル・止めているかを戻す。追加日時と出どころは記録のみ）
+const ADDED_KEY: &str = "# added:";
+const SOURCE_KEY: &str = "# source:";
+const LABEL_KEY: &str = "# label:";
+const EXPIRES_KEY: &str = "# expires:";
+/// 止めているルールはコメントアウトして書く（そのまま `.sssignore` として置いても効かないように）
+const DISABLED_KEY: &str = "# disabled:";
+
+/// DB のルールを gitignore 形式のファイルに書き出す（now は書き出し時刻の UNIX 秒）
+///
+/// 各ルールの前に追加日時・出どころ・ラベル・スヌーズの期限をコメントで残す。期限切れのルールも含めて追加順に並べる。
+pub fn export_rules(rules: &[IgnoreRule], now: i64) -> String {
+    let mut out = String::new();
+    out.push_str("# sss の除外ルール（gitignore 形式）\n");
+    out.push_str(&format!("# exported: {}\n", format_timestamp(now)));
+    out.push_str(&format!(
+        "# 各ルールの上の \"{ADDED_KEY}\" は追加日時（UTC）、\"{SOURCE_KEY}\" は出どころ、\
+         \"{LABEL_KEY}\" は覚え書き、\"{EXPIRES_KEY}\" はスヌーズの期限。\
+         \"{DISABLED_KEY}\" の行は止めているルール\n"
+    ));
+    for rule in rules {
+        out.push('\n');
+        if let Some(added_at) = &rule.added_at {
+            out.push_str(&format!("{ADDED_KEY} {added_at}\n"));
+        }
+        if let Some(source) = &rule.source {
+            out.push_str(&format!("{SOURCE_KEY} {}\n", describe_source(source)));
+        }
+        if let Some(label) = &rule.label {
+            out.push_str(&format!("{LABEL_KEY} {label}\n"));
+        }
+        if let Some(expires_at) = rule.expires_at {
+            out.push_str(&format!("{EXPIRES_KEY} {}\n", format_timestamp(expires_at)));
+        }
+        if !rule.enabled {
+            out.push_str(DISABLED_KEY);
+            out.push(' ');
+        }
+        out.push_str(&rule.pattern);
+        out.push('\n');
+    }
+    out
+}
+
+/// 書き出しの由来コメント用に出どころを1行にする
+fn describe_source(source: &RuleSource) -> String {
+    match source {
+        RuleSource::Default => "default".to_string(),
+        RuleSource::Settings => "settings".to_string(),
+        RuleSource::Menu {
+            image_path,
+            exclude_type,
+        } => format!("menu ({exclude_type}) {image_path}"),
+        RuleSource::Migration => "migration".to_string(),
+        RuleSource::Import { file_path } => format!("import {file_path}"),
+    }
+}
+
+/// 書き出したファイル（または手書きの `.sssignore`）を読み、DB に入れるルールを決める
+///
+/// `replace` なら既存のルールと置き換える前提でファイルのルールをすべて返す。そうでなければ既存のルールに
+/// 足す分だけを返し、同じパターンで期限が違うもの・既存のルールを `!` で打ち消す（またはその逆の）ものは
+/// 衝突として読み込まない。読めないパターン・期限、すでに期限切れのルールは invalid に入れる。
+pub fn plan_import(
+    existing: &[IgnoreRule],
+    content: &str,
+    replace: bool,
+    now: i64,
+) -> (Vec<IgnoreRule>, IgnoreImportReport) {
+    let mut rules: Vec<IgnoreRule> = Vec::new();
+    let mut report = IgnoreImportReport::default();
+    let mut pending_expiry: Option<i64> = None;
+    let mut pending_label: Option<String> = None;
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        let issue = |message: String| ImportIssue {
+            line: index + 1,
+            text: line.to_string(),
+            message,
+        };
+
+        if line.is_empty() {
+            pending_expiry = None;
+            pending_label = None;
+            continue;
+        }
+        if let Some(value) = line.strip_prefix(EXPIRES_KEY) {
+            match chrono::DateTime::parse_from_rfc3339(value.trim()) {
+                Ok(expires_at) => pending_expiry = Some(expires_at.timestamp()),
+                Err(e) => report.invalid.push(issue(format!("Invalid expiry: {e}"))),
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix(LABEL_KEY) {
+            pending_label = Some(value.trim().to_string()).filter(|label| !label.is_empty());
+            continue;
+        }
+        let (line, enabled) = match line.strip_prefix(DISABLED_KEY) {
+            Some(pattern) => (pattern.trim(), false),
+            None => (line, true),
+        };
+        if enabled && line.starts_with('#') {
+            continue;
+        }
+
+        let expires_at = pending_expiry.take();
+        let label = pending_label.take();
+        if let Err(e) = validate_pattern(line) {
+            report.invalid.push(issue(e.to_string()));
+            continue;
+        }
+        if expires_at.is_some_and(|expires_at| expires_at <= now) {
+            report
+                .invalid
+                .push(issue("Snooze has already expired".to_string()));
+            continue;
+        }
+        // ファイル内の重複は最初の行を採る
+        if rules.iter().any(|rule| rule.pattern == line) {
+            continue;
+        }
+
+        let rule = IgnoreRule {
+            expires_at,
+            label,
+            enabled,
+            ..IgnoreRule::new(line, None)
+        };
+        if !replace {
+            if let Some(current) = existing.iter().find(|r| r.pattern == rule.pattern) {
+                if current.expires_at == rule.expires_at {
+                    report.unchanged += 1;
+                } else {
+                    report.conflicts.push(issue(
+                        "Rule already exists with a different expiry; kept the existing one"
+                            .to_string(),
+                    ));
                 }
+                continue;
             }
+            let opposite = match rule.pattern.strip_prefix('!') {
+                Some(pattern) => pattern.to_string(),
+                None => format!("!{}", rule.pattern),
+            };
+            if existing.iter().any(|r| r.pattern == opposite) {
+                report
+                    .conflicts
+                    .push(issue(format!("Reverses the existing rule \"{opposite}\"")));
+                continue;
+            }
+        }
+        rules.push(rule);
+    }
 
-            false
+    report.added = rules.len();
+    (rules, report)
+}
+
+/// 「…」メニューで除外するパスをパターンにする（末尾の `/` なし）
+///
+/// ライブラリ内ならルートからの固定パターン（`/trip/a.jpg`）にして、ライブラリを別の場所・別の OS に
+/// 移しても効くようにする。ライブラリ外（別ライブラリの最近の画像など）は絶対パスのまま。
+pub fn anchored_pattern(path: &Path, root: Option<&Path>) -> String {
+    let absolute = canonical_path(path);
+    let relative = root.and_then(|root| {
+        strip_path_prefix(&absolute, &canonical_path(root), CasePolicy::platform())
+    });
+    match relative {
+        Some("") => String::new(),
+        Some(relative) => format!("/{}", literal_pattern(relative)),
+        None => literal_pattern(absolute.trim_end_matches('/')),
+    }
+}
+
+/// パスをそのファイル・フォルダだけにマッチするパターンにする
+///
+/// ファイル名の `[` `*` `{` などがワイルドカードとして読まれて別の写真まで除外したり、
+/// 書式の誤りで保存できなくなったりしないよう、区切りごとに globset の書き方でエスケープする。
+pub fn literal_pattern(path: &str) -> String {
+    path.split('/')
+        .map(globset::escape)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// パターンとして保存できるか確かめる（読めなければ位置と理由を返す）
+pub fn validate_pattern(pattern: &str) -> Result<(), PatternError> {
+    let error = |position: Option<usize>, reason: String| PatternError {
+        pattern: pattern.to_string(),
+        position,
+        reason,
+    };
+    match compile_rule(pattern, None, CasePolicy::platform()) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(error(None, "pattern is empty or a comment".to_string())),
+        Err(e) => Err(error(
+            error_position(pattern, e.kind()),
+            e.kind().to_string(),
+        )),
+    }
+}
+
+/// globset のエラーが指す文字の位置を、入力されたパターン上で探す（文字数、0 始まり）
+fn error_position(pattern: &str, kind: &globset::ErrorKind) -> Option<usize> {
+    use globset::ErrorKind;
+
+    let offset = pattern.chars().count() - pattern.trim_start().chars().count();
+    let chars: Vec<char> = pattern.trim().chars().collect();
+    let mut class_start: Option<usize> = None;
+    let mut braces: Vec<usize> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' {
+            if i + 1 == chars.len() {
+                return matches!(kind, ErrorKind::DanglingEscape).then_some(offset + i);
+            }
+            i += 2;
+            continue;
+        }
+        if let Some(start) = class_start {
+            // `[` 直後（`[!` の直後）の `]` は文字そのもの
+            let first = start + 1 + usize::from(matches!(chars.get(start + 1), Some('!' | '^')));
+            if c == ']' && i > first {
+                class_start = None;
+            } else if let ErrorKind::InvalidRange(from, to) = kind {
+                if c == *from && chars.get(i + 1) == Some(&'-') && chars.get(i + 2) == Some(to) {
+                    return Some(offset + i);
+                }
+            }
         } else {
-            false
+            match c {
+                '[' => class_start = Some(i),
+                '{' => braces.push(i),
+                '}' => {
+                    let unopened = braces.pop().is_none();
+                    if unopened && matches!(kind, ErrorKind::UnopenedAlternates) {
+                        return Some(offset + i);
+                    }
+                }
+                _ => {}
+            }
         }
+        i += 1;
     }
 
-    /// パターンが設定されているかチェック（テスト用）
-    #[cfg(test)]
-    pub fn has_patterns(&self) -> bool {
-        self.globset.is_some()
+    match kind {
+        ErrorKind::UnclosedClass => class_start.map(|start| offset + start),
+        ErrorKind::UnclosedAlternates => braces.last().map(|start| offset + start),
+        _ => None,
+    }
+}
+
+/// 保存済みのルールのうち、読めないものとライブラリのどこにもマッチしないものを挙げる
+///
+/// entries はライブラリ内の全ファイル・フォルダ（除外ルールをかけずに集めたもの）と、フォルダかどうか。
+/// `!` のルールは取り消し対象のパスがあるかで判定する。
+pub fn check_rules(
+    patterns: &[String],
+    root: &Path,
+    entries: &[(PathBuf, bool)],
+) -> Vec<RuleHealth> {
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            let problem = match validate_pattern(pattern) {
+                Err(error) => RuleProblem::Invalid { error },
+                Ok(()) => {
+                    let positive = pattern.trim().strip_prefix('!').unwrap_or(pattern.trim());
+                    let filter =
+                        IgnoreFilter::from_patterns(&[positive.to_string()]).with_root(root);
+                    if entries
+                        .iter()
+                        .any(|(path, is_dir)| filter.matches(path, *is_dir))
+                    {
+                        return None;
+                    }
+                    RuleProblem::Unmatched
+                }
+            };
+            Some(RuleHealth {
+                pattern: pattern.clone(),
+                problem,
+            })
+        })
+        .collect()
+}
+
+/// UNIX 秒を RFC 3339（UTC）にする
+fn format_timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| secs.to_string())
+}
+
+/// 書式の誤りは追加・読み込みの時点で `validate_pattern`・`plan_import` が返すので、ここでは黙って読み飛ばす
+fn compile_rules(patterns: &[String], root: Option<&str>, case: CasePolicy) -> Vec<Rule> {
+    patterns
+        .iter()
+        .filter_map(|pattern| compile_rule(pattern, root, case).ok().flatten())
+        .collect()
+}
+
+/// 1行を gitignore の規則で解釈する（空行・コメントは Ok(None)。root は判定に使う形のフォルダ）
+fn compile_rule(
+    pattern: &str,
+    root: Option<&str>,
+    case: CasePolicy,
+) -> Result<Option<Rule>, globset::Error> {
+    // パスと同じく NFC に揃えて比べる
+    let normalized = nfc(pattern.trim());
+    let mut line: &str = &normalized;
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
     }
+
+    // 先頭の `!` は取り消し、`\!` `\#` はエスケープ。どちらも1文字落とす
+    let negated = line.starts_with('!');
+    if negated || line.starts_with("\\!") || line.starts_with("\\#") {
+        line = &line[1..];
+    }
+
+    // Windows の区切り文字（絶対パスのパターン）も `/` に揃える
+    let mut line = if cfg!(windows) {
+        line.replace('\\', "/")
+    } else {
+        line.to_string()
+    };
+
+    let dir_only = line.ends_with('/');
+    while line.ends_with('/') && line.len() > 1 {
+        line.pop();
+    }
+    if line.is_empty() || line == "/" {
+        return Ok(None);
+    }
+
+    // ルート配下を指す絶対パス（exclude_image が保存する形式）はルートからの固定パターンにする
+    let absolute = canonical_str(&line);
+    if let Some(relative) = root.and_then(|root| strip_path_prefix(&absolute, root, case)) {
+        let relative = split_components(relative).join("/");
+        if relative.is_empty() {
+            return Ok(None);
+        }
+        line = format!("/{relative}");
+    }
+
+    // `/` を含むパターンはルート（または Windows のドライブ）からの位置に固定、含まなければどの階層にもマッチ
+    let glob = if line.contains('/') {
+        let anchored = line.trim_start_matches('/');
+        normalize_double_star(anchored)
+    } else {
+        format!("**/{}", normalize_double_star(&line))
+    };
+
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .case_insensitive(case == CasePolicy::Insensitive)
+        .build()?
+        .compile_matcher();
+    Ok(Some(Rule {
+        matcher,
+        negated,
+        dir_only,
+    }))
+}
+
+/// 階層まるごとではない `**`（`.**` 等）は gitignore と同じく `*` として扱う
+fn normalize_double_star(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .map(|segment| {
+            if segment == "**" {
+                segment.to_string()
+            } else {
+                let mut segment = segment.to_string();
+                while segment.contains("**") {
+                    segment = segment.replace("**", "*");
+                }
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn filter(patterns: &[&str]) -> IgnoreFilter {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        IgnoreFilter::from_patterns(&patterns).with_root(Path::new("/photos"))
+    }
+
     #[test]
     fn test_ignore_filter_basic() {
         let patterns = vec![
@@ -116,4 +913,295 @@ mod tests {
         assert!(!filter.has_patterns());
         assert!(!filter.is_ignored(Path::new("/photos/image.jpg")));
     }
+
+    #[test]
+    fn test_gitignore_rules() {
+        // 名前だけのパターンはどの階層にもマッチし、`*` は `/` をまたがない
+        let f = filter(&["private", "*.jpg.tmp", "# comment", ""]);
+        assert!(f.is_ignored(Path::new("/photos/a/private/b.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/x.jpg.tmp")));
+        assert!(!f.is_ignored(Path::new("/photos/private.jpg")));
+
+        // `/` を含むパターンはルートに固定
+        let f = filter(&["/raw", "trip/*.png"]);
+        assert!(f.is_ignored(Path::new("/photos/raw/a.jpg")));
+        assert!(!f.is_ignored(Path::new("/photos/2024/raw/a.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/trip/a.png")));
+        assert!(!f.is_ignored(Path::new("/photos/trip/day1/a.png")));
+
+        // 末尾 `/` はディレクトリだけ
+        let f = filter(&["edits/"]);
+        assert!(f.is_ignored(Path::new("/photos/2024/edits/a.jpg")));
+        assert!(!f.is_ignored(Path::new("/photos/edits")));
+        assert!(f.is_ignored_dir(Path::new("/photos/edits")));
+
+        // 最後にマッチした行が勝つ。除外したディレクトリの中身は取り消せない
+        let f = filter(&["*.png", "!keep.png", "old/", "!old/keep.png"]);
+        assert!(f.is_ignored(Path::new("/photos/a.png")));
+        assert!(!f.is_ignored(Path::new("/photos/keep.png")));
+        assert!(f.is_ignored(Path::new("/photos/old/keep.png")));
+        let f = filter(&["!keep.png", "*.png"]);
+        assert!(f.is_ignored(Path::new("/photos/keep.png")));
+    }
+
+    #[test]
+    fn test_snoozed_rules_expire() {
+        let rules = vec![
+            IgnoreRule {
+                expires_at: Some(1_000),
+                ..IgnoreRule::new("trip/", None)
+            },
+            IgnoreRule::new("*.png", None),
+            IgnoreRule {
+                enabled: false,
+                ..IgnoreRule::new("*.gif", None)
+            },
+        ];
+
+        let filter = IgnoreFilter::from_rules(&rules, 999).with_root(Path::new("/photos"));
+        assert!(filter.is_ignored(Path::new("/photos/trip/a.jpg")));
+        assert!(filter.is_ignored(Path::new("/photos/b.png")));
+
+        // 期限を過ぎたら戻る（無期限のルールは残る）
+        let filter = IgnoreFilter::from_rules(&rules, 1_000).with_root(Path::new("/photos"));
+        assert!(!filter.is_ignored(Path::new("/photos/trip/a.jpg")));
+        assert!(filter.is_ignored(Path::new("/photos/b.png")));
+        // 止めているルールは期限に関係なく効かない
+        assert!(!filter.is_ignored(Path::new("/photos/c.gif")));
+        assert_eq!(active_patterns(&rules, 1_000), vec!["*.png".to_string()]);
+    }
+
+    #[test]
+    fn test_export_and_import_rules() {
+        let rules = vec![
+            IgnoreRule {
+                added_at: Some("2024-01-02 03:04:05".to_string()),
+                ..IgnoreRule::new("**/.thumbnails/", Some(RuleSource::Default))
+            },
+            IgnoreRule {
+                expires_at: Some(2_000_000_000),
+                added_at: Some("2026-10-19 08:00:00".to_string()),
+                label: Some("旅行".to_string()),
+                ..IgnoreRule::new(
+                    "trip/",
+                    Some(RuleSource::Menu {
+                        image_path: "/photos/trip/a.jpg".to_string(),
+                        exclude_type: "directory".to_string(),
+                    }),
+                )
+            },
+            IgnoreRule {
+                enabled: false,
+                ..IgnoreRule::new("*.gif", Some(RuleSource::Settings))
+            },
+        ];
+        let exported = export_rules(&rules, 1_800_000_000);
+        assert!(
+            exported.contains("# added: 2024-01-02 03:04:05\n# source: default\n**/.thumbnails/\n")
+        );
+        assert!(exported.contains(
+            "# source: menu (directory) /photos/trip/a.jpg\n# label: 旅行\n\
+             # expires: 2033-05-18T03:33:20+00:00\ntrip/\n"
+        ));
+        assert!(exported.contains("# source: settings\n# disabled: *.gif\n"));
+
+        // 書き出したものを置き換えで読み込むと、パターン・期限・ラベル・止めているかが元に戻る
+        let (imported, report) = plan_import(&[], &exported, true, 1_800_000_000);
+        let restored: Vec<_> = imported
+            .iter()
+            .map(|r| {
+                (
+                    r.pattern.as_str(),
+                    r.expires_at,
+                    r.label.as_deref(),
+                    r.enabled,
+                )
+            })
+            .collect();
+        assert_eq!(
+            restored,
+            vec![
+                ("**/.thumbnails/", None, None, true),
+                ("trip/", Some(2_000_000_000), Some("旅行"), true),
+                ("*.gif", None, None, false),
+            ]
+        );
+        assert_eq!(report.added, 3);
+        assert!(report.invalid.is_empty());
+
+        // 追加では、同じルールは数えるだけ、期限違いと打ち消しは衝突、読めない行は invalid
+        let content = "**/.thumbnails/\n# expires: 2030-01-01T00:00:00Z\ntrip/\n!**/.thumbnails/\n\
+                       a[b\n# expires: someday\n*.png\n# expires: 2000-01-01T00:00:00Z\nold/\n*.png\n";
+        let (added, report) = plan_import(&rules, content, false, 1_800_000_000);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].pattern, "*.png");
+        assert_eq!(added[0].expires_at, None);
+        assert_eq!(report.unchanged, 1);
+        let lines = |issues: &[ImportIssue]| issues.iter().map(|i| i.line).collect::<Vec<_>>();
+        assert_eq!(lines(&report.conflicts), vec![3, 4]);
+        assert_eq!(lines(&report.invalid), vec![5, 6, 9]);
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("**/thumbs/").is_ok());
+        assert!(validate_pattern("!keep[0-9].png").is_ok());
+
+        let position = |pattern: &str| validate_pattern(pattern).unwrap_err().position;
+        assert_eq!(position("photos/[abc"), Some(7));
+        assert_eq!(position("a{b,c"), Some(1));
+        assert_eq!(position("a}b"), Some(1));
+        assert_eq!(position("x[z-a]"), Some(2));
+        assert_eq!(position("  []x"), Some(2));
+        assert_eq!(position("# comment"), None);
+        assert!(validate_pattern("a[b")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid pattern at position 1: "));
+    }
+
+    #[test]
+    fn test_literal_pattern() {
+        let file = literal_pattern("/photos/a[1].jpg");
+        let directory = format!("{}/*", literal_pattern("/photos/{x,y} *"));
+        assert!(validate_pattern(&file).is_ok());
+        assert!(validate_pattern(&directory).is_ok());
+
+        // 記号は字義どおりにマッチし、ほかの写真を巻き込まない
+        let f = filter(&[&file, &directory]);
+        assert!(f.is_ignored(Path::new("/photos/a[1].jpg")));
+        assert!(!f.is_ignored(Path::new("/photos/a1.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/{x,y} */b.jpg")));
+        assert!(!f.is_ignored(Path::new("/photos/x 2024/b.jpg")));
+    }
+
+    #[test]
+    fn test_anchored_pattern() {
+        let root = Some(Path::new("/photos"));
+        assert_eq!(
+            anchored_pattern(Path::new("/photos/trip/a.jpg"), root),
+            "/trip/a.jpg"
+        );
+        assert_eq!(anchored_pattern(Path::new("/photos/trip"), root), "/trip");
+        assert_eq!(anchored_pattern(Path::new("/photos"), root), "");
+        assert_eq!(
+            anchored_pattern(Path::new("/photos2/a*.jpg"), root),
+            "/photos2/a[*].jpg"
+        );
+        assert_eq!(
+            anchored_pattern(Path::new("/photos/a.jpg"), None),
+            "/photos/a.jpg"
+        );
+
+        // ルートからの固定パターンは、ライブラリを移した先でも同じ写真に当たる
+        let pattern = anchored_pattern(Path::new("/photos/trip/a.jpg"), root);
+        let moved = IgnoreFilter::from_patterns(&[pattern]).with_root(Path::new("/mnt/backup"));
+        assert!(moved.is_ignored(Path::new("/mnt/backup/trip/a.jpg")));
+        assert!(!moved.is_ignored(Path::new("/mnt/backup/a.jpg")));
+    }
+
+    #[test]
+    fn test_check_rules() {
+        let root = Path::new("/photos");
+        let entries = vec![
+            (PathBuf::from("/photos/2024"), true),
+            (PathBuf::from("/photos/2024/a.jpg"), false),
+            (PathBuf::from("/photos/2024/keep.png"), false),
+        ];
+        let patterns: Vec<String> = ["2024/", "*.png", "!keep.png", "trip/", "*.gif", "a[b"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+
+        let health = check_rules(&patterns, root, &entries);
+        let summary: Vec<(&str, bool)> = health
+            .iter()
+            .map(|h| {
+                (
+                    h.pattern.as_str(),
+                    matches!(h.problem, RuleProblem::Invalid { .. }),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("trip/", false), ("*.gif", false), ("a[b", true)]
+        );
+    }
+
+    #[test]
+    fn test_preview_pattern() {
+        let existing = vec!["*.tmp.jpg".to_string(), "old/".to_string()];
+        let paths: Vec<String> = [
+            "/photos/a.png",
+            "/photos/b.png",
+            "/photos/c.jpg",
+            "/photos/old/d.png",
+            "/photos/e.tmp.jpg",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+
+        let preview =
+            preview_pattern(&existing, "*.png", Some(Path::new("/photos")), &paths, 1).unwrap();
+        assert_eq!(preview.total_files, 5);
+        assert_eq!(preview.matched_count, 3);
+        assert_eq!(preview.excluded_count, 2);
+        assert_eq!(preview.already_excluded_count, 1);
+        assert_eq!(preview.sample, vec!["/photos/a.png".to_string()]);
+        assert_eq!(
+            preview.covering_rules,
+            vec![RuleCoverage {
+                pattern: "old/".to_string(),
+                count: 1
+            }]
+        );
+
+        assert!(preview_pattern(&existing, "# comment", None, &paths, 10).is_err());
+        assert!(preview_pattern(&existing, "[", None, &paths, 10).is_err());
+    }
+
+    #[test]
+    fn test_legacy_patterns() {
+        // exclude_image が保存した絶対パス・旧既定ルールの `**/.**/`
+        let f = filter(&[
+            "/photos/2024/a.jpg",
+            "/photos/trip/*",
+            "**/.**/",
+            "/other/b.jpg",
+        ]);
+        assert!(f.is_ignored(Path::new("/photos/2024/a.jpg")));
+        assert!(!f.is_ignored(Path::new("/photos/2024/b.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/trip/x.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/trip/day1/x.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/.cache/x.jpg")));
+        // ルート外のパスはファイルシステムのルートから判定する
+        assert!(f.is_ignored(Path::new("/other/b.jpg")));
+    }
+
+    #[test]
+    fn test_path_normalization() {
+        // NFD のファイル名（macOS の HFS+ が返す形）も NFC のパターンにマッチする
+        let f = filter(&["/photos/Am\u{e9}lie/", "caf\u{e9}.jpg"]);
+        assert!(f.is_ignored(Path::new("/photos/Ame\u{301}lie/a.jpg")));
+        assert!(f.is_ignored(Path::new("/photos/trip/cafe\u{301}.jpg")));
+
+        // Windows で保存した絶対パスのパターンもルートからの相対パスとして読む
+        let f = IgnoreFilter::from_patterns(&[r"c:\Photos\trip\*".to_string()])
+            .with_root(Path::new(r"C:\Photos"));
+        assert!(f.is_ignored(Path::new("C:/Photos/trip/a.jpg")));
+
+        // 大文字小文字は CasePolicy に従う
+        let f = filter(&["/RAW", "*.PNG"]);
+        let sensitive = f.clone().with_case_policy(CasePolicy::Sensitive);
+        assert!(!sensitive.is_ignored(Path::new("/photos/raw/a.jpg")));
+        assert!(!sensitive.is_ignored(Path::new("/photos/a.png")));
+        let insensitive = f.with_case_policy(CasePolicy::Insensitive);
+        assert!(insensitive.is_ignored(Path::new("/photos/raw/a.jpg")));
+        assert!(insensitive.is_ignored(Path::new("/photos/a.png")));
+        assert!(!insensitive.is_ignored(Path::new("/photos/raw2/a.jpg")));
+        let insensitive = insensitive.with_root(Path::new("/PHOTOS"));
+        assert!(insensitive.is_ignored(Path::new("/photos/raw/a.jpg")));
+    }
 }
@@ -1,4 +1,13 @@
-use image::{imageops::FilterType, GenericImageView, ImageFormat};
+use crate::capture_date::CaptureDate;
+use crate::geocode::Location;
+use crate::tone_mapping::{tone_map, ToneMapping};
+use crate::video_metadata::VideoMetadata;
+use crate::xmp::XmpMetadata;
+use image::{
+    imageops::FilterType, DynamicImage, GenericImageView, ImageBuffer, ImageDecoder, ImageFormat,
+    ImageReader, Pixel,
+};
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformExecutor, TransformOptions};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufReader;
@@ -8,17 +17,86 @@ use std::path::Path;
 pub const MAX_WIDTH_4K: u32 = 3840;
 pub const MAX_HEIGHT_4K: u32 = 2160;
 
+/// 注目点解析に使う縮小画像の長辺
+const FOCAL_ANALYSIS_SIZE: u32 = 128;
+
+/// 注目点（画像の幅・高さに対する 0.0〜1.0 の相対座標。EXIF 回転適用後の向きで表す）
+/// フロントエンドは CSS の object-position にそのまま使える
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocalPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// サムネイルの既定サイズ（長辺または正方形の一辺）
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// サムネイルの切り出し方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThumbnailMode {
+    /// 中央を正方形に切り抜く（グリッド表示向け）
+    Square,
+    /// アスペクト比を保って size×size の枠に収める
+    Fit,
+}
+
 /// EXIF情報
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 欠けている項目は下記の順に代替タグを探し、どれもなければ None
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExifInfo {
+    /// 撮影日時 "YYYY-MM-DD HH:MM:SS"（カメラの現地時刻）
+    /// DateTimeOriginal → DateTimeDigitized → DateTime の順に探す
     pub date_time: Option<String>,
+    /// date_time をどのタグから取ったか
+    pub date_time_source: Option<CaptureTimeSource>,
+    /// 撮影日時の ISO 8601 表記。秒未満（SubSecTime*）と UTC オフセット（OffsetTime*）は
+    /// date_time と同じ系統のタグにある場合だけ付く（例: "2024-01-15T10:30:00.120+09:00"）
+    pub capture_time: Option<String>,
+    /// 撮影時の UTC オフセット "+09:00"（記録がなければ None。端末のタイムゾーンは仮定しない）
+    pub utc_offset: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// LensModel（なければ None。LensMake だけでは機種が分からないので使わない）
+    pub lens_model: Option<String>,
+    /// 焦点距離（mm）
+    pub focal_length: Option<f64>,
+    /// 35mm 判換算の焦点距離（mm）
+    pub focal_length_35mm: Option<u32>,
+    /// F値。FNumber → ApertureValue（APEX）
+    pub f_number: Option<f64>,
+    /// 露出時間（秒）。ExposureTime → ShutterSpeedValue（APEX）
+    pub exposure_time: Option<f64>,
+    /// ISO感度。PhotographicSensitivity → ISOSpeed
+    pub iso: Option<u32>,
+    /// フラッシュが発光したか（Flash タグの bit 0）
+    pub flash_fired: Option<bool>,
     pub gps_latitude: Option<f64>,
     pub gps_longitude: Option<f64>,
+    /// 海抜高度（m）。GPSAltitudeRef が 1 なら海面下として負の値
+    pub gps_altitude: Option<f64>,
+    /// 撮影方向（度、GPSImgDirection）
+    pub gps_bearing: Option<f64>,
+    /// gps_latitude / gps_longitude / gps_altitude が EXIF ではなく GPX トラックログから推定したものか
+    #[serde(default)]
+    pub gps_inferred: bool,
     pub width: Option<u32>,
     pub height: Option<u32>,
 }
 
+/// 撮影日時の出どころ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureTimeSource {
+    /// DateTimeOriginal（シャッターを切った時刻）
+    Original,
+    /// DateTimeDigitized（デジタル化した時刻。フィルムスキャン等では撮影時刻と異なる）
+    Digitized,
+    /// DateTime（ファイルを最後に変更した時刻。編集ソフトで書き換わる）
+    Modified,
+}
+
 /// 画像情報
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,20 +110,166 @@ pub struct ImageInfo {
     pub exif: Option<ExifInfo>,
     pub display_count: i32,
     pub last_displayed: Option<String>,
+    pub focal_point: Option<FocalPoint>, // 塗りつぶし表示やパンで中心に据える注目点
+    pub letterboxed: bool,               // optimized_path がぼかし背景付きの全画面合成画像か
+    pub frame_count: Option<u32>,        // アニメーション画像のフレーム数（静止画は None）
+    pub animation_duration_ms: Option<u64>, // アニメーション1ループの再生時間
+    pub video: Option<VideoMetadata>,    // 動画の再生時間・回転・作成日時（画像は None）
+    pub poster_path: Option<String>,     // 動画のポスターフレーム（未生成・ffmpeg なしは None）
+    pub capture_date: Option<CaptureDate>, // 撮影日時と出どころ（EXIF がなければファイル名・更新日時等）
+    pub xmp: Option<XmpMetadata>, // レーティング・キーワード・キャプション・ラベル（何もなければ None）
+    pub location: Option<Location>, // GPS 座標から引いた撮影地（GPS なし・近くに地名なしは None）
+}
+
+/// ぼかし背景を作るときの縮小率（縮小してからぼかすことで大きな半径のぼかしを安く得る）
+const LETTERBOX_BLUR_DOWNSCALE: u32 = 16;
+/// 縮小後の背景にかけるガウスぼかしの強さ
+const LETTERBOX_BLUR_SIGMA: f32 = 4.0;
+/// 背景の明るさ（写真本体を引き立たせるため少し暗くする。0〜256）
+const LETTERBOX_BACKGROUND_BRIGHTNESS: u16 = 160;
+
+/// 表示先のアスペクト比（幅:高さ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
 }
 
-/// 画像を最適化（EXIF回転適用 + 4Kリサイズ）
-pub fn optimize_image_for_4k(image_path: &Path, apply_rotation: bool) -> Result<Vec<u8>, String> {
-    // 画像を読み込む
-    let img = image::open(image_path).map_err(|e| format!("Failed to open image: {e}"))?;
+impl AspectRatio {
+    /// "16:9" / "1920x1080" 形式の文字列を解釈する（約分して保持）
+    pub fn parse(value: &str) -> Option<Self> {
+        let (w, h) = value.trim().split_once([':', 'x'])?;
+        let width: u32 = w.trim().parse().ok()?;
+        let height: u32 = h.trim().parse().ok()?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let divisor = gcd(width, height);
+        Some(Self {
+            width: width / divisor,
+            height: height / divisor,
+        })
+    }
 
-    // EXIF Orientationに基づいて回転・反転を適用（リサイズ前）
-    let img = if apply_rotation {
-        apply_exif_orientation(image_path, img)
+    /// (width, height) の画像をちょうど収める、このアスペクト比の最小キャンバス
+    fn canvas_for(&self, width: u32, height: u32) -> (u32, u32) {
+        let (aw, ah) = (self.width as u64, self.height as u64);
+        let (w, h) = (width as u64, height as u64);
+        if w * ah >= h * aw {
+            // 画像の方が横長 → 上下に余白
+            (width, (w * ah).div_ceil(aw) as u32)
+        } else {
+            ((h * aw).div_ceil(ah) as u32, height)
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// 画像を最適化（EXIF回転適用 + 高ビット深度の自動トーンマッピング + 4Kリサイズ）
+/// `letterbox` を指定すると、そのアスペクト比のぼかし背景付き全画面合成画像にする
+pub fn optimize_image_for_4k(
+    image_path: &Path,
+    apply_rotation: bool,
+    letterbox: Option<AspectRatio>,
+) -> Result<Vec<u8>, String> {
+    let img = tone_map(load_image(image_path, apply_rotation)?, ToneMapping::Auto);
+    match letterbox {
+        Some(aspect) => encode_for_4k(compose_blurred_letterbox(img, aspect)),
+        None => encode_for_4k(img),
+    }
+}
+
+/// 黒帯の代わりに、写真自体を拡大して強くぼかした背景の中央に写真を重ねた画像を作る
+/// キャンバスは写真を縮小せずに収まる最小サイズ（4K を超える場合は 4K に収まるよう縮小）
+pub fn compose_blurred_letterbox(img: DynamicImage, aspect: AspectRatio) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let (canvas_w, canvas_h) = aspect.canvas_for(width, height);
+    if (canvas_w, canvas_h) == (width, height) {
+        // アスペクト比が一致していれば余白はない
+        return img;
+    }
+
+    let scale = (MAX_WIDTH_4K as f64 / canvas_w as f64)
+        .min(MAX_HEIGHT_4K as f64 / canvas_h as f64)
+        .min(1.0);
+    let canvas_w = ((canvas_w as f64 * scale).round() as u32).max(1);
+    let canvas_h = ((canvas_h as f64 * scale).round() as u32).max(1);
+    let foreground = if scale < 1.0 {
+        img.resize(canvas_w, canvas_h, FilterType::Lanczos3)
     } else {
         img
     };
 
+    // 背景: 縮小 → ぼかし → 暗く → キャンバスサイズに拡大
+    let mut background = foreground
+        .resize_to_fill(
+            (canvas_w / LETTERBOX_BLUR_DOWNSCALE).max(1),
+            (canvas_h / LETTERBOX_BLUR_DOWNSCALE).max(1),
+            FilterType::Triangle,
+        )
+        .blur(LETTERBOX_BLUR_SIGMA)
+        .to_rgba8();
+    for pixel in background.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = (*channel as u16 * LETTERBOX_BACKGROUND_BRIGHTNESS / 256) as u8;
+        }
+        pixel.0[3] = 255;
+    }
+    let mut canvas = image::imageops::resize(&background, canvas_w, canvas_h, FilterType::Triangle);
+
+    let (fg_w, fg_h) = foreground.dimensions();
+    image::imageops::overlay(
+        &mut canvas,
+        &foreground.to_rgba8(),
+        ((canvas_w - fg_w) / 2) as i64,
+        ((canvas_h - fg_h) / 2) as i64,
+    );
+
+    DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8())
+}
+
+/// 画像を読み込む
+///   - 埋め込み ICC プロファイル（JPEG APP2 / PNG iCCP / WebP ICCP）があれば sRGB に変換する
+///     （JPEG への再エンコードでプロファイルが落ち、広色域の写真がくすんで見えるのを防ぐ）
+///   - apply_rotation=true なら EXIF Orientation に基づいて回転・反転を適用
+pub fn load_image(image_path: &Path, apply_rotation: bool) -> Result<DynamicImage, String> {
+    let mut decoder = ImageReader::open(image_path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("Failed to open image: {e}"))?
+        .into_decoder()
+        .map_err(|e| format!("Failed to open image: {e}"))?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let img =
+        DynamicImage::from_decoder(decoder).map_err(|e| format!("Failed to open image: {e}"))?;
+
+    let img = match icc_profile {
+        Some(icc) => match convert_to_srgb(&img, &icc) {
+            Ok(converted) => converted,
+            Err(e) => {
+                // 壊れた・未対応のプロファイルは無視して元の画素をそのまま使う
+                eprintln!("Skipped ICC conversion for {}: {e}", image_path.display());
+                img
+            }
+        },
+        None => img,
+    };
+
+    if apply_rotation {
+        Ok(apply_exif_orientation(image_path, img))
+    } else {
+        Ok(img)
+    }
+}
+
+/// デコード済みの画像を 4K に収まるよう縮小し、JPEG にエンコード
+pub fn encode_for_4k(img: DynamicImage) -> Result<Vec<u8>, String> {
     let (width, height) = img.dimensions();
 
     // 4K解像度を超える場合はリサイズ
@@ -64,19 +288,159 @@ pub fn optimize_image_for_4k(image_path: &Path, apply_rotation: bool) -> Result<
     Ok(buffer)
 }
 
+/// サムネイルを生成（EXIF回転適用 + 縮小、JPEGで返す）
+pub fn generate_thumbnail(
+    image_path: &Path,
+    size: u32,
+    mode: ThumbnailMode,
+    apply_rotation: bool,
+) -> Result<Vec<u8>, String> {
+    let img = load_image(image_path, apply_rotation)?;
+
+    let thumbnail = match mode {
+        ThumbnailMode::Square => img.resize_to_fill(size, size, FilterType::Triangle),
+        ThumbnailMode::Fit => img.thumbnail(size, size),
+    };
+
+    // 16bit / HDR は縮小後に自動トーンマッピング（ライブラリ設定は表示用キャッシュのみに適用）
+    let thumbnail = tone_map(thumbnail, ToneMapping::Auto);
+
+    // JPEG はアルファを持てないので RGB に落としてからエンコード（品質85%）
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, 85)
+        .encode_image(&thumbnail.to_rgb8())
+        .map_err(|e| format!("Failed to encode thumbnail: {e}"))?;
+
+    Ok(buffer)
+}
+
+/// ICC プロファイルで表された画素を sRGB に変換する
+/// RGB プロファイルのみ対応（グレー・CMYK はデコーダ側の変換結果をそのまま使う）
+/// 16bit 以上の画像は 16bit のまま変換し、後段の階調処理に精度を残す
+fn convert_to_srgb(img: &DynamicImage, icc: &[u8]) -> Result<DynamicImage, String> {
+    let source = ColorProfile::new_from_slice(icc).map_err(|e| format!("Invalid ICC: {e}"))?;
+    if source.color_space != DataColorSpace::Rgb {
+        return Err(format!(
+            "Unsupported ICC color space: {:?}",
+            source.color_space
+        ));
+    }
+
+    let srgb = ColorProfile::new_srgb();
+    let options = TransformOptions::default();
+    let has_alpha = img.color().has_alpha();
+    let layout = if has_alpha { Layout::Rgba } else { Layout::Rgb };
+    let cms_error = |e: moxcms::CmsError| format!("Failed to convert ICC: {e}");
+
+    if img.color().bytes_per_pixel() / img.color().channel_count() > 1 {
+        let transform = source
+            .create_transform_16bit(layout, &srgb, layout, options)
+            .map_err(cms_error)?;
+        if has_alpha {
+            transform_pixels(img.to_rgba16(), transform.as_ref()).map(DynamicImage::ImageRgba16)
+        } else {
+            transform_pixels(img.to_rgb16(), transform.as_ref()).map(DynamicImage::ImageRgb16)
+        }
+    } else {
+        let transform = source
+            .create_transform_8bit(layout, &srgb, layout, options)
+            .map_err(cms_error)?;
+        if has_alpha {
+            transform_pixels(img.to_rgba8(), transform.as_ref()).map(DynamicImage::ImageRgba8)
+        } else {
+            transform_pixels(img.to_rgb8(), transform.as_ref()).map(DynamicImage::ImageRgb8)
+        }
+    }
+}
+
+/// 画素バッファ全体に色変換を適用する
+fn transform_pixels<P: Pixel>(
+    buffer: ImageBuffer<P, Vec<P::Subpixel>>,
+    transform: &(dyn TransformExecutor<P::Subpixel> + Send + Sync),
+) -> Result<ImageBuffer<P, Vec<P::Subpixel>>, String>
+where
+    P::Subpixel: Default,
+{
+    let (width, height) = buffer.dimensions();
+    let source = buffer.into_raw();
+    let mut converted = vec![P::Subpixel::default(); source.len()];
+    transform
+        .transform(&source, &mut converted)
+        .map_err(|e| format!("Failed to convert ICC: {e}"))?;
+    ImageBuffer::from_raw(width, height, converted)
+        .ok_or_else(|| "Failed to convert ICC: buffer size mismatch".to_string())
+}
+
+/// 画像の注目点を求める
+/// `oriented` が false（EXIF 回転未適用の画像）の場合は、縮小してから回転を適用して向きを揃える
+pub fn detect_focal_point(image_path: &Path, img: &DynamicImage, oriented: bool) -> FocalPoint {
+    let small = img.thumbnail(FOCAL_ANALYSIS_SIZE, FOCAL_ANALYSIS_SIZE);
+    let small = if oriented {
+        small
+    } else {
+        apply_exif_orientation(image_path, small)
+    };
+    compute_focal_point(&small)
+}
+
+/// エッジの強さ（輝度勾配）を重みとした重心を注目点とする
+/// 平均以下の勾配はノイズや平坦な背景として無視し、中央寄りをやや優先する
+fn compute_focal_point(img: &DynamicImage) -> FocalPoint {
+    let center = FocalPoint { x: 0.5, y: 0.5 };
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return center;
+    }
+
+    // 中央差分による勾配の大きさ
+    let mut energy = Vec::with_capacity(((width - 2) * (height - 2)) as usize);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let dx = luma.get_pixel(x + 1, y)[0] as f32 - luma.get_pixel(x - 1, y)[0] as f32;
+            let dy = luma.get_pixel(x, y + 1)[0] as f32 - luma.get_pixel(x, y - 1)[0] as f32;
+            energy.push((x, y, dx.abs() + dy.abs()));
+        }
+    }
+    let mean = energy.iter().map(|&(_, _, e)| e).sum::<f32>() / energy.len() as f32;
+
+    let (mut sum_w, mut sum_x, mut sum_y) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y, e) in energy {
+        if e <= mean {
+            continue;
+        }
+        let nx = (x as f32 + 0.5) / width as f32;
+        let ny = (y as f32 + 0.5) / height as f32;
+        // 中央バイアス: 四隅で重みが半分になる程度
+        let offset = ((nx - 0.5) * 2.0).powi(2) + ((ny - 0.5) * 2.0).powi(2);
+        let weight = (e - mean) * (1.0 - 0.25 * offset);
+        sum_w += weight;
+        sum_x += weight * nx;
+        sum_y += weight * ny;
+    }
+
+    if sum_w <= f32::EPSILON {
+        return center;
+    }
+    FocalPoint {
+        x: sum_x / sum_w,
+        y: sum_y / sum_w,
+    }
+}
+
+/// EXIF Orientationタグの値を読み取る（EXIFなし・タグなしは None）
+fn read_exif_orientation(image_path: &Path) -> Option<u32> {
+    let file = File::open(image_path).ok()?;
+    let mut buf_reader = BufReader::new(file);
+    let exif_reader = exif::Reader::new();
+    let exif = exif_reader.read_from_container(&mut buf_reader).ok()?;
+    let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    field.value.get_uint(0)
+}
+
 /// EXIF Orientationタグを読み取り、画像に回転・反転を適用する
-fn apply_exif_orientation(image_path: &Path, img: image::DynamicImage) -> image::DynamicImage {
-    // ファイルを開いてEXIFを読み取る
-    let orientation = (|| -> Option<u32> {
-        let file = File::open(image_path).ok()?;
-        let mut buf_reader = BufReader::new(file);
-        let exif_reader = exif::Reader::new();
-        let exif = exif_reader.read_from_container(&mut buf_reader).ok()?;
-        let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
-        field.value.get_uint(0)
-    })();
-
-    match orientation {
+fn apply_exif_orientation(image_path: &Path, img: DynamicImage) -> DynamicImage {
+    match read_exif_orientation(image_path) {
         Some(1) | None => img,             // そのまま（変換不要 / EXIFなし）
         Some(2) => img.fliph(),            // 水平反転
         Some(3) => img.rotate180(),        // 180度回転
@@ -89,11 +453,18 @@ fn apply_exif_orientation(image_path: &Path, img: image::DynamicImage) -> image:
     }
 }
 
-/// 画像の基本情報を取得
+/// 画像の寸法を取得（ヘッダのみ読み取り、ピクセルはデコードしない）
+/// EXIF Orientation が 5〜8（90度回転を含む）の場合は幅と高さを入れ替えた表示上の寸法を返す
 pub fn get_image_dimensions(image_path: &Path) -> Result<(u32, u32), String> {
-    let img = image::open(image_path).map_err(|e| format!("Failed to open image: {e}"))?;
+    let (width, height) = image::ImageReader::open(image_path)
+        .map_err(|e| format!("Failed to open image: {e}"))?
+        .into_dimensions()
+        .map_err(|e| format!("Failed to read image dimensions: {e}"))?;
 
-    Ok(img.dimensions())
+    match read_exif_orientation(image_path) {
+        Some(5..=8) => Ok((height, width)),
+        _ => Ok((width, height)),
+    }
 }
 
 /// EXIF情報を取得
@@ -103,77 +474,192 @@ pub fn get_exif_info(image_path: &Path) -> Result<ExifInfo, String> {
     let mut buf_reader = BufReader::new(file);
     let exif_reader = exif::Reader::new();
 
-    match exif_reader.read_from_container(&mut buf_reader) {
-        Ok(exif) => {
-            let mut info = ExifInfo {
-                date_time: None,
-                gps_latitude: None,
-                gps_longitude: None,
-                width: None,
-                height: None,
-            };
-
-            // 撮影日時
-            if let Some(field) = exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY) {
-                info.date_time = Some(field.display_value().to_string());
-            }
+    let Ok(exif) = exif_reader.read_from_container(&mut buf_reader) else {
+        // EXIF情報がない場合は空の情報を返す
+        return Ok(ExifInfo::default());
+    };
 
-            // GPS座標の取得
-            // 緯度
-            if let Some(lat_field) = exif.get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY) {
-                if let Some(lat_ref_field) =
-                    exif.get_field(exif::Tag::GPSLatitudeRef, exif::In::PRIMARY)
-                {
-                    if let Some(latitude) = parse_gps_coordinate(
-                        &lat_field.value,
-                        &lat_ref_field.display_value().to_string(),
-                    ) {
-                        info.gps_latitude = Some(latitude);
-                    }
-                }
-            }
+    let mut info = ExifInfo::default();
 
-            // 経度
-            if let Some(lon_field) = exif.get_field(exif::Tag::GPSLongitude, exif::In::PRIMARY) {
-                if let Some(lon_ref_field) =
-                    exif.get_field(exif::Tag::GPSLongitudeRef, exif::In::PRIMARY)
-                {
-                    if let Some(longitude) = parse_gps_coordinate(
-                        &lon_field.value,
-                        &lon_ref_field.display_value().to_string(),
-                    ) {
-                        info.gps_longitude = Some(longitude);
-                    }
-                }
-            }
+    // 撮影日時（秒未満・UTC オフセットは同じ系統のタグから取る）
+    let date_tags = [
+        (
+            CaptureTimeSource::Original,
+            exif::Tag::DateTimeOriginal,
+            exif::Tag::SubSecTimeOriginal,
+            exif::Tag::OffsetTimeOriginal,
+        ),
+        (
+            CaptureTimeSource::Digitized,
+            exif::Tag::DateTimeDigitized,
+            exif::Tag::SubSecTimeDigitized,
+            exif::Tag::OffsetTimeDigitized,
+        ),
+        (
+            CaptureTimeSource::Modified,
+            exif::Tag::DateTime,
+            exif::Tag::SubSecTime,
+            exif::Tag::OffsetTime,
+        ),
+    ];
+    for (source, date_tag, subsec_tag, offset_tag) in date_tags {
+        let Some(mut date_time) =
+            ascii_bytes(&exif, date_tag).and_then(|data| exif::DateTime::from_ascii(data).ok())
+        else {
+            continue;
+        };
+        if let Some(data) = ascii_bytes(&exif, subsec_tag) {
+            let _ = date_time.parse_subsec(data);
+        }
+        if let Some(data) = ascii_bytes(&exif, offset_tag) {
+            let _ = date_time.parse_offset(data);
+        }
 
-            // 画像サイズ
-            if let Some(field) = exif.get_field(exif::Tag::PixelXDimension, exif::In::PRIMARY) {
-                if let Some(width) = field.value.get_uint(0) {
-                    info.width = Some(width);
-                }
+        info.date_time = Some(format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            date_time.year,
+            date_time.month,
+            date_time.day,
+            date_time.hour,
+            date_time.minute,
+            date_time.second
+        ));
+        info.utc_offset = date_time.offset.map(format_utc_offset);
+        info.capture_time = Some(format_capture_time(&date_time));
+        info.date_time_source = Some(source);
+        break;
+    }
+
+    // カメラ・レンズ
+    info.camera_make = ascii_string(&exif, exif::Tag::Make);
+    info.camera_model = ascii_string(&exif, exif::Tag::Model);
+    info.lens_model = ascii_string(&exif, exif::Tag::LensModel);
+
+    // 撮影設定
+    info.focal_length = rational_value(&exif, exif::Tag::FocalLength);
+    info.focal_length_35mm = exif
+        .get_field(exif::Tag::FocalLengthIn35mmFilm, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .filter(|&mm| mm > 0);
+    info.f_number = rational_value(&exif, exif::Tag::FNumber)
+        .or_else(|| rational_value(&exif, exif::Tag::ApertureValue).map(|av| 2f64.powf(av / 2.0)));
+    info.exposure_time = rational_value(&exif, exif::Tag::ExposureTime)
+        .or_else(|| rational_value(&exif, exif::Tag::ShutterSpeedValue).map(|tv| 2f64.powf(-tv)));
+    info.iso = [exif::Tag::PhotographicSensitivity, exif::Tag::ISOSpeed]
+        .into_iter()
+        .filter_map(|tag| exif.get_field(tag, exif::In::PRIMARY))
+        .find_map(|field| field.value.get_uint(0))
+        .filter(|&iso| iso > 0);
+    info.flash_fired = exif
+        .get_field(exif::Tag::Flash, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .map(|flash| flash & 1 == 1);
+
+    // GPS座標の取得
+    // 緯度
+    if let Some(lat_field) = exif.get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY) {
+        if let Some(lat_ref_field) = exif.get_field(exif::Tag::GPSLatitudeRef, exif::In::PRIMARY) {
+            if let Some(latitude) =
+                parse_gps_coordinate(&lat_field.value, &lat_ref_field.display_value().to_string())
+            {
+                info.gps_latitude = Some(latitude);
             }
-            if let Some(field) = exif.get_field(exif::Tag::PixelYDimension, exif::In::PRIMARY) {
-                if let Some(height) = field.value.get_uint(0) {
-                    info.height = Some(height);
-                }
+        }
+    }
+
+    // 経度
+    if let Some(lon_field) = exif.get_field(exif::Tag::GPSLongitude, exif::In::PRIMARY) {
+        if let Some(lon_ref_field) = exif.get_field(exif::Tag::GPSLongitudeRef, exif::In::PRIMARY) {
+            if let Some(longitude) =
+                parse_gps_coordinate(&lon_field.value, &lon_ref_field.display_value().to_string())
+            {
+                info.gps_longitude = Some(longitude);
             }
+        }
+    }
+
+    // 高度（GPSAltitudeRef: 0 = 海抜、1 = 海面下）
+    if let Some(altitude) = rational_value(&exif, exif::Tag::GPSAltitude) {
+        let below_sea_level = exif
+            .get_field(exif::Tag::GPSAltitudeRef, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+            == Some(1);
+        info.gps_altitude = Some(if below_sea_level { -altitude } else { altitude });
+    }
+
+    // 撮影方向
+    info.gps_bearing = rational_value(&exif, exif::Tag::GPSImgDirection);
+
+    // 画像サイズ
+    if let Some(field) = exif.get_field(exif::Tag::PixelXDimension, exif::In::PRIMARY) {
+        if let Some(width) = field.value.get_uint(0) {
+            info.width = Some(width);
+        }
+    }
+    if let Some(field) = exif.get_field(exif::Tag::PixelYDimension, exif::In::PRIMARY) {
+        if let Some(height) = field.value.get_uint(0) {
+            info.height = Some(height);
+        }
+    }
+
+    Ok(info)
+}
+
+/// ASCII タグの生のバイト列（最初の文字列のみ）
+fn ascii_bytes(exif: &exif::Exif, tag: exif::Tag) -> Option<&[u8]> {
+    match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Ascii(values) => values.first().map(|v| v.as_slice()),
+        _ => None,
+    }
+}
 
-            Ok(info)
+/// ASCII タグを文字列で取得（末尾の NUL・空白を除き、空なら None）
+fn ascii_string(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    let text = String::from_utf8_lossy(ascii_bytes(exif, tag)?);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// 有理数タグを小数で取得（分母 0 の不正な値は None）
+fn rational_value(exif: &exif::Exif, tag: exif::Tag) -> Option<f64> {
+    match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Rational(values) => {
+            values.first().filter(|v| v.denom != 0).map(|v| v.to_f64())
         }
-        Err(_) => {
-            // EXIF情報がない場合は空の情報を返す
-            Ok(ExifInfo {
-                date_time: None,
-                gps_latitude: None,
-                gps_longitude: None,
-                width: None,
-                height: None,
-            })
+        exif::Value::SRational(values) => {
+            values.first().filter(|v| v.denom != 0).map(|v| v.to_f64())
         }
+        _ => None,
     }
 }
 
+/// UTC オフセット（分）を "+09:00" 形式にする
+fn format_utc_offset(offset: i16) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    format!("{sign}{:02}:{:02}", offset / 60, offset % 60)
+}
+
+/// 撮影日時を ISO 8601 にする（秒未満はミリ秒まで、オフセットは分かる場合のみ）
+fn format_capture_time(date_time: &exif::DateTime) -> String {
+    let mut text = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date_time.year,
+        date_time.month,
+        date_time.day,
+        date_time.hour,
+        date_time.minute,
+        date_time.second
+    );
+    if let Some(nanosecond) = date_time.nanosecond {
+        text.push_str(&format!(".{:03}", nanosecond / 1_000_000));
+    }
+    if let Some(offset) = date_time.offset {
+        text.push_str(&format_utc_offset(offset));
+    }
+    text
+}
+
 /// GPS座標をパースして10進数に変換
 fn parse_gps_coordinate(value: &exif::Value, reference: &str) -> Option<f64> {
     // GPS座標は度・分・秒の3つの有理数で表現される
@@ -216,9 +702,346 @@ pub fn is_video_file(path: &Path) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use image::{ImageEncoder, RgbImage};
+    use std::path::PathBuf;
+
+    /// テスト専用のユニークな一時ファイルパス
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sss_image_processor_{}_{name}", std::process::id()))
+    }
+
+    /// Orientation タグだけを持つ最小の EXIF (APP1) を SOI 直後に差し込んだ JPEG を作る
+    fn jpeg_with_orientation(width: u32, height: u32, orientation: u16) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+            .write_image(
+                RgbImage::new(width, height).as_raw(),
+                width,
+                height,
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+
+        // TIFF ヘッダ（ビッグエンディアン）+ IFD0（エントリ1件: Orientation, SHORT, 1）
+        let mut tiff = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        let mut payload = b"Exif\0\0".to_vec();
+        payload.extend_from_slice(&tiff);
+
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        app1.extend_from_slice(&payload);
+
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&app1);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn test_image_dimensions_from_header() {
+        let path = temp_path("plain.png");
+        RgbImage::new(40, 20).save(&path).unwrap();
+
+        assert_eq!(get_image_dimensions(&path), Ok((40, 20)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_generate_thumbnail_modes() {
+        let path = temp_path("wide.png");
+        image::RgbaImage::new(400, 100).save(&path).unwrap();
+
+        // Square は size×size に切り抜かれる
+        let square = generate_thumbnail(&path, 64, ThumbnailMode::Square, true).unwrap();
+        let square = image::load_from_memory(&square).unwrap();
+        assert_eq!(square.dimensions(), (64, 64));
+
+        // Fit はアスペクト比を保ったまま長辺が size になる
+        let fit = generate_thumbnail(&path, 64, ThumbnailMode::Fit, true).unwrap();
+        let fit = image::load_from_memory(&fit).unwrap();
+        assert_eq!(fit.dimensions(), (64, 16));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_focal_point_follows_detail() {
+        // 平坦なグレーの左上にだけ市松模様（細部）がある画像
+        let mut img = image::GrayImage::from_pixel(200, 100, image::Luma([128]));
+        for y in 10..40 {
+            for x in 10..50 {
+                let v = if (x / 3 + y / 3) % 2 == 0 { 0 } else { 255 };
+                img.put_pixel(x, y, image::Luma([v]));
+            }
+        }
+        let focal = compute_focal_point(&DynamicImage::ImageLuma8(img));
+        assert!(focal.x < 0.3, "x = {}", focal.x);
+        assert!(focal.y < 0.45, "y = {}", focal.y);
+
+        // 何もない画像は中央
+        let flat =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([90])));
+        assert_eq!(compute_focal_point(&flat), FocalPoint { x: 0.5, y: 0.5 });
+    }
+
+    #[test]
+    fn test_aspect_ratio_parse() {
+        let ratio = AspectRatio::parse("1920x1080").unwrap();
+        assert_eq!((ratio.width, ratio.height), (16, 9));
+        assert_eq!(AspectRatio::parse(" 4:3 "), AspectRatio::parse("800x600"));
+        assert_eq!(AspectRatio::parse("16:0"), None);
+        assert_eq!(AspectRatio::parse("wide"), None);
+    }
+
+    #[test]
+    fn test_blurred_letterbox_fills_display_aspect() {
+        let aspect = AspectRatio::parse("16:9").unwrap();
+
+        // 縦長の写真 → 横に余白。写真は縮小されず中央に置かれ、余白は黒ではない
+        let photo = image::RgbImage::from_pixel(90, 160, image::Rgb([200, 120, 40]));
+        let composite = compose_blurred_letterbox(DynamicImage::ImageRgb8(photo), aspect);
+        assert_eq!(composite.dimensions(), (285, 160));
+        assert_eq!(composite.get_pixel(142, 80).0[..3], [200, 120, 40]);
+        let edge = composite.get_pixel(2, 80);
+        assert!(edge.0[0] > 0 && edge.0[0] < 200, "edge = {edge:?}");
+
+        // 4K を超えるキャンバスは 4K に収める
+        let tall = image::RgbImage::new(1000, 4000);
+        let composite = compose_blurred_letterbox(DynamicImage::ImageRgb8(tall), aspect);
+        assert_eq!(composite.dimensions().1, MAX_HEIGHT_4K);
+
+        // アスペクト比が同じなら合成しない
+        let wide = image::RgbImage::new(160, 90);
+        let composite = compose_blurred_letterbox(DynamicImage::ImageRgb8(wide), aspect);
+        assert_eq!(composite.dimensions(), (160, 90));
+    }
+
+    /// 単色の画像を ICC プロファイル付きで書き出す
+    fn write_with_profile(path: &Path, rgb: [u8; 3], profile: Option<&ColorProfile>) {
+        let img = image::RgbImage::from_pixel(8, 8, image::Rgb(rgb));
+        let file = std::fs::File::create(path).unwrap();
+        let icc = profile.map(|p| p.encode().unwrap());
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => {
+                let mut encoder = image::codecs::png::PngEncoder::new(file);
+                if let Some(icc) = icc {
+                    encoder.set_icc_profile(icc).unwrap();
+                }
+                encoder
+                    .write_image(&img, 8, 8, image::ExtendedColorType::Rgb8)
+                    .unwrap();
+            }
+            Some("webp") => {
+                let mut encoder = image::codecs::webp::WebPEncoder::new_lossless(file);
+                if let Some(icc) = icc {
+                    encoder.set_icc_profile(icc).unwrap();
+                }
+                encoder
+                    .write_image(&img, 8, 8, image::ExtendedColorType::Rgb8)
+                    .unwrap();
+            }
+            _ => {
+                let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(file, 100);
+                if let Some(icc) = icc {
+                    encoder.set_icc_profile(icc).unwrap();
+                }
+                encoder
+                    .write_image(&img, 8, 8, image::ExtendedColorType::Rgb8)
+                    .unwrap();
+            }
+        }
+    }
+
     #[test]
-    fn test_image_dimensions() {
-        // テスト画像がないため、実際のテストはスキップ
-        // 実際の環境でテストする際は、テスト用の画像ファイルを用意
+    fn test_icc_profile_converted_to_srgb() {
+        // Adobe RGB の中間的な緑は、sRGB ではより彩度の高い値になる（そのまま扱うとくすむ）
+        let adobe = ColorProfile::new_adobe_rgb();
+        for name in ["adobe.png", "adobe.jpg", "adobe.webp"] {
+            let path = temp_path(name);
+            write_with_profile(&path, [100, 150, 100], Some(&adobe));
+            let pixel = load_image(&path, false)
+                .unwrap()
+                .to_rgb8()
+                .get_pixel(4, 4)
+                .0;
+            assert!(
+                pixel[1] as i32 - pixel[0] as i32 > 70,
+                "{name}: pixel = {pixel:?}"
+            );
+            let _ = std::fs::remove_file(&path);
+        }
+
+        // sRGB プロファイル付き・プロファイルなしは（JPEG の誤差を除き）変わらない
+        let srgb = ColorProfile::new_srgb();
+        for (name, profile) in [
+            ("srgb.png", Some(&srgb)),
+            ("srgb.webp", Some(&srgb)),
+            ("plain.png", None),
+        ] {
+            let path = temp_path(name);
+            write_with_profile(&path, [100, 150, 100], profile);
+            let pixel = load_image(&path, false)
+                .unwrap()
+                .to_rgb8()
+                .get_pixel(4, 4)
+                .0;
+            for (actual, expected) in pixel.iter().zip([100u8, 150, 100]) {
+                assert!(actual.abs_diff(expected) <= 1, "{name}: pixel = {pixel:?}");
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_image_dimensions_respect_exif_orientation() {
+        // Orientation 6（90度時計回り）は縦横が入れ替わる
+        let rotated = temp_path("rotated.jpg");
+        std::fs::write(&rotated, jpeg_with_orientation(32, 16, 6)).unwrap();
+        assert_eq!(get_image_dimensions(&rotated), Ok((16, 32)));
+
+        // Orientation 3（180度）は縦横そのまま
+        let upside_down = temp_path("upside_down.jpg");
+        std::fs::write(&upside_down, jpeg_with_orientation(32, 16, 3)).unwrap();
+        assert_eq!(get_image_dimensions(&upside_down), Ok((32, 16)));
+
+        let _ = std::fs::remove_file(&rotated);
+        let _ = std::fs::remove_file(&upside_down);
+    }
+
+    /// 指定した EXIF フィールドを埋め込んだ JPEG を作る
+    fn jpeg_with_exif(fields: &[exif::Field]) -> Vec<u8> {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+            .write_image(
+                RgbImage::new(8, 8).as_raw(),
+                8,
+                8,
+                image::ExtendedColorType::Rgb8,
+            )
+            .unwrap();
+
+        let mut payload = b"Exif\0\0".to_vec();
+        payload.extend_from_slice(tiff.get_ref());
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(&payload);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn exif_field(tag: exif::Tag, value: exif::Value) -> exif::Field {
+        exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value,
+        }
+    }
+
+    fn ascii(text: &str) -> exif::Value {
+        exif::Value::Ascii(vec![text.as_bytes().to_vec()])
+    }
+
+    fn rational(num: u32, denom: u32) -> exif::Value {
+        exif::Value::Rational(vec![exif::Rational { num, denom }])
+    }
+
+    #[test]
+    fn test_exif_capture_time_and_camera() {
+        use exif::Tag;
+
+        let fields = [
+            exif_field(Tag::Make, ascii("FUJIFILM")),
+            exif_field(Tag::Model, ascii("X-T5 ")),
+            exif_field(Tag::DateTime, ascii("2024:03:01 09:00:00")),
+            exif_field(Tag::DateTimeOriginal, ascii("2024:01:15 10:30:00")),
+            exif_field(Tag::SubSecTimeOriginal, ascii("12")),
+            exif_field(Tag::OffsetTimeOriginal, ascii("+09:00")),
+            exif_field(Tag::ExposureTime, rational(1, 250)),
+            exif_field(Tag::FNumber, rational(28, 10)),
+            exif_field(Tag::PhotographicSensitivity, exif::Value::Short(vec![400])),
+            exif_field(Tag::Flash, exif::Value::Short(vec![0x10])),
+            exif_field(Tag::FocalLength, rational(23, 1)),
+            exif_field(Tag::LensModel, ascii("XF23mmF1.4 R LM WR")),
+            exif_field(Tag::GPSAltitudeRef, exif::Value::Byte(vec![1])),
+            exif_field(Tag::GPSAltitude, rational(125, 10)),
+            exif_field(Tag::GPSImgDirection, rational(2705, 10)),
+        ];
+        let path = temp_path("exif_full.jpg");
+        std::fs::write(&path, jpeg_with_exif(&fields)).unwrap();
+
+        let info = get_exif_info(&path).unwrap();
+        // DateTime（変更日時）ではなく DateTimeOriginal を使い、秒未満とオフセットを付ける
+        assert_eq!(info.date_time.as_deref(), Some("2024-01-15 10:30:00"));
+        assert_eq!(info.date_time_source, Some(CaptureTimeSource::Original));
+        assert_eq!(
+            info.capture_time.as_deref(),
+            Some("2024-01-15T10:30:00.120+09:00")
+        );
+        assert_eq!(info.utc_offset.as_deref(), Some("+09:00"));
+        assert_eq!(info.camera_make.as_deref(), Some("FUJIFILM"));
+        assert_eq!(info.camera_model.as_deref(), Some("X-T5"));
+        assert_eq!(info.lens_model.as_deref(), Some("XF23mmF1.4 R LM WR"));
+        assert_eq!(info.focal_length, Some(23.0));
+        assert_eq!(info.f_number, Some(2.8));
+        assert_eq!(info.exposure_time, Some(0.004));
+        assert_eq!(info.iso, Some(400));
+        assert_eq!(info.flash_fired, Some(false));
+        assert_eq!(info.gps_altitude, Some(-12.5));
+        assert_eq!(info.gps_bearing, Some(270.5));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_exif_fallbacks() {
+        use exif::Tag;
+
+        // DateTimeOriginal がなければ DateTimeDigitized。オフセットは同じ系統のタグがなければ付けない
+        // F値・露出時間は APEX 値（ApertureValue / ShutterSpeedValue）から換算する
+        let fields = [
+            exif_field(Tag::DateTime, ascii("2024:03:01 09:00:00")),
+            exif_field(Tag::OffsetTime, ascii("+01:00")),
+            exif_field(Tag::DateTimeDigitized, ascii("2001:05:20 18:45:10")),
+            exif_field(Tag::ApertureValue, rational(4, 1)),
+            exif_field(
+                Tag::ShutterSpeedValue,
+                exif::Value::SRational(vec![exif::SRational { num: 7, denom: 1 }]),
+            ),
+            exif_field(Tag::Flash, exif::Value::Short(vec![0x19])),
+        ];
+        let path = temp_path("exif_fallback.jpg");
+        std::fs::write(&path, jpeg_with_exif(&fields)).unwrap();
+
+        let info = get_exif_info(&path).unwrap();
+        assert_eq!(info.date_time.as_deref(), Some("2001-05-20 18:45:10"));
+        assert_eq!(info.date_time_source, Some(CaptureTimeSource::Digitized));
+        assert_eq!(info.capture_time.as_deref(), Some("2001-05-20T18:45:10"));
+        assert_eq!(info.utc_offset, None);
+        assert_eq!(info.f_number, Some(4.0));
+        assert_eq!(info.exposure_time, Some(1.0 / 128.0));
+        assert_eq!(info.flash_fired, Some(true));
+        assert_eq!(info.camera_model, None);
+
+        // EXIF がなければ空
+        let plain = temp_path("exif_none.png");
+        RgbImage::new(4, 4).save(&plain).unwrap();
+        let info = get_exif_info(&plain).unwrap();
+        assert_eq!(info.date_time, None);
+        assert_eq!(info.date_time_source, None);
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&plain);
     }
 }
@@ -0,0 +1,117 @@
+//! 取り消しのための操作履歴（除外・ピック）。
+//!
+//! 「…」メニューや設定画面からの除外ルールの追加と、ピック・ピックの削除を DB の `action_journal` に
+//! JSON で記録し、最後の操作から順に取り消し（undo）・やり直し（redo）できるようにする。
+//! 元に戻すのに必要な情報（上書き前のルール、削除したピックの退避先など）は操作ごとに持たせる。
+//! 実際に DB やファイルを戻すのは commands/journal.rs。
+
+use crate::ignore::IgnoreRule;
+use crate::metadata_rule::MetadataRule;
+use serde::{Deserialize, Serialize};
+
+/// 記録しておく履歴の件数（古いものから捨てる）
+pub const JOURNAL_LIMIT: i64 = 100;
+
+/// 取り消せる操作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum JournalAction {
+    /// 除外ルールの追加（previous は同じパターンで上書きした元のルール。新規なら None）
+    AddIgnoreRule {
+        rule: Box<IgnoreRule>,
+        previous: Option<Box<IgnoreRule>>,
+    },
+    /// 撮影日などメタデータの除外ルールの追加（id はやり直しのたびに振り直される）
+    AddMetadataRule { id: i64, rule: MetadataRule },
+    /// ピック（source_path を picked_path にコピーした）
+    Pick {
+        source_path: String,
+        picked_path: String,
+    },
+    /// ピックの削除（取り消せるように trash_path へ退避した）
+    DeletePicked {
+        picked_path: String,
+        trash_path: String,
+    },
+}
+
+/// 記録済みの操作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: i64,
+    pub action: JournalAction,
+    /// 操作の要約（通知に出す）
+    pub summary: String,
+    /// 記録日時（DB の `created_at`、UTC の "YYYY-MM-DD HH:MM:SS"）
+    pub created_at: String,
+}
+
+impl JournalAction {
+    /// 通知に出す1行の要約
+    pub fn summary(&self) -> String {
+        match self {
+            JournalAction::AddIgnoreRule { rule, .. } => {
+                format!("除外パターン追加: {}", rule.pattern)
+            }
+            JournalAction::AddMetadataRule { rule, .. } => match rule {
+                MetadataRule::DateRange {
+                    from: Some(from),
+                    to: Some(to),
+                } if from == to => format!("撮影日の除外ルール追加: {from}"),
+                _ => "メタデータの除外ルール追加".to_string(),
+            },
+            JournalAction::Pick { picked_path, .. } => format!("ピック: {picked_path}"),
+            JournalAction::DeletePicked { picked_path, .. } => {
+                format!("ピックの削除: {picked_path}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore::RuleSource;
+
+    #[test]
+    fn test_journal_action_round_trip() {
+        let action = JournalAction::AddIgnoreRule {
+            rule: Box::new(IgnoreRule::new(
+                "/photos/trip/*",
+                Some(RuleSource::Menu {
+                    image_path: "/photos/trip/a.jpg".to_string(),
+                    exclude_type: "directory".to_string(),
+                }),
+            )),
+            previous: None,
+        };
+        let json = serde_json::to_string(&action).unwrap();
+        assert!(json.contains(r#""kind":"addIgnoreRule""#));
+        assert_eq!(
+            serde_json::from_str::<JournalAction>(&json).unwrap(),
+            action
+        );
+        assert_eq!(action.summary(), "除外パターン追加: /photos/trip/*");
+
+        let date = JournalAction::AddMetadataRule {
+            id: 3,
+            rule: MetadataRule::DateRange {
+                from: Some("2024-05-01".to_string()),
+                to: Some("2024-05-01".to_string()),
+            },
+        };
+        assert_eq!(date.summary(), "撮影日の除外ルール追加: 2024-05-01");
+
+        let delete = JournalAction::DeletePicked {
+            picked_path: "/picked/a.jpg".to_string(),
+            trash_path: "/trash/1_a.jpg".to_string(),
+        };
+        let json = serde_json::to_string(&delete).unwrap();
+        assert!(json.contains(r#""trashPath":"/trash/1_a.jpg""#));
+    }
+}
@@ -1,20 +1,31 @@
 //! sss バックエンドのライブラリ本体。
 //!
 //! Tauri アプリの起動 (`run`) と、スライドショーの芯となるモジュール群
-//! (scanner / playlist / ignore / image_processor / database / commands) を公開する。
+//! (scanner / playlist / ignore / image_processor / animation / database / commands) を公開する。
 //! `main.rs` (bin) はこの `run()` を呼ぶだけの薄い殻で、結合テスト
 //! (`tests/golden_e2e.rs`) はここで公開した芯を直接叩いて golden path を機械検証する。
 
+pub mod animation;
+pub mod capture_date;
 pub mod commands;
 pub mod database;
+pub mod geocode;
+pub mod gpx;
 pub mod ignore;
 pub mod image_processor;
+pub mod journal;
+pub mod metadata_rule;
+pub mod path_norm;
 pub mod playlist;
+pub mod poster;
 pub mod scanner;
+pub mod tone_mapping;
+pub mod video_metadata;
+pub mod xmp;
 
 use commands::AppState;
 use database::Database;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 /// Tauri アプリを起動する。
@@ -57,15 +68,36 @@ pub fn run() {
             }
             std::fs::create_dir_all(&cache_dir).expect("failed to create cache directory");
 
+            // サムネイルは永続キャッシュ（起動時にクリアしない）
+            let thumbnail_dir = app_data_dir.join("thumbnails");
+            std::fs::create_dir_all(&thumbnail_dir).expect("failed to create thumbnail directory");
+            // 元画像の更新で使われなくなった分などは、上限を超えたら古い順にバックグラウンドで消す
+            let prune_dir = thumbnail_dir.clone();
+            std::thread::spawn(move || {
+                commands::thumbnail::prune_thumbnail_cache(
+                    &prune_dir,
+                    commands::thumbnail::THUMBNAIL_CACHE_LIMIT,
+                );
+            });
+
+            // 削除したピックの退避先（取り消し用。履歴から落ちたものは消す）
+            let trash_dir = app_data_dir.join("trash");
+            std::fs::create_dir_all(&trash_dir).expect("failed to create trash directory");
+
             // データベースを初期化
             let db = Database::new(db_path).expect("failed to initialize database");
 
             // アプリケーション状態を設定
             app.manage(AppState {
-                db: Mutex::new(db),
+                db: Arc::new(Mutex::new(db)),
                 playlist: Mutex::new(None),
                 directory_path: Mutex::new(None),
                 cache_dir,
+                thumbnail_dir,
+                trash_dir,
+                // 起動時のスキャンは期限切れのルールを読み飛ばすので、それより前に切れた分は戻し済み
+                snoozes_checked_at: Mutex::new(chrono::Utc::now().timestamp()),
+                grid_paths: Mutex::new(None),
                 _keep_awake: keep_awake,
             });
 
@@ -90,10 +122,26 @@ pub fn run() {
             commands::file_operations::get_ignore_patterns,
             commands::file_operations::remove_ignore_pattern,
             commands::file_operations::add_ignore_pattern,
+            commands::file_operations::preview_ignore_pattern,
+            commands::file_operations::validate_ignore_pattern,
+            commands::file_operations::check_ignore_rules,
+            commands::file_operations::export_ignore_rules,
+            commands::file_operations::import_ignore_rules,
+            commands::file_operations::set_ignore_rule_enabled,
+            commands::file_operations::set_ignore_rule_label,
+            commands::journal::undo_action,
+            commands::journal::redo_action,
+            commands::file_operations::get_metadata_rules,
+            commands::file_operations::add_metadata_rule,
+            commands::file_operations::remove_metadata_rule,
             commands::file_operations::get_recent_images,
             commands::file_operations::get_picked_images,
             commands::file_operations::delete_picked_image,
             commands::file_operations::reset_all_display_counts,
+            commands::thumbnail::get_thumbnail_grid,
+            commands::gpx::import_gpx,
+            commands::gpx::get_gpx_tracks,
+            commands::gpx::remove_gpx_track,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
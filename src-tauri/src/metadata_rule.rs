@@ -0,0 +1,314 @@
+//! メタデータによる除外ルール（撮影日の範囲・カメラ機種・画素数・ファイルサイズ・種類・縦横比）。
+//!
+//! パスの glob（ignore.rs）では表せない条件を、スキャン時に DB へ索引したメタデータに対して判定する。
+//! ルールは DB の `metadata_rules` に JSON で保存し、プレイリストを組むときにどれか1つでも
+//! 当てはまったファイルを除く。判定に必要なメタデータが分からないファイル（撮影日不明・寸法が読めない等）は
+//! そのルールでは除外しない。
+
+use serde::{Deserialize, Serialize};
+
+/// メタデータの除外ルール（当てはまったファイルを除外する）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MetadataRule {
+    /// 撮影日がこの範囲（"YYYY-MM-DD"、両端を含む。None は上限・下限なし）
+    DateRange {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// カメラのメーカー名か機種名にこの文字列を含む（大文字小文字を区別しない）
+    Camera { model: String },
+    /// 画素数が足りない（短辺・長辺をそれぞれ比べるので縦横の向きは問わない）
+    MinDimensions { width: u32, height: u32 },
+    /// ファイルサイズがこの範囲の外（バイト。None は上限・下限なし）
+    FileSize {
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+    },
+    /// 画像か動画か
+    MediaType { kind: MediaKind },
+    /// 縦横比（幅 / 高さ）がこの範囲の外（None は上限・下限なし）
+    AspectRatio { min: Option<f64>, max: Option<f64> },
+}
+
+/// 保存済みのルール
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataRuleEntry {
+    pub id: i64,
+    pub rule: MetadataRule,
+}
+
+/// メディアの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+/// ルールの判定に使う、DB に索引済みのメタデータ
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaFacts {
+    pub is_video: bool,
+    pub file_size: u64,
+    /// 撮影日 "YYYY-MM-DD"（capture_date.rs で解決したもの）
+    pub capture_date: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// 表示上の寸法（回転を反映済み）
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl MetadataRule {
+    /// 保存できるルールか確かめる（日付の書式・範囲の向き・空の条件）
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            MetadataRule::DateRange { from, to } => {
+                for date in [from, to].into_iter().flatten() {
+                    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {date}"))?;
+                }
+                check_range(from.as_ref(), to.as_ref())
+            }
+            MetadataRule::Camera { model } => {
+                if model.trim().is_empty() {
+                    return Err("Camera model is empty".to_string());
+                }
+                Ok(())
+            }
+            MetadataRule::MinDimensions { width, height } => {
+                if *width == 0 && *height == 0 {
+                    return Err("Minimum dimensions are both zero".to_string());
+                }
+                Ok(())
+            }
+            MetadataRule::FileSize {
+                min_bytes,
+                max_bytes,
+            } => check_range(min_bytes.as_ref(), max_bytes.as_ref()),
+            MetadataRule::MediaType { .. } => Ok(()),
+            MetadataRule::AspectRatio { min, max } => {
+                if [min, max]
+                    .into_iter()
+                    .flatten()
+                    .any(|ratio| !ratio.is_finite() || *ratio <= 0.0)
+                {
+                    return Err("Aspect ratio must be a positive number".to_string());
+                }
+                check_range(min.as_ref(), max.as_ref())
+            }
+        }
+    }
+
+    /// このルールで除外されるか
+    pub fn matches(&self, facts: &MediaFacts) -> bool {
+        match self {
+            MetadataRule::DateRange { from, to } => {
+                facts.capture_date.as_deref().is_some_and(|date| {
+                    from.as_deref().is_none_or(|from| date >= from)
+                        && to.as_deref().is_none_or(|to| date <= to)
+                })
+            }
+            MetadataRule::Camera { model } => {
+                let wanted = model.trim().to_lowercase();
+                [&facts.camera_make, &facts.camera_model]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| name.to_lowercase().contains(&wanted))
+            }
+            MetadataRule::MinDimensions { width, height } => {
+                let (Some(w), Some(h)) = (facts.width, facts.height) else {
+                    return false;
+                };
+                w.max(h) < (*width).max(*height) || w.min(h) < (*width).min(*height)
+            }
+            MetadataRule::FileSize {
+                min_bytes,
+                max_bytes,
+            } => {
+                min_bytes.is_some_and(|min| facts.file_size < min)
+                    || max_bytes.is_some_and(|max| facts.file_size > max)
+            }
+            MetadataRule::MediaType { kind } => facts.is_video == (*kind == MediaKind::Video),
+            MetadataRule::AspectRatio { min, max } => {
+                let (Some(w), Some(h)) = (facts.width, facts.height) else {
+                    return false;
+                };
+                if h == 0 {
+                    return false;
+                }
+                let ratio = w as f64 / h as f64;
+                min.is_some_and(|min| ratio < min) || max.is_some_and(|max| ratio > max)
+            }
+        }
+    }
+}
+
+/// 下限と上限が両方あれば下限 <= 上限、どちらもなければ条件なしとしてエラー
+fn check_range<T: PartialOrd>(min: Option<&T>, max: Option<&T>) -> Result<(), String> {
+    match (min, max) {
+        (None, None) => Err("Range has neither a lower nor an upper bound".to_string()),
+        (Some(min), Some(max)) if min > max => {
+            Err("Lower bound is greater than upper bound".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// いずれかのルールで除外されるか
+pub fn is_excluded(rules: &[MetadataRule], facts: &MediaFacts) -> bool {
+    rules.iter().any(|rule| rule.matches(facts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo() -> MediaFacts {
+        MediaFacts {
+            is_video: false,
+            file_size: 3_000_000,
+            capture_date: Some("2023-05-15".to_string()),
+            camera_make: Some("Canon".to_string()),
+            camera_model: Some("Canon EOS R6".to_string()),
+            width: Some(6000),
+            height: Some(4000),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let date = |from: Option<&str>, to: Option<&str>| MetadataRule::DateRange {
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+        };
+        assert!(date(Some("2023-05-15"), Some("2023-05-15")).matches(&photo()));
+        assert!(date(None, Some("2023-12-31")).matches(&photo()));
+        assert!(!date(Some("2023-05-16"), None).matches(&photo()));
+
+        let camera = |model: &str| MetadataRule::Camera {
+            model: model.to_string(),
+        };
+        assert!(camera("eos r6").matches(&photo()));
+        assert!(camera("canon").matches(&photo()));
+        assert!(!camera("iPhone").matches(&photo()));
+
+        // 縦位置でも同じ画素数なら残す
+        let min = MetadataRule::MinDimensions {
+            width: 1920,
+            height: 1080,
+        };
+        let portrait = MediaFacts {
+            width: Some(1080),
+            height: Some(1920),
+            ..photo()
+        };
+        let small = MediaFacts {
+            width: Some(800),
+            height: Some(600),
+            ..photo()
+        };
+        assert!(!min.matches(&portrait));
+        assert!(min.matches(&small));
+
+        let size = MetadataRule::FileSize {
+            min_bytes: Some(100_000),
+            max_bytes: None,
+        };
+        assert!(!size.matches(&photo()));
+        assert!(size.matches(&MediaFacts {
+            file_size: 20_000,
+            ..photo()
+        }));
+
+        let videos = MetadataRule::MediaType {
+            kind: MediaKind::Video,
+        };
+        assert!(!videos.matches(&photo()));
+
+        // パノラマ（3:1 より横長）を除く
+        let aspect = MetadataRule::AspectRatio {
+            min: None,
+            max: Some(3.0),
+        };
+        assert!(!aspect.matches(&photo()));
+        assert!(aspect.matches(&MediaFacts {
+            width: Some(12000),
+            height: Some(2000),
+            ..photo()
+        }));
+    }
+
+    #[test]
+    fn test_unknown_metadata_is_not_excluded() {
+        let unknown = MediaFacts {
+            file_size: 1000,
+            ..Default::default()
+        };
+        let rules = [
+            MetadataRule::DateRange {
+                from: None,
+                to: Some("2100-01-01".to_string()),
+            },
+            MetadataRule::Camera {
+                model: "Canon".to_string(),
+            },
+            MetadataRule::MinDimensions {
+                width: 100,
+                height: 100,
+            },
+            MetadataRule::AspectRatio {
+                min: Some(1.0),
+                max: Some(1.0),
+            },
+        ];
+        assert!(!is_excluded(&rules, &unknown));
+    }
+
+    #[test]
+    fn test_validate_and_json() {
+        let rule: MetadataRule =
+            serde_json::from_str(r#"{"type":"dateRange","from":"2023-05-15","to":null}"#).unwrap();
+        assert!(rule.validate().is_ok());
+        let rule: MetadataRule =
+            serde_json::from_str(r#"{"type":"fileSize","minBytes":10,"maxBytes":null}"#).unwrap();
+        assert_eq!(
+            rule,
+            MetadataRule::FileSize {
+                min_bytes: Some(10),
+                max_bytes: None
+            }
+        );
+
+        let invalid = [
+            MetadataRule::DateRange {
+                from: Some("2023/05/15".to_string()),
+                to: None,
+            },
+            MetadataRule::DateRange {
+                from: Some("2024-01-01".to_string()),
+                to: Some("2023-01-01".to_string()),
+            },
+            MetadataRule::Camera {
+                model: " ".to_string(),
+            },
+            MetadataRule::FileSize {
+                min_bytes: None,
+                max_bytes: None,
+            },
+            MetadataRule::AspectRatio {
+                min: Some(0.0),
+                max: None,
+            },
+        ];
+        for rule in invalid {
+            assert!(rule.validate().is_err(), "{rule:?}");
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! 保存・比較に使うパスの正規形。
+//!
+//! 同じライブラリを別の OS から開いてもルールや統計が食い違わないよう、DB に保存するパスと
+//! 除外ルールの判定に使うパスをここで揃える。
+//! - 区切りは `/`（Windows の `\` は変換する。Windows 以外では `C:\` や `\\server` で始まる
+//!   Windows 形式の文字列だけを変換し、`\` を含むファイル名はそのまま残す）
+//! - 重複した `/`・末尾の `/`・途中の `.` は落とす。ドライブ名は大文字にする
+//! - Unicode は、保存する形では macOS のときだけ NFC に揃える（macOS のファイルシステムは NFC と NFD を
+//!   同じ名前として扱うが、Linux・Windows では別のファイルになりうるので、書き換えると開けなくなる）。
+//!   除外ルールの判定ではどの OS でも NFC に揃えて比べる
+//! - 大文字小文字は保存する形では変えず、判定のときに `CasePolicy` に従う
+
+use icu_normalizer::ComposingNormalizerBorrowed;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// パスを比べるときの大文字小文字の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CasePolicy {
+    /// 区別する（Linux の既定）
+    Sensitive,
+    /// 区別しない（Windows・macOS の既定のファイルシステム）
+    Insensitive,
+}
+
+impl CasePolicy {
+    /// この OS のファイルシステムの既定
+    pub const fn platform() -> Self {
+        if cfg!(any(windows, target_os = "macos")) {
+            CasePolicy::Insensitive
+        } else {
+            CasePolicy::Sensitive
+        }
+    }
+
+    /// 2つの文字列をこの扱いで比べる
+    pub fn eq(self, a: &str, b: &str) -> bool {
+        match self {
+            CasePolicy::Sensitive => a == b,
+            CasePolicy::Insensitive if a.is_ascii() && b.is_ascii() => a.eq_ignore_ascii_case(b),
+            CasePolicy::Insensitive => a.to_lowercase() == b.to_lowercase(),
+        }
+    }
+}
+
+/// DB に保存する形のパス
+pub fn canonical_path(path: &Path) -> String {
+    canonical_str(&path.to_string_lossy())
+}
+
+/// 文字列のパスを保存する形に揃える（DB に残っている旧形式のパスの読み替えにも使う）
+pub fn canonical_str(path: &str) -> String {
+    let path = if cfg!(target_os = "macos") {
+        nfc(path)
+    } else {
+        Cow::Borrowed(path)
+    };
+    let path = if cfg!(windows) || looks_like_windows_path(&path) {
+        Cow::Owned(path.replace('\\', "/"))
+    } else {
+        path
+    };
+
+    // UNC（//server/share）は先頭の2つの `/` を残す
+    let lead = if path.starts_with("//") {
+        "//"
+    } else if path.starts_with('/') {
+        "/"
+    } else {
+        ""
+    };
+    let mut segments: Vec<Cow<str>> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .map(Cow::Borrowed)
+        .collect();
+    if let Some(first) = segments.first_mut() {
+        if lead.is_empty() && is_drive(first) {
+            *first = Cow::Owned(first.to_ascii_uppercase());
+            // ドライブ直下（`C:\`）は `C:` と区別して `/` を残す
+            if segments.len() == 1 && path.len() > 2 {
+                return format!("{}/", segments[0]);
+            }
+        }
+    }
+    format!("{lead}{}", segments.join("/"))
+}
+
+/// 判定用に Unicode を NFC に揃える（ファイルを開くのには使わない）
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    ComposingNormalizerBorrowed::new_nfc().normalize(text)
+}
+
+/// 保存する形の path が prefix（同じく保存する形のディレクトリ）の配下なら、そこからの相対パスを返す
+///
+/// prefix 自身なら空文字列。`photos` と `photos2` のような前方一致だけのものは配下としない。
+pub fn strip_path_prefix<'a>(path: &'a str, prefix: &str, case: CasePolicy) -> Option<&'a str> {
+    let prefix = prefix.trim_end_matches('/');
+    let head = path.get(..prefix.len())?;
+    if !case.eq(head, prefix) {
+        return None;
+    }
+    let rest = &path[prefix.len()..];
+    if rest.is_empty() {
+        return Some(rest);
+    }
+    rest.strip_prefix('/')
+}
+
+/// `C:\...` や `\\server\...` のような Windows 形式の文字列か
+pub fn looks_like_windows_path(path: &str) -> bool {
+    path.starts_with("\\\\")
+        || path
+            .get(..3)
+            .is_some_and(|head| is_drive(&head[..2]) && head.ends_with('\\'))
+}
+
+/// `C:` のようなドライブ名か
+fn is_drive(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_str() {
+        // 重複・末尾の `/` と途中の `.` を落とす
+        assert_eq!(canonical_str("/photos//trip/./a.jpg"), "/photos/trip/a.jpg");
+        assert_eq!(canonical_str("/photos/trip/"), "/photos/trip");
+        assert_eq!(canonical_str("/"), "/");
+        // Windows 形式はどの OS でも `/` 区切り・大文字のドライブ名にする
+        assert_eq!(
+            canonical_str(r"c:\Photos\trip\a.jpg"),
+            "C:/Photos/trip/a.jpg"
+        );
+        assert_eq!(canonical_str(r"C:\"), "C:/");
+        assert_eq!(canonical_str(r"\\nas\share\a.jpg"), "//nas/share/a.jpg");
+        // Windows 以外では `\` を含むファイル名をそのまま残す
+        if !cfg!(windows) {
+            assert_eq!(canonical_str(r"/photos/a\b.jpg"), r"/photos/a\b.jpg");
+        }
+        // NFD（macOS の HFS+ が返す形）は macOS でだけ NFC に揃える
+        let nfd = "/photos/Ame\u{301}lie.jpg";
+        let expected = if cfg!(target_os = "macos") {
+            "/photos/Am\u{e9}lie.jpg"
+        } else {
+            nfd
+        };
+        assert_eq!(canonical_str(nfd), expected);
+        assert_eq!(nfc(nfd), "/photos/Am\u{e9}lie.jpg");
+    }
+
+    #[test]
+    fn test_strip_path_prefix() {
+        let sensitive = CasePolicy::Sensitive;
+        assert_eq!(
+            strip_path_prefix("/photos/trip/a.jpg", "/photos", sensitive),
+            Some("trip/a.jpg")
+        );
+        assert_eq!(
+            strip_path_prefix("/photos", "/photos/", sensitive),
+            Some("")
+        );
+        assert_eq!(
+            strip_path_prefix("/photos2/a.jpg", "/photos", sensitive),
+            None
+        );
+        assert_eq!(strip_path_prefix("/a.jpg", "/", sensitive), Some("a.jpg"));
+        assert_eq!(
+            strip_path_prefix("/Photos/a.jpg", "/photos", sensitive),
+            None
+        );
+        assert_eq!(
+            strip_path_prefix("C:/Photos/a.jpg", "c:/photos", CasePolicy::Insensitive),
+            Some("a.jpg")
+        );
+    }
+}
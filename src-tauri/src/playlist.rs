@@ -1,5 +1,10 @@
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// プレイリストの版の払い出し元（作り直しても同じ版にならないよう全体で1つ）
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
 
 /// プレイリスト管理
 #[derive(Debug, Clone)]
@@ -12,6 +17,11 @@ pub struct Playlist {
     history: Vec<usize>,
     /// 履歴内の現在位置
     history_position: usize,
+    /// 周回の先頭の画像が表示中に消え、まだ何も表示していない状態か
+    /// このとき `current_index` は次に表示する画像を指し、`history_position` は次にたどる履歴の位置
+    before_first: bool,
+    /// 画像の顔ぶれの版（作成・更新のたびに変わる）
+    generation: u64,
 }
 
 impl Playlist {
@@ -25,6 +35,8 @@ impl Playlist {
             current_index: 0,
             history: vec![0],
             history_position: 0,
+            before_first: false,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -38,7 +50,8 @@ impl Playlist {
         if self.shuffled_list.is_empty() {
             return None;
         }
-        let next_index = (self.current_index + n) % self.shuffled_list.len();
+        let next_index = (self.current_index + n).saturating_sub(usize::from(self.before_first))
+            % self.shuffled_list.len();
         self.shuffled_list.get(next_index)
     }
 
@@ -49,6 +62,18 @@ impl Playlist {
             return (None, false);
         }
 
+        if self.before_first {
+            self.before_first = false;
+            if let Some(&index) = self.history.get(self.history_position) {
+                // 戻った先で消した: 履歴の続き（表示済み）をたどる
+                self.current_index = index;
+                return (self.current(), false);
+            }
+            // 次へ進めずに、消えた画像の次（周回の先頭）を新しく表示する
+            self.push_history();
+            return (self.current(), true);
+        }
+
         // 履歴の途中にいるかチェック（前へで戻った後か？）
         let is_in_history = self.history_position < self.history.len() - 1;
 
@@ -77,18 +102,32 @@ impl Playlist {
             }
         }
 
-        // 履歴に追加（最大100件）
+        self.push_history();
+        (self.current(), true)
+    }
+
+    /// 現在の画像を履歴に追加（最大100件）
+    fn push_history(&mut self) {
         if self.history.len() >= 100 {
             self.history.remove(0);
         }
         self.history.push(self.current_index);
         self.history_position = self.history.len() - 1;
-
-        (self.current(), true)
     }
 
     /// 前の画像に戻る（履歴から、カウント増やさない）
     pub fn go_back(&mut self) -> Option<&String> {
+        if self.before_first {
+            if self.history_position == 0 {
+                return self.current();
+            }
+            // 次にたどる位置の1つ手前が直前に表示した画像
+            self.before_first = false;
+            self.history_position -= 1;
+            self.current_index = self.history[self.history_position];
+            return self.current();
+        }
+
         if self.history_position == 0 {
             // 履歴の最初なので戻れない
             return self.current();
@@ -116,16 +155,74 @@ impl Playlist {
         if self.shuffled_list.is_empty() {
             0
         } else {
-            self.current_index + 1
+            self.current_index + 1 - usize::from(self.before_first)
         }
     }
 
     /// 画像リストを更新（新規画像追加、削除画像除外）
+    ///
+    /// 周回の途中の位置は保つ。現在の画像より前（この周回で表示済み）の削除は位置を詰めるだけで、
+    /// 新規画像はこの周回の残りの末尾に入る。現在の画像自体が消えた場合は1つ手前を指し、
+    /// 次へ進んだときに消えた画像の次の画像が出るようにする。消えたのが周回の先頭なら手前がないので
+    /// `before_first` にして、次へ進んだときに進めずに新しい先頭を出す。
+    /// 履歴は消えた画像の分だけ除いて残す。
     pub fn update_images(&mut self, new_images: Vec<String>, deleted_images: Vec<String>) {
-        // 削除された画像を除外
+        // 削除された画像を除外（旧インデックス → 新インデックスの対応を作る）
         if !deleted_images.is_empty() {
-            self.shuffled_list
-                .retain(|path| !deleted_images.contains(path));
+            let deleted: HashSet<&String> = deleted_images.iter().collect();
+            let mut remap = Vec::with_capacity(self.shuffled_list.len());
+            let mut next = 0;
+            for path in &self.shuffled_list {
+                if deleted.contains(path) {
+                    remap.push(None);
+                } else {
+                    remap.push(Some(next));
+                    next += 1;
+                }
+            }
+
+            // 現在の画像が消えたら、それより前で残っている画像の数（= 消えた画像の次の位置）から1つ戻す
+            // 手前に残っている画像がなければ、消えた画像の次を指したまま表示前の状態にする
+            let current_removed = remap.get(self.current_index).is_some_and(Option::is_none);
+            let kept_before = remap[..self.current_index.min(remap.len())]
+                .iter()
+                .flatten()
+                .count();
+            let was_before_first = self.before_first;
+            self.current_index = match remap.get(self.current_index).copied().flatten() {
+                Some(index) => index,
+                None if current_removed && kept_before > 0 => kept_before - 1,
+                None => {
+                    self.before_first |= current_removed;
+                    kept_before
+                }
+            };
+
+            // 履歴も新しいインデックスに読み替える（消えた画像の分は除く）
+            // cursor は現在位置より前（現在の画像を含む）の履歴の件数
+            let cursor = self.history_position + usize::from(!was_before_first);
+            let mut history = Vec::with_capacity(self.history.len());
+            let mut kept_cursor = 0;
+            for (i, old) in self.history.iter().enumerate() {
+                if let Some(index) = remap.get(*old).copied().flatten() {
+                    history.push(index);
+                }
+                if i + 1 == cursor {
+                    kept_cursor = history.len();
+                }
+            }
+            let mut position = kept_cursor;
+            if !self.before_first {
+                position = kept_cursor.saturating_sub(1);
+                if history.get(position) != Some(&self.current_index) {
+                    position = kept_cursor;
+                    history.insert(position, self.current_index);
+                }
+            }
+            self.history = history;
+            self.history_position = position;
+
+            self.shuffled_list.retain(|path| !deleted.contains(path));
         }
 
         // 新規画像を追加してシャッフル
@@ -143,11 +240,22 @@ impl Playlist {
         // 現在のインデックスが範囲外になった場合は調整
         if self.current_index >= self.shuffled_list.len() && !self.shuffled_list.is_empty() {
             self.current_index = self.shuffled_list.len() - 1;
+            self.history = vec![self.current_index];
+            self.history_position = 0;
+            self.before_first = false;
         }
 
-        // 履歴をクリア（リストが変更されたため）
-        self.history = vec![self.current_index];
-        self.history_position = 0;
+        self.generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 画像の顔ぶれの版（並べ替えたパスの一覧などをキャッシュする鍵）
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// プレイリストに含まれる画像（順序はシャッフル後）
+    pub fn images(&self) -> &[String] {
+        &self.shuffled_list
     }
 
     /// プレイリストが空かチェック
@@ -244,4 +352,80 @@ mod tests {
         playlist.update_images(vec![], vec!["img2.jpg".to_string()]);
         assert_eq!(playlist.total_count(), 2);
     }
+
+    #[test]
+    fn test_playlist_update_keeps_position() {
+        let images: Vec<String> = (0..6).map(|i| format!("img{i}.jpg")).collect();
+        let mut playlist = Playlist::new(images);
+        playlist.advance();
+        playlist.advance();
+        playlist.advance();
+        let order = playlist.images().to_vec();
+        let current = playlist.current().cloned();
+
+        // 表示済みの画像と、まだの画像を1枚ずつ除外しても、現在の画像と履歴は変わらない
+        playlist.update_images(vec![], vec![order[1].clone(), order[4].clone()]);
+        assert_eq!(playlist.current().cloned(), current);
+        assert_eq!(playlist.current_position(), 3);
+        assert!(playlist.can_go_back());
+        assert_eq!(playlist.go_back(), Some(&order[2]));
+        assert_eq!(playlist.go_back(), Some(&order[0]));
+        playlist.advance();
+        playlist.advance();
+        assert_eq!(playlist.current().cloned(), current);
+
+        // 現在の画像が消えたら、次へ進むと消えた画像の次が出る
+        playlist.update_images(vec![], vec![order[3].clone()]);
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[5]));
+        assert!(should_count);
+
+        // 戻した画像はこの周回の残りに入る
+        playlist.update_images(vec![order[1].clone()], vec![]);
+        let (next, _) = playlist.advance();
+        assert_eq!(next, Some(&order[1]));
+    }
+
+    #[test]
+    fn test_playlist_remove_current_first() {
+        let images: Vec<String> = (0..4).map(|i| format!("img{i}.jpg")).collect();
+        let mut playlist = Playlist::new(images);
+        let order = playlist.images().to_vec();
+
+        // 周回の先頭を表示中に除外しても、次へ進むと残りの先頭が出て飛ばされない
+        playlist.update_images(vec![], vec![order[0].clone()]);
+        assert!(!playlist.can_go_back());
+        assert_eq!(playlist.peek_next_n(1), Some(&order[1]));
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[1]));
+        assert!(should_count);
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[2]));
+        assert!(should_count);
+        assert_eq!(playlist.go_back(), Some(&order[1]));
+        assert!(!playlist.can_go_back());
+    }
+
+    #[test]
+    fn test_playlist_remove_current_after_going_back() {
+        let images: Vec<String> = (0..4).map(|i| format!("img{i}.jpg")).collect();
+        let mut playlist = Playlist::new(images);
+        playlist.advance();
+        playlist.advance();
+        let order = playlist.images().to_vec();
+        playlist.go_back();
+        playlist.go_back();
+
+        // 戻った先の先頭を除外したら、次へ進むと履歴の続きを表示済みとしてたどる
+        playlist.update_images(vec![], vec![order[0].clone()]);
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[1]));
+        assert!(!should_count);
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[2]));
+        assert!(!should_count);
+        let (next, should_count) = playlist.advance();
+        assert_eq!(next, Some(&order[3]));
+        assert!(should_count);
+    }
 }
@@ -0,0 +1,129 @@
+//! 動画のポスターフレーム（表示前に見せる静止画）の生成。
+//!
+//! PATH 上に `ffmpeg` がある場合だけ使う。見つからなければポスターは作らず、
+//! 従来どおり `<video>` の読み込みを待って表示する。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// ポスターに使う位置の上限（冒頭の黒フレームやフェードインを避けつつ、早めの場面を選ぶ）
+const POSTER_MAX_POSITION_MS: u64 = 1000;
+
+static FFMPEG_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+/// 一時ファイル名の通し番号
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// PATH から ffmpeg を探す（結果はプロセス内でキャッシュ）
+pub fn find_ffmpeg() -> Option<&'static Path> {
+    FFMPEG_PATH
+        .get_or_init(|| {
+            let name = if cfg!(windows) {
+                "ffmpeg.exe"
+            } else {
+                "ffmpeg"
+            };
+            let paths = std::env::var_os("PATH")?;
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(name))
+                .find(|candidate| candidate.is_file())
+        })
+        .as_deref()
+}
+
+/// output_path の隣に置く、呼び出しごとに別名の一時ファイル（拡張子は ffmpeg が形式を決める `.jpg`）
+///
+/// 表示と先読みが同じ動画のポスターを同時に作っても、互いの書きかけを rename しないようにする。
+pub fn temp_output_path(output_path: &Path) -> PathBuf {
+    let name = output_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let count = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    output_path.with_file_name(format!("{name}.{}-{count}.part.jpg", std::process::id()))
+}
+
+/// ポスターにする位置（ミリ秒）。1秒目、短い動画は長さの 1/10
+fn poster_position_ms(duration_ms: Option<u64>) -> u64 {
+    duration_ms.map_or(POSTER_MAX_POSITION_MS, |d| {
+        (d / 10).min(POSTER_MAX_POSITION_MS)
+    })
+}
+
+/// 動画からポスターフレームを切り出して JPEG で保存する（4K に収まるよう縮小、回転は ffmpeg が適用）
+/// キーフレームだけをデコードするので、長い動画でも速い。指定位置以降にキーフレームがなければ先頭を使う
+pub fn extract_poster_frame(
+    video_path: &Path,
+    output_path: &Path,
+    duration_ms: Option<u64>,
+) -> Result<(), String> {
+    let ffmpeg = find_ffmpeg().ok_or("ffmpeg not found")?;
+
+    let position = poster_position_ms(duration_ms);
+    let positions: &[u64] = if position > 0 { &[position, 0] } else { &[0] };
+
+    for &position in positions {
+        let mut command = Command::new(ffmpeg);
+        command
+            .args(["-v", "error", "-y", "-skip_frame", "nokey", "-ss"])
+            .arg(format!("{:.3}", position as f64 / 1000.0))
+            .arg("-i")
+            .arg(video_path)
+            .args([
+                "-frames:v",
+                "1",
+                "-vf",
+                "scale='min(3840,iw)':'min(2160,ih)':force_original_aspect_ratio=decrease",
+                "-q:v",
+                "3",
+            ])
+            .arg(output_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        // Windows でコンソールウィンドウを出さない
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+        }
+
+        let status = command
+            .status()
+            .map_err(|e| format!("Failed to run ffmpeg: {e}"))?;
+        if status.success() && output_path.exists() {
+            return Ok(());
+        }
+    }
+
+    Err(format!(
+        "Failed to extract poster frame: {}",
+        video_path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poster_position() {
+        // 長さ不明・長い動画は 1 秒目、短い動画は長さの 1/10
+        assert_eq!(poster_position_ms(None), 1000);
+        assert_eq!(poster_position_ms(Some(60_000)), 1000);
+        assert_eq!(poster_position_ms(Some(3000)), 300);
+        assert_eq!(poster_position_ms(Some(5)), 0);
+    }
+
+    #[test]
+    fn test_temp_output_path_is_unique() {
+        let output = Path::new("/cache/abc.jpg");
+        let first = temp_output_path(output);
+        let second = temp_output_path(output);
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), output.parent());
+        assert!(first.to_string_lossy().ends_with(".part.jpg"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ignore::IgnoreFilter;
+use crate::path_norm::{canonical_path, strip_path_prefix, CasePolicy};
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
@@ -7,7 +8,10 @@ use std::time::SystemTime;
 use walkdir::WalkDir;
 
 /// 画像ファイルの拡張子
-const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif"];
+/// exr / hdr は WebView で表示できないため、常にトーンマッピング済みのキャッシュ経由で表示する
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "exr", "hdr",
+];
 
 /// 動画ファイルの拡張子（HTMLのvideoタグでネイティブ再生可能な形式のみ）
 /// avi/mkv/flv/wmv等の旧フォーマットはffmpeg同梱後に対応予定
@@ -63,14 +67,31 @@ impl ImageScanner {
         }
 
         // WalkDirでファイルエントリを収集
-        let entries: Vec<_> = WalkDir::new(directory)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .filter(|e| self.is_media_file(e.path()))
-            .filter(|e| !self.ignore_filter.is_ignored(e.path()))
-            .collect();
+        // 除外されたディレクトリには降りない（中身は `!` でも戻せないので丸ごと飛ばせる）。
+        // 降りたディレクトリの `.sssignore` はその場で読み込み、配下の判定に加える
+        // （毎回読み直すので、ファイルを書き換えれば次のスキャンで反映される）
+        let mut ignore_filter = self.ignore_filter.clone();
+        let mut entries = Vec::new();
+        let mut walker = WalkDir::new(directory).follow_links(false).into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if entry.file_type().is_dir() {
+                if entry.depth() > 0 && ignore_filter.is_ignored_dir(entry.path()) {
+                    walker.skip_current_dir();
+                } else {
+                    ignore_filter.load_ignore_file(entry.path());
+                }
+                continue;
+            }
+            if entry.file_type().is_file()
+                && self.is_media_file(entry.path())
+                && !ignore_filter.is_ignored(entry.path())
+            {
+                entries.push(entry);
+            }
+        }
 
         let total = entries.len();
 
@@ -106,7 +127,7 @@ impl ImageScanner {
                 }
 
                 Some(FileMetadata {
-                    path: path.to_string_lossy().to_string(),
+                    path: canonical_path(path),
                     modified_time,
                     file_size: metadata.len() as i64,
                 })
@@ -158,7 +179,16 @@ impl ImageScanner {
         }
 
         // 削除されたファイルを検出（previous_mapに残っているもの）
-        let deleted_files: Vec<String> = previous_map.keys().cloned().collect();
+        // ライブラリ内にまだあるのに歩いた結果にないファイルは除外ルール（スヌーズ中を含む）で
+        // 外れただけなので、表示回数などを消さないよう削除扱いにしない
+        let root = canonical_path(directory);
+        let deleted_files: Vec<String> = previous_map
+            .into_keys()
+            .filter(|path| {
+                strip_path_prefix(path, &root, CasePolicy::platform()).is_none()
+                    || !Path::new(path).exists()
+            })
+            .collect();
 
         let duration_ms = start_time.elapsed().as_millis();
 
@@ -199,10 +229,57 @@ impl ImageScanner {
     }
 }
 
+/// 除外ルール（`.sssignore` を含む）をかけずに、ディレクトリ配下のメディアファイルを列挙する
+/// （保存する形のパス。メタデータは読まない）
+///
+/// `file_metadata` には一度も取り込んでいない除外済みのファイルが入らないので、除外ルールの試算はこちらで数える。
+pub fn list_media_files(directory: &Path) -> Vec<String> {
+    let scanner = ImageScanner::new(IgnoreFilter::from_patterns(&[]));
+    WalkDir::new(directory)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && scanner.is_media_file(entry.path()))
+        .map(|entry| canonical_path(entry.path()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ignored_files_are_not_deleted() {
+        let dir = std::env::temp_dir().join(format!("sss_scanner_{}_ignored", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"a").unwrap();
+        fs::write(dir.join("snoozed.jpg"), b"b").unwrap();
+        let root = canonical_path(&dir);
+        let previous = ["a.jpg", "snoozed.jpg", "gone.jpg"]
+            .iter()
+            .map(|name| (format!("{root}/{name}"), 0, 1))
+            .chain([("/elsewhere/c.jpg".to_string(), 0, 1)])
+            .collect();
+
+        // 除外ルールで外れたファイルは残し、消えたファイルとライブラリ外のファイルだけを削除扱いにする
+        let scanner = ImageScanner::new(
+            IgnoreFilter::from_patterns(&["snoozed.jpg".to_string()]).with_root(&dir),
+        );
+        let result = scanner
+            .scan_directory_incremental_with_progress(&dir, previous, |_, _| {})
+            .unwrap();
+        let mut deleted = result.deleted_files;
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            ["/elsewhere/c.jpg".to_string(), format!("{root}/gone.jpg")]
+        );
+        assert_eq!(result.total_count, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_is_image_file() {
         let scanner = ImageScanner::new(IgnoreFilter::from_patterns(&[]));
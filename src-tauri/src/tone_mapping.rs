@@ -0,0 +1,354 @@
+//! 16bit / HDR 画像を 8bit 表示用に落とすときの階調変換。
+//!
+//! `DynamicImage::to_rgb8` は上位ビットを切り捨てるだけなので、
+//! 濃度域の狭いネガスキャンは眠く、HDR（リニアな浮動小数点）は白飛びする。
+//! ここでは画像の種類に合わせたカーブで 8bit に変換する。
+
+use image::{DynamicImage, GenericImageView, ImageReader, RgbImage, RgbaImage};
+use std::path::Path;
+
+/// 16bit / 浮動小数点を格納しうる形式の拡張子
+const HIGH_BIT_DEPTH_EXTENSIONS: &[&str] = &["png", "tif", "tiff", "exr", "hdr"];
+/// レベル補正で切り捨てる両端の割合（ゴミ・ホコリ・スペキュラで範囲が引っ張られないように）
+const LEVELS_CLIP_FRACTION: f64 = 0.001;
+/// パーセンタイル計算用ヒストグラムの分割数
+const HISTOGRAM_BINS: usize = 4096;
+/// フィルミックの自動露出で平均輝度を合わせる値（中間グレー）
+const FILMIC_MIDDLE_GREY: f32 = 0.18;
+
+/// 階調変換の方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapping {
+    /// 画像の種類から選ぶ（浮動小数点 HDR → Filmic、16bit 整数 → Clip。レベル補正はライブラリごとに明示する）
+    #[default]
+    Auto,
+    /// 単純に 8bit へ丸める（従来の挙動）
+    Clip,
+    /// 輝度の実際の濃度域を 0〜255 に引き伸ばす（全チャンネルに同じ補正をかけるので色味は変わらない）
+    Levels,
+    /// チャンネルごとに濃度域を引き伸ばす（色かぶり・褪色したスキャン向け。ホワイトバランスも変わる）
+    ChannelLevels,
+    /// 自動露出 + ACES フィルミックカーブ（HDR 向け）
+    Filmic,
+}
+
+impl ToneMapping {
+    /// 設定値（"auto" / "clip" / "levels" / "channel_levels" / "filmic"）を解釈する
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Self::Auto),
+            "clip" => Some(Self::Clip),
+            "levels" => Some(Self::Levels),
+            "channel_levels" => Some(Self::ChannelLevels),
+            "filmic" => Some(Self::Filmic),
+            _ => None,
+        }
+    }
+}
+
+/// 1チャンネル 8bit を超える（16bit 整数・浮動小数点）画像か
+pub fn is_high_bit_depth(img: &DynamicImage) -> bool {
+    img.color().bytes_per_pixel() / img.color().channel_count() > 1
+}
+
+/// ファイルが高ビット深度の画像か（ヘッダのみ読む。JPEG 等の 8bit 専用形式は開かない）
+pub fn is_high_bit_depth_file(image_path: &Path) -> bool {
+    use image::ImageDecoder;
+
+    let Some(ext) = image_path.extension() else {
+        return false;
+    };
+    if !HIGH_BIT_DEPTH_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()) {
+        return false;
+    }
+
+    ImageReader::open(image_path)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .is_some_and(|decoder| {
+            let color = decoder.color_type();
+            color.bytes_per_pixel() / color.channel_count() > 1
+        })
+}
+
+/// 高ビット深度の画像を 8bit に変換する（8bit の画像はそのまま返す）
+pub fn tone_map(img: DynamicImage, mode: ToneMapping) -> DynamicImage {
+    if !is_high_bit_depth(&img) {
+        return img;
+    }
+
+    let is_float = matches!(
+        img,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let mode = match mode {
+        ToneMapping::Auto if is_float => ToneMapping::Filmic,
+        ToneMapping::Auto => ToneMapping::Clip,
+        other => other,
+    };
+
+    match mode {
+        ToneMapping::Clip | ToneMapping::Auto => clip(img),
+        ToneMapping::Levels => levels(&img, is_float),
+        ToneMapping::ChannelLevels => channel_levels(&img, is_float),
+        ToneMapping::Filmic => filmic(&img, is_float),
+    }
+}
+
+fn clip(img: DynamicImage) -> DynamicImage {
+    if img.color().has_alpha() {
+        DynamicImage::ImageRgba8(img.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    }
+}
+
+/// 画素を「表示用にエンコードされた値」で取り出す
+/// 16bit は既にガンマ付きなので 0〜1 に正規化するだけ、浮動小数点 HDR はリニアなので sRGB ガンマをかける
+fn encoded_pixels(img: &DynamicImage, is_float: bool) -> Vec<[f32; 4]> {
+    img.to_rgba32f()
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            if is_float {
+                [linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a]
+            } else {
+                [r, g, b, a]
+            }
+        })
+        .collect()
+}
+
+/// 画素をリニアな値で取り出す
+fn linear_pixels(img: &DynamicImage, is_float: bool) -> Vec<[f32; 4]> {
+    img.to_rgba32f()
+        .pixels()
+        .map(|p| {
+            let [r, g, b, a] = p.0;
+            if is_float {
+                [r.max(0.0), g.max(0.0), b.max(0.0), a]
+            } else {
+                [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a]
+            }
+        })
+        .collect()
+}
+
+/// 輝度のレベル補正（両端 0.1% を除いた輝度の範囲を 0〜1 に引き伸ばす）
+/// 3チャンネルに同じオフセットと倍率をかけるので、色の正しい 16bit 書き出しのホワイトバランスを崩さない
+fn levels(img: &DynamicImage, is_float: bool) -> DynamicImage {
+    let mut pixels = encoded_pixels(img, is_float);
+
+    let values: Vec<f32> = pixels.iter().map(luminance).collect();
+    let (low, high) = percentile_range(&values);
+    if high - low > f32::EPSILON {
+        for pixel in &mut pixels {
+            for value in &mut pixel[..3] {
+                *value = (*value - low) / (high - low);
+            }
+        }
+    }
+
+    to_8bit(img, &pixels)
+}
+
+/// チャンネルごとのレベル補正（両端 0.1% を除いた範囲を 0〜1 に引き伸ばす）
+fn channel_levels(img: &DynamicImage, is_float: bool) -> DynamicImage {
+    let mut pixels = encoded_pixels(img, is_float);
+
+    for channel in 0..3 {
+        let values: Vec<f32> = pixels.iter().map(|p| p[channel]).collect();
+        let (low, high) = percentile_range(&values);
+        if high - low <= f32::EPSILON {
+            continue;
+        }
+        for pixel in &mut pixels {
+            pixel[channel] = (pixel[channel] - low) / (high - low);
+        }
+    }
+
+    to_8bit(img, &pixels)
+}
+
+/// 自動露出（対数平均輝度を中間グレーに合わせる）+ ACES フィルミックカーブ
+fn filmic(img: &DynamicImage, is_float: bool) -> DynamicImage {
+    let mut pixels = linear_pixels(img, is_float);
+
+    let log_sum: f64 = pixels
+        .iter()
+        .map(|p| (1e-4 + luminance(p) as f64).ln())
+        .sum();
+    let key = (log_sum / pixels.len().max(1) as f64).exp() as f32;
+    let exposure = FILMIC_MIDDLE_GREY / key.max(1e-4);
+
+    for pixel in &mut pixels {
+        for value in &mut pixel[..3] {
+            *value = linear_to_srgb(aces_filmic(*value * exposure));
+        }
+    }
+
+    to_8bit(img, &pixels)
+}
+
+/// 0〜1 に収めて 8bit 画像にする（アルファは元画像にある場合だけ残す）
+fn to_8bit(img: &DynamicImage, pixels: &[[f32; 4]]) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let quantize = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    if img.color().has_alpha() {
+        let raw = pixels.iter().flat_map(|p| p.map(quantize)).collect();
+        DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, raw).expect("buffer size"))
+    } else {
+        let raw = pixels
+            .iter()
+            .flat_map(|p| [quantize(p[0]), quantize(p[1]), quantize(p[2])])
+            .collect();
+        DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, raw).expect("buffer size"))
+    }
+}
+
+/// 値の下側・上側 LEVELS_CLIP_FRACTION 点
+fn percentile_range(values: &[f32]) -> (f32, f32) {
+    let max = values
+        .iter()
+        .copied()
+        .fold(0.0f32, f32::max)
+        .max(f32::EPSILON);
+
+    let mut histogram = vec![0usize; HISTOGRAM_BINS];
+    for value in values {
+        let bin = ((value.max(0.0) / max) * (HISTOGRAM_BINS - 1) as f32) as usize;
+        histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+    }
+
+    let clip_count = (values.len() as f64 * LEVELS_CLIP_FRACTION) as usize;
+    let bin_value = |bin: usize| bin as f32 / (HISTOGRAM_BINS - 1) as f32 * max;
+
+    let mut accumulated = 0;
+    let mut low = 0.0;
+    for (bin, &count) in histogram.iter().enumerate() {
+        accumulated += count;
+        if accumulated > clip_count {
+            low = bin_value(bin);
+            break;
+        }
+    }
+
+    accumulated = 0;
+    let mut high = max;
+    for (bin, &count) in histogram.iter().enumerate().rev() {
+        accumulated += count;
+        if accumulated > clip_count {
+            high = bin_value(bin);
+            break;
+        }
+    }
+
+    (low, high)
+}
+
+fn luminance(pixel: &[f32; 4]) -> f32 {
+    0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]
+}
+
+/// ACES フィルミックカーブの近似（Krzysztof Narkowicz）
+fn aces_filmic(x: f32) -> f32 {
+    let x = x.max(0.0);
+    ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.max(0.0);
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+    #[test]
+    fn test_levels_stretches_narrow_16bit_scan() {
+        // 濃度域の狭い 16bit スキャン（20000〜30000 のグラデーション）
+        let scan: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::from_fn(100, 10, |x, _| {
+            let v = 20000 + (x as u16) * 100;
+            Rgb([v, v, v])
+        });
+        let img = DynamicImage::ImageRgb16(scan);
+
+        // 従来の丸めでは 78〜117 程度に収まって眠い
+        let clipped = tone_map(img.clone(), ToneMapping::Clip).to_rgb8();
+        assert!(clipped.get_pixel(0, 0)[0] > 70);
+        assert!(clipped.get_pixel(99, 0)[0] < 120);
+
+        // 自動は 16bit 整数を丸めるだけ（仕上がった書き出しの階調を変えない）
+        let auto = tone_map(img.clone(), ToneMapping::Auto).to_rgb8();
+        assert_eq!(auto, clipped);
+
+        // レベル補正ではほぼ全域を使う
+        let mapped = tone_map(img, ToneMapping::Levels);
+        assert!(matches!(mapped, DynamicImage::ImageRgb8(_)));
+        let mapped = mapped.to_rgb8();
+        assert!(mapped.get_pixel(0, 0)[0] < 5);
+        assert!(mapped.get_pixel(99, 0)[0] > 250);
+    }
+
+    #[test]
+    fn test_levels_keeps_white_balance() {
+        // 色の正しい暖色寄りの 16bit 書き出し（R > G > B）
+        let warm: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::from_fn(100, 10, |x, _| {
+            let v = 10000 + (x as u16) * 400;
+            Rgb([v, v / 10 * 8, v / 10 * 6])
+        });
+        let img = DynamicImage::ImageRgb16(warm);
+
+        // 輝度のレベル補正は3チャンネルに同じ補正をかけるので R > G > B のまま
+        let mapped = tone_map(img.clone(), ToneMapping::Levels).to_rgb8();
+        for x in [20, 50, 80] {
+            let [r, g, b] = mapped.get_pixel(x, 0).0;
+            assert!(r > g && g > b, "{x}: {r} {g} {b}");
+        }
+
+        // チャンネルごとの補正は各チャンネルを全域に広げるので灰色に寄る
+        let mapped = tone_map(img, ToneMapping::ChannelLevels).to_rgb8();
+        let [r, _, b] = mapped.get_pixel(50, 0).0;
+        assert!(r.abs_diff(b) < 5);
+    }
+
+    #[test]
+    fn test_filmic_compresses_hdr_highlights() {
+        // 0.05〜50.0 のリニア HDR。単純な丸めでは半分以上が白飛びする
+        let hdr = Rgb32FImage::from_fn(100, 1, |x, _| {
+            let v = 0.05 * 1000f32.powf(x as f32 / 99.0);
+            Rgb([v, v, v])
+        });
+        let img = DynamicImage::ImageRgb32F(hdr);
+
+        let mapped = tone_map(img, ToneMapping::Auto).to_rgb8();
+        let values: Vec<u8> = (0..100).map(|x| mapped.get_pixel(x, 0)[0]).collect();
+        // 単調増加で、白飛び（255）はハイライトのごく一部だけ
+        assert!(values.windows(2).all(|w| w[0] <= w[1]));
+        assert!(values.iter().filter(|&&v| v == 255).count() < 10);
+        assert!(values[0] < 40);
+    }
+
+    #[test]
+    fn test_8bit_images_untouched() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([10, 20, 30])));
+        let mapped = tone_map(img.clone(), ToneMapping::Levels);
+        assert_eq!(mapped, img);
+    }
+}
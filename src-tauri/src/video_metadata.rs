@@ -0,0 +1,624 @@
+//! 動画コンテナ（MP4 / M4V / WebM）から再生時間・解像度・回転・作成日時を読む。
+//!
+//! ffmpeg 等の外部ツールに頼らず、コンテナのヘッダ部分（MP4 の moov、WebM の Info / Tracks）
+//! だけを辿る。映像データ（mdat / Cluster）は読み飛ばすので、大きな動画でも軽い。
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// MP4 の時刻の基準（1904-01-01）から UNIX エポックまでの秒数
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+/// WebM の DateUTC の基準（2001-01-01）の UNIX 時刻
+const WEBM_EPOCH_UNIX: i64 = 978_307_200;
+/// WebM の TimestampScale の既定値（ナノ秒）
+const WEBM_DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// 動画のメタデータ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoMetadata {
+    pub duration_ms: Option<u64>,
+    /// 表示上の幅・高さ（回転適用後）
+    pub width: u32,
+    pub height: u32,
+    /// 時計回りの回転角（0 / 90 / 180 / 270）
+    pub rotation: u32,
+    /// 作成日時（UTC、"YYYY-MM-DD HH:MM:SS"）
+    pub creation_time: Option<String>,
+}
+
+/// 動画ファイルのメタデータを読む（未対応の形式・壊れたファイルは None）
+pub fn read_video_metadata(video_path: &Path) -> Option<VideoMetadata> {
+    let ext = video_path.extension()?.to_string_lossy().to_lowercase();
+    let file = File::open(video_path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut reader = BufReader::new(file);
+
+    match ext.as_str() {
+        "mp4" | "m4v" | "mov" => read_mp4(&mut reader, len),
+        "webm" | "mkv" => read_webm(&mut reader, len),
+        _ => None,
+    }
+}
+
+/// UNIX 秒を "YYYY-MM-DD HH:MM:SS"（UTC）にする
+fn format_unix_time(secs: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs, 0).map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+// ---------------------------------------------------------------------------
+// MP4（ISO Base Media File Format）
+// ---------------------------------------------------------------------------
+
+/// trak ボックスから集めた情報
+#[derive(Default)]
+struct Mp4Track {
+    handler: Option<[u8; 4]>,
+    width: u32,
+    height: u32,
+    rotation: u32,
+}
+
+#[derive(Default)]
+struct Mp4Movie {
+    timescale: u32,
+    duration: u64,
+    creation_time: u64,
+    tracks: Vec<Mp4Track>,
+}
+
+fn read_mp4<R: Read + Seek>(reader: &mut R, len: u64) -> Option<VideoMetadata> {
+    let mut movie = Mp4Movie::default();
+    let mut found_moov = false;
+
+    walk_mp4_boxes(reader, 0, len, &mut |reader, box_type, start, end| {
+        if &box_type == b"moov" {
+            found_moov = true;
+            read_mp4_moov(reader, start, end, &mut movie)?;
+        }
+        Some(())
+    })?;
+
+    if !found_moov {
+        return None;
+    }
+
+    // 映像トラック（hdlr が vide、なければ寸法を持つ最初のトラック）
+    let track = movie
+        .tracks
+        .iter()
+        .find(|t| t.handler == Some(*b"vide"))
+        .or_else(|| movie.tracks.iter().find(|t| t.width > 0 && t.height > 0));
+    let (width, height, rotation) = match track {
+        Some(t) if t.rotation == 90 || t.rotation == 270 => (t.height, t.width, t.rotation),
+        Some(t) => (t.width, t.height, t.rotation),
+        None => (0, 0, 0),
+    };
+
+    // version 1 の mvhd は 64bit なので、ミリ秒への換算は u128 で行い収まらなければ捨てる
+    let duration_ms = (movie.timescale > 0)
+        .then(|| u64::try_from(movie.duration as u128 * 1000 / movie.timescale as u128).ok())
+        .flatten();
+    // 0 は「未設定」
+    let creation_time = (movie.creation_time > 0)
+        .then(|| i64::try_from(movie.creation_time).ok())
+        .flatten()
+        .and_then(|time| format_unix_time(time.checked_sub(MP4_EPOCH_OFFSET)?));
+
+    Some(VideoMetadata {
+        duration_ms,
+        width,
+        height,
+        rotation,
+        creation_time,
+    })
+}
+
+fn read_mp4_moov<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    movie: &mut Mp4Movie,
+) -> Option<()> {
+    walk_mp4_boxes(reader, start, end, &mut |reader, box_type, start, end| {
+        match &box_type {
+            b"mvhd" => {
+                let (version, _) = read_full_box_header(reader)?;
+                if version == 1 {
+                    movie.creation_time = read_u64(reader)?;
+                    read_u64(reader)?; // modification_time
+                    movie.timescale = read_u32(reader)?;
+                    movie.duration = read_u64(reader)?;
+                } else {
+                    movie.creation_time = read_u32(reader)? as u64;
+                    read_u32(reader)?; // modification_time
+                    movie.timescale = read_u32(reader)?;
+                    movie.duration = read_u32(reader)? as u64;
+                }
+            }
+            b"trak" => {
+                let mut track = Mp4Track::default();
+                read_mp4_trak(reader, start, end, &mut track)?;
+                movie.tracks.push(track);
+            }
+            _ => {}
+        }
+        Some(())
+    })
+}
+
+fn read_mp4_trak<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    track: &mut Mp4Track,
+) -> Option<()> {
+    walk_mp4_boxes(reader, start, end, &mut |reader, box_type, start, end| {
+        match &box_type {
+            b"tkhd" => {
+                let (version, _) = read_full_box_header(reader)?;
+                // creation / modification / track_ID / reserved / duration
+                let skip = if version == 1 {
+                    8 + 8 + 4 + 4 + 8
+                } else {
+                    4 * 5
+                };
+                // reserved(8) + layer(2) + alternate_group(2) + volume(2) + reserved(2)
+                reader.seek(SeekFrom::Current(skip + 16)).ok()?;
+                let mut matrix = [0i32; 9];
+                for value in &mut matrix {
+                    *value = read_u32(reader)? as i32;
+                }
+                track.rotation = rotation_from_matrix(&matrix);
+                // 16.16 固定小数点
+                track.width = read_u32(reader)? >> 16;
+                track.height = read_u32(reader)? >> 16;
+            }
+            b"mdia" => {
+                walk_mp4_boxes(reader, start, end, &mut |reader, box_type, _, _| {
+                    if &box_type == b"hdlr" {
+                        read_full_box_header(reader)?;
+                        read_u32(reader)?; // pre_defined
+                        let mut handler = [0u8; 4];
+                        reader.read_exact(&mut handler).ok()?;
+                        track.handler = Some(handler);
+                    }
+                    Some(())
+                })?;
+            }
+            _ => {}
+        }
+        Some(())
+    })
+}
+
+/// tkhd の変換行列（16.16 固定小数点の a, b）から時計回りの回転角を求める
+fn rotation_from_matrix(matrix: &[i32; 9]) -> u32 {
+    const ONE: i32 = 0x0001_0000;
+    match (matrix[0], matrix[1]) {
+        (0, ONE) => 90,
+        (a, 0) if a == -ONE => 180,
+        (0, b) if b == -ONE => 270,
+        _ => 0,
+    }
+}
+
+/// [start, end) の範囲にあるボックスを順に訪れる。コールバックにはボックス種別と中身の範囲を渡す
+fn walk_mp4_boxes<R, F>(reader: &mut R, start: u64, end: u64, visit: &mut F) -> Option<()>
+where
+    R: Read + Seek,
+    F: FnMut(&mut R, [u8; 4], u64, u64) -> Option<()>,
+{
+    let mut offset = start;
+    while end.saturating_sub(offset) >= 8 {
+        reader.seek(SeekFrom::Start(offset)).ok()?;
+        let size = read_u32(reader)? as u64;
+        let mut box_type = [0u8; 4];
+        reader.read_exact(&mut box_type).ok()?;
+
+        let (header_len, box_size) = match size {
+            // 64bit サイズ
+            1 => (16, read_u64(reader)?),
+            // ファイル末尾まで
+            0 => (8, end - offset),
+            _ => (8, size),
+        };
+        // 壊れたファイルの巨大な 64bit サイズで offset が巻き戻って止まらなくならないよう、桁あふれは打ち切る
+        let Some(box_end) = offset.checked_add(box_size) else {
+            break;
+        };
+        if box_size < header_len || box_end > end {
+            break;
+        }
+
+        visit(reader, box_type, offset + header_len, box_end)?;
+        offset = box_end;
+    }
+    Some(())
+}
+
+/// FullBox のバージョンとフラグ
+fn read_full_box_header<R: Read>(reader: &mut R) -> Option<(u8, u32)> {
+    let value = read_u32(reader)?;
+    Some(((value >> 24) as u8, value & 0x00FF_FFFF))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Option<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf).ok()?;
+    Some(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf).ok()?;
+    Some(u64::from_be_bytes(buf))
+}
+
+// ---------------------------------------------------------------------------
+// WebM / Matroska（EBML）
+// ---------------------------------------------------------------------------
+
+const EBML_SEGMENT: u32 = 0x1853_8067;
+const EBML_INFO: u32 = 0x1549_A966;
+const EBML_TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const EBML_DURATION: u32 = 0x4489;
+const EBML_DATE_UTC: u32 = 0x4461;
+const EBML_TRACKS: u32 = 0x1654_AE6B;
+const EBML_TRACK_ENTRY: u32 = 0xAE;
+const EBML_TRACK_TYPE: u32 = 0x83;
+const EBML_VIDEO: u32 = 0xE0;
+const EBML_PIXEL_WIDTH: u32 = 0xB0;
+const EBML_PIXEL_HEIGHT: u32 = 0xBA;
+const EBML_CLUSTER: u32 = 0x1F43_B675;
+/// Matroska の TrackType: 映像
+const TRACK_TYPE_VIDEO: u64 = 1;
+
+/// EBML 要素のヘッダ
+struct EbmlElement {
+    id: u32,
+    /// 中身の開始位置
+    start: u64,
+    /// 中身の終了位置（サイズ不明の要素は親の終端）
+    end: u64,
+}
+
+fn read_webm<R: Read + Seek>(reader: &mut R, len: u64) -> Option<VideoMetadata> {
+    let mut timestamp_scale = WEBM_DEFAULT_TIMESTAMP_SCALE;
+    let mut duration = None;
+    let mut date_utc = None;
+    let mut dimensions = None;
+
+    // EBML ヘッダ
+    let header = read_ebml_element(reader, len)?;
+    if header.id != 0x1A45_DFA3 {
+        return None;
+    }
+    reader.seek(SeekFrom::Start(header.end)).ok()?;
+
+    let segment = read_ebml_element(reader, len)?;
+    if segment.id != EBML_SEGMENT {
+        return None;
+    }
+
+    let mut offset = segment.start;
+    while offset < segment.end {
+        reader.seek(SeekFrom::Start(offset)).ok()?;
+        let Some(element) = read_ebml_element(reader, segment.end) else {
+            break;
+        };
+        match element.id {
+            EBML_INFO => {
+                for child in ebml_children(reader, &element)? {
+                    reader.seek(SeekFrom::Start(child.start)).ok()?;
+                    let size = child.end - child.start;
+                    match child.id {
+                        EBML_TIMESTAMP_SCALE => timestamp_scale = read_ebml_uint(reader, size)?,
+                        EBML_DURATION => duration = read_ebml_float(reader, size),
+                        EBML_DATE_UTC => date_utc = Some(read_ebml_uint(reader, size)? as i64),
+                        _ => {}
+                    }
+                }
+            }
+            EBML_TRACKS => {
+                for entry in ebml_children(reader, &element)? {
+                    if entry.id != EBML_TRACK_ENTRY || dimensions.is_some() {
+                        continue;
+                    }
+                    dimensions = read_webm_video_track(reader, &entry)?;
+                }
+            }
+            // ここから先は映像データ。必要な情報はすべてこれより前にある
+            EBML_CLUSTER => break,
+            _ => {}
+        }
+        offset = element.end;
+    }
+
+    let (width, height) = dimensions.unwrap_or((0, 0));
+    Some(VideoMetadata {
+        duration_ms: duration.map(|d| (d * timestamp_scale as f64 / 1_000_000.0) as u64),
+        width,
+        height,
+        rotation: 0,
+        creation_time: date_utc
+            .and_then(|ns| format_unix_time(WEBM_EPOCH_UNIX + ns.div_euclid(1_000_000_000))),
+    })
+}
+
+/// TrackEntry が映像トラックなら (PixelWidth, PixelHeight) を返す
+fn read_webm_video_track<R: Read + Seek>(
+    reader: &mut R,
+    entry: &EbmlElement,
+) -> Option<Option<(u32, u32)>> {
+    let mut track_type = None;
+    let mut dimensions = (0, 0);
+
+    for child in ebml_children(reader, entry)? {
+        reader.seek(SeekFrom::Start(child.start)).ok()?;
+        match child.id {
+            EBML_TRACK_TYPE => track_type = Some(read_ebml_uint(reader, child.end - child.start)?),
+            EBML_VIDEO => {
+                for video in ebml_children(reader, &child)? {
+                    reader.seek(SeekFrom::Start(video.start)).ok()?;
+                    let size = video.end - video.start;
+                    match video.id {
+                        EBML_PIXEL_WIDTH => dimensions.0 = read_ebml_uint(reader, size)? as u32,
+                        EBML_PIXEL_HEIGHT => dimensions.1 = read_ebml_uint(reader, size)? as u32,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some((track_type == Some(TRACK_TYPE_VIDEO)).then_some(dimensions))
+}
+
+/// 要素の直下の子要素を列挙する
+fn ebml_children<R: Read + Seek>(reader: &mut R, parent: &EbmlElement) -> Option<Vec<EbmlElement>> {
+    let mut children = Vec::new();
+    let mut offset = parent.start;
+    while offset < parent.end {
+        reader.seek(SeekFrom::Start(offset)).ok()?;
+        let Some(child) = read_ebml_element(reader, parent.end) else {
+            break;
+        };
+        offset = child.end;
+        children.push(child);
+    }
+    Some(children)
+}
+
+/// 現在位置から要素ヘッダ（ID + サイズ）を読む
+fn read_ebml_element<R: Read + Seek>(reader: &mut R, parent_end: u64) -> Option<EbmlElement> {
+    let (id, _) = read_ebml_vint(reader, true)?;
+    let (size, unknown) = read_ebml_vint(reader, false)?;
+    let start = reader.stream_position().ok()?;
+    let end = if unknown {
+        parent_end
+    } else {
+        start.checked_add(size)?.min(parent_end)
+    };
+    Some(EbmlElement {
+        id: id as u32,
+        start,
+        end,
+    })
+}
+
+/// 可変長整数を読む。`keep_marker` が true なら（要素 ID として）先頭の長さビットを残す
+/// 2つ目の戻り値はサイズ不明（値のビットがすべて 1）かどうか
+fn read_ebml_vint<R: Read>(reader: &mut R, keep_marker: bool) -> Option<(u64, bool)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first).ok()?;
+    let len = first[0].leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+
+    let mut value = if keep_marker {
+        first[0] as u64
+    } else {
+        first[0] as u64 & ((1 << (8 - len)) - 1)
+    };
+    let mut rest = [0u8; 7];
+    reader.read_exact(&mut rest[..len - 1]).ok()?;
+    for &byte in &rest[..len - 1] {
+        value = (value << 8) | byte as u64;
+    }
+
+    let all_ones = (1u64 << (7 * len)) - 1;
+    Some((value, !keep_marker && value == all_ones))
+}
+
+fn read_ebml_uint<R: Read>(reader: &mut R, size: u64) -> Option<u64> {
+    if size > 8 {
+        return None;
+    }
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf[8 - size as usize..]).ok()?;
+    Some(u64::from_be_bytes(buf))
+}
+
+fn read_ebml_float<R: Read>(reader: &mut R, size: u64) -> Option<f64> {
+    match size {
+        4 => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf).ok()?;
+            Some(f32::from_be_bytes(buf) as f64)
+        }
+        8 => {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf).ok()?;
+            Some(f64::from_be_bytes(buf))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sss_video_{}_{name}", std::process::id()))
+    }
+
+    fn mp4_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(box_type);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// 縦持ちで撮ったスマホ動画（1920x1080 + 90°回転行列、5秒、2024-01-01 作成）
+    fn portrait_mp4() -> Vec<u8> {
+        let creation = (1_704_067_200 + MP4_EPOCH_OFFSET) as u32;
+
+        let mut mvhd = vec![0; 4];
+        for value in [creation, creation, 1000, 5000] {
+            mvhd.extend_from_slice(&value.to_be_bytes());
+        }
+        mvhd.extend_from_slice(&[0; 80]);
+
+        let mut tkhd = vec![0, 0, 0, 3];
+        tkhd.extend_from_slice(&[0; 20 + 16]);
+        let one = 0x0001_0000i32;
+        for value in [0, one, 0, -one, 0, 0, 0, 0, 0x4000_0000] {
+            tkhd.extend_from_slice(&value.to_be_bytes());
+        }
+        tkhd.extend_from_slice(&(1920u32 << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(1080u32 << 16).to_be_bytes());
+
+        let mut hdlr = vec![0; 8];
+        hdlr.extend_from_slice(b"vide");
+        hdlr.extend_from_slice(&[0; 13]);
+
+        let mdia = mp4_box(b"mdia", &mp4_box(b"hdlr", &hdlr));
+        let trak = mp4_box(b"trak", &[mp4_box(b"tkhd", &tkhd), mdia].concat());
+        let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), trak].concat());
+
+        [
+            mp4_box(b"ftyp", b"isom\0\0\0\0isom"),
+            mp4_box(b"mdat", &[0; 32]),
+            moov,
+        ]
+        .concat()
+    }
+
+    fn ebml(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        // 8バイト長のサイズ表現（0x01 + 7バイト）
+        let mut data = id.to_vec();
+        data.push(0x01);
+        data.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn test_read_mp4_metadata() {
+        let path = temp_path("portrait.mp4");
+        std::fs::write(&path, portrait_mp4()).unwrap();
+
+        let metadata = read_video_metadata(&path).expect("MP4 のはず");
+        assert_eq!(metadata.duration_ms, Some(5000));
+        assert_eq!((metadata.width, metadata.height), (1080, 1920));
+        assert_eq!(metadata.rotation, 90);
+        assert_eq!(
+            metadata.creation_time.as_deref(),
+            Some("2024-01-01 00:00:00")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_webm_metadata() {
+        // 2024-01-01 00:00:00 UTC（2001-01-01 起点のナノ秒）
+        let date_ns = (1_704_067_200 - WEBM_EPOCH_UNIX) * 1_000_000_000;
+        let info = [
+            ebml(&[0x2A, 0xD7, 0xB1], &1_000_000u32.to_be_bytes()),
+            ebml(&[0x44, 0x89], &2500.0f64.to_be_bytes()),
+            ebml(&[0x44, 0x61], &date_ns.to_be_bytes()),
+        ]
+        .concat();
+        let audio = ebml(&[0xAE], &ebml(&[0x83], &[2]));
+        let video = ebml(
+            &[0xAE],
+            &[
+                ebml(&[0x83], &[1]),
+                ebml(
+                    &[0xE0],
+                    &[ebml(&[0xB0], &[0x02, 0x80]), ebml(&[0xBA], &[0x01, 0x68])].concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let tracks = ebml(&[0x16, 0x54, 0xAE, 0x6B], &[audio, video].concat());
+
+        // Segment はサイズ不明（ライブ録画などで多い）
+        let mut webm = ebml(&[0x1A, 0x45, 0xDF, 0xA3], &ebml(&[0x42, 0x82], b"webm"));
+        webm.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+        webm.extend(ebml(&[0x15, 0x49, 0xA9, 0x66], &info));
+        webm.extend(tracks);
+        webm.extend(ebml(&[0x1F, 0x43, 0xB6, 0x75], &[0; 16]));
+
+        let path = temp_path("clip.webm");
+        std::fs::write(&path, webm).unwrap();
+
+        let metadata = read_video_metadata(&path).expect("WebM のはず");
+        assert_eq!(metadata.duration_ms, Some(2500));
+        assert_eq!((metadata.width, metadata.height), (640, 360));
+        assert_eq!(metadata.rotation, 0);
+        assert_eq!(
+            metadata.creation_time.as_deref(),
+            Some("2024-01-01 00:00:00")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unsupported_or_broken_video() {
+        let path = temp_path("broken.mp4");
+        std::fs::write(&path, b"not a video").unwrap();
+        assert_eq!(read_video_metadata(&path), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_mp4_overflowing_sizes() {
+        // u64::MAX 近くの 64bit サイズを持つボックス（桁あふれで先頭に巻き戻らず、そこで打ち切る）
+        let mut huge = 1u32.to_be_bytes().to_vec();
+        huge.extend_from_slice(b"free");
+        huge.extend_from_slice(&(u64::MAX - 8).to_be_bytes());
+        let data = [mp4_box(b"ftyp", b"isom\0\0\0\0isom"), huge, portrait_mp4()].concat();
+        let path = temp_path("huge_box.mp4");
+        std::fs::write(&path, data).unwrap();
+        assert_eq!(read_video_metadata(&path), None);
+
+        // version 1 の mvhd で、ミリ秒にすると u64 に収まらない再生時間・範囲外の作成日時
+        let mut mvhd = vec![1, 0, 0, 0];
+        mvhd.extend_from_slice(&u64::MAX.to_be_bytes());
+        mvhd.extend_from_slice(&0u64.to_be_bytes());
+        mvhd.extend_from_slice(&1u32.to_be_bytes());
+        mvhd.extend_from_slice(&u64::MAX.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 80]);
+        let data = [
+            mp4_box(b"ftyp", b"isom\0\0\0\0isom"),
+            mp4_box(b"moov", &mp4_box(b"mvhd", &mvhd)),
+        ]
+        .concat();
+        std::fs::write(&path, data).unwrap();
+        let metadata = read_video_metadata(&path).expect("moov はある");
+        assert_eq!(metadata.duration_ms, None);
+        assert_eq!(metadata.creation_time, None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,619 @@
+//! XMP / IPTC メタデータ（レーティング・キーワード・キャプション・ラベル）の読み取り。
+//!
+//! Lightroom や darktable で付けた情報を、画像に埋め込まれた XMP パケット
+//! （JPEG APP1 / PNG iTXt / WebP "XMP " チャンク / TIFF タグ 700）、
+//! JPEG の IPTC-IIM（APP13）、`.xmp` サイドカーから読む。
+//! 同じ項目が複数にある場合はサイドカー → 埋め込み XMP → IPTC の順に優先する。
+//!
+//! XMP は RDF/XML だが、必要なのは決まった数個のプロパティだけなので、
+//! XML パーサは使わずに属性形式（`xmp:Rating="3"`）と要素形式（`<xmp:Rating>3</xmp:Rating>`）の両方を拾う。
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::UNIX_EPOCH;
+
+/// JPEG APP1 の XMP 識別子
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// JPEG APP13 の Photoshop IRB 識別子
+const JPEG_PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+/// IPTC-IIM を格納する Photoshop リソース ID
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+/// PNG iTXt の XMP キーワード
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+/// 1チャンク・1セグメントとして読み込む上限（壊れたファイルで巨大な確保をしないように）
+const MAX_PACKET_SIZE: u64 = 16 * 1024 * 1024;
+
+static SUBJECT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:subject\b[^>]*>(.*?)</dc:subject>").expect("valid regex")
+});
+static DESCRIPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:description\b[^>]*>(.*?)</dc:description>").expect("valid regex")
+});
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<rdf:li\b([^>]*)>(.*?)</rdf:li>").expect("valid regex"));
+static RATING: LazyLock<Regex> = LazyLock::new(|| property_regex("xmp:Rating"));
+static LABEL: LazyLock<Regex> = LazyLock::new(|| property_regex("xmp:Label"));
+
+/// XMP / IPTC から読み取ったメタデータ
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XmpMetadata {
+    /// レーティング（xmp:Rating。0〜5、却下は -1）
+    pub rating: Option<i32>,
+    /// キーワード（dc:subject、なければ IPTC Keywords）
+    pub keywords: Vec<String>,
+    /// キャプション（dc:description の既定言語、なければ IPTC Caption-Abstract）
+    pub caption: Option<String>,
+    /// カラーラベル（xmp:Label。"Red" 等）
+    pub label: Option<String>,
+}
+
+impl XmpMetadata {
+    /// どの項目も設定されていないか
+    pub fn is_empty(&self) -> bool {
+        self.rating.is_none()
+            && self.keywords.is_empty()
+            && self.caption.is_none()
+            && self.label.is_none()
+    }
+
+    /// 欠けている項目を other で埋める（self の値を優先）
+    fn or(self, other: XmpMetadata) -> XmpMetadata {
+        XmpMetadata {
+            rating: self.rating.or(other.rating),
+            keywords: if self.keywords.is_empty() {
+                other.keywords
+            } else {
+                self.keywords
+            },
+            caption: self.caption.or(other.caption),
+            label: self.label.or(other.label),
+        }
+    }
+}
+
+/// プレイリストに入れる画像をメタデータで絞り込む条件（未設定の項目は絞り込まない）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataFilter {
+    /// このレーティング以上だけ（レーティングなしは 0 扱い。0 を指定すると却下だけを除く）
+    pub min_rating: Option<i32>,
+    /// いずれかのキーワードを持つものだけ（大文字小文字を区別しない）
+    pub keywords: Vec<String>,
+    /// このラベルのものだけ（大文字小文字を区別しない）
+    pub label: Option<String>,
+}
+
+impl MetadataFilter {
+    /// 設定値（"3" / "家族, 旅行" / "Red"）から作る。空文字は未設定扱い
+    pub fn from_settings(
+        min_rating: Option<&str>,
+        keywords: Option<&str>,
+        label: Option<&str>,
+    ) -> Self {
+        MetadataFilter {
+            min_rating: min_rating.and_then(|v| v.trim().parse().ok()),
+            keywords: keywords
+                .map(|v| {
+                    v.split(',')
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            label: label
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+        }
+    }
+
+    /// 絞り込み条件が1つでもあるか
+    pub fn is_active(&self) -> bool {
+        self.min_rating.is_some() || !self.keywords.is_empty() || self.label.is_some()
+    }
+
+    /// 条件を満たすか（メタデータのない画像はレーティング 0・キーワードなし・ラベルなしとして判定）
+    pub fn matches(&self, metadata: Option<&XmpMetadata>) -> bool {
+        let empty = XmpMetadata::default();
+        let metadata = metadata.unwrap_or(&empty);
+
+        if let Some(min_rating) = self.min_rating {
+            if metadata.rating.unwrap_or(0) < min_rating {
+                return false;
+            }
+        }
+
+        if !self.keywords.is_empty()
+            && !self.keywords.iter().any(|wanted| {
+                metadata
+                    .keywords
+                    .iter()
+                    .any(|keyword| keyword.to_lowercase() == wanted.to_lowercase())
+            })
+        {
+            return false;
+        }
+
+        if let Some(label) = &self.label {
+            if !metadata
+                .label
+                .as_ref()
+                .is_some_and(|l| l.eq_ignore_ascii_case(label))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// 画像の XMP / IPTC メタデータを読む（何もなければ空のメタデータ）
+pub fn read_xmp_metadata(path: &Path) -> XmpMetadata {
+    let sidecar = sidecar_paths(path)
+        .into_iter()
+        .find_map(|sidecar| std::fs::read(sidecar).ok())
+        .map(|xml| parse_xmp(&String::from_utf8_lossy(&xml)))
+        .unwrap_or_default();
+    let embedded = read_embedded_xmp(path)
+        .map(|xml| parse_xmp(&xml))
+        .unwrap_or_default();
+    let iptc = read_jpeg_iptc(path).unwrap_or_default();
+
+    sidecar.or(embedded).or(iptc)
+}
+
+/// サイドカーの候補（Lightroom の "photo.xmp" と darktable の "photo.jpg.xmp"）
+pub fn sidecar_paths(path: &Path) -> Vec<PathBuf> {
+    let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    vec![
+        path.with_extension("xmp"),
+        path.with_file_name(format!("{file_name}.xmp")),
+    ]
+}
+
+/// メタデータの鮮度判定に使う更新日時（画像とサイドカーのうち新しい方、UNIX 秒）
+/// 画像を変更せずにサイドカーだけ書き換えた場合も読み直せるようにする
+pub fn metadata_modified_time(path: &Path, modified_time: i64) -> i64 {
+    sidecar_paths(path)
+        .iter()
+        .filter_map(|sidecar| std::fs::metadata(sidecar).ok()?.modified().ok())
+        .filter_map(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .fold(modified_time, i64::max)
+}
+
+/// XMP パケット（RDF/XML）から必要なプロパティを取り出す
+pub fn parse_xmp(xml: &str) -> XmpMetadata {
+    let rating = property(xml, &RATING)
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .map(|v| v.round() as i32);
+    let label = property(xml, &LABEL).filter(|v| !v.is_empty());
+
+    let keywords = SUBJECT
+        .captures(xml)
+        .map(|caps| {
+            LIST_ITEM
+                .captures_iter(&caps[1])
+                .map(|item| decode_entities(item[2].trim()))
+                .filter(|k| !k.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // 複数言語がある場合は既定言語（x-default）を優先
+    let caption = DESCRIPTION.captures(xml).and_then(|caps| {
+        let items: Vec<_> = LIST_ITEM.captures_iter(&caps[1]).collect();
+        items
+            .iter()
+            .find(|item| item[1].contains("x-default"))
+            .or(items.first())
+            .map(|item| decode_entities(item[2].trim()))
+            .filter(|c| !c.is_empty())
+    });
+
+    XmpMetadata {
+        rating,
+        keywords,
+        caption,
+        label,
+    }
+}
+
+/// 単純なプロパティを属性形式・要素形式のどちらでも拾う正規表現
+fn property_regex(name: &str) -> Regex {
+    let name = regex::escape(name);
+    Regex::new(&format!(
+        r#"(?s)\b{name}\s*=\s*["']([^"']*)["']|<{name}>(.*?)</{name}>"#
+    ))
+    .expect("valid regex")
+}
+
+/// 単純なプロパティの値
+fn property(xml: &str, pattern: &Regex) -> Option<String> {
+    let caps = pattern.captures(xml)?;
+    let value = caps.get(1).or_else(|| caps.get(2))?;
+    Some(decode_entities(value.as_str().trim()))
+}
+
+/// XML の文字参照・実体参照を戻す
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(';') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let entity = &after[..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => out.push(c),
+            None => out.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 画像に埋め込まれた XMP パケットを読む
+fn read_embedded_xmp(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    let packet = match ext.as_str() {
+        "jpg" | "jpeg" => jpeg_segments(path, 0xE1, JPEG_XMP_HEADER)?,
+        "png" => png_xmp(path)?,
+        "webp" => webp_xmp(path)?,
+        "tif" | "tiff" => tiff_xmp(path)?,
+        _ => return None,
+    };
+    Some(String::from_utf8_lossy(&packet).into_owned())
+}
+
+/// JPEG の指定マーカーのうち、header で始まる最初のセグメントの中身（header を除く）
+fn jpeg_segments(path: &Path, marker: u8, header: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut soi = [0u8; 2];
+    reader.read_exact(&mut soi).ok()?;
+    if soi != [0xFF, 0xD8] {
+        return None;
+    }
+
+    loop {
+        let mut head = [0u8; 4];
+        reader.read_exact(&mut head).ok()?;
+        if head[0] != 0xFF {
+            return None;
+        }
+        // 画像データ（SOS）以降にメタデータのセグメントはない
+        if head[1] == 0xDA || head[1] == 0xD9 {
+            return None;
+        }
+        let len = u16::from_be_bytes([head[2], head[3]]).checked_sub(2)? as usize;
+        if head[1] == marker && len >= header.len() {
+            let mut data = vec![0u8; len];
+            reader.read_exact(&mut data).ok()?;
+            if let Some(payload) = data.strip_prefix(header) {
+                return Some(payload.to_vec());
+            }
+        } else {
+            reader.seek(SeekFrom::Current(len as i64)).ok()?;
+        }
+    }
+}
+
+/// PNG の iTXt チャンク（キーワード "XML:com.adobe.xmp"、非圧縮）
+fn png_xmp(path: &Path) -> Option<Vec<u8>> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature).ok()?;
+    if &signature != b"\x89PNG\r\n\x1a\n" {
+        return None;
+    }
+
+    loop {
+        let mut head = [0u8; 8];
+        reader.read_exact(&mut head).ok()?;
+        let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as u64;
+        let kind = &head[4..8];
+        if kind == b"IEND" || len > MAX_PACKET_SIZE {
+            return None;
+        }
+        if kind == b"iTXt" {
+            let mut data = vec![0u8; len as usize];
+            reader.read_exact(&mut data).ok()?;
+            // keyword \0 圧縮フラグ 圧縮方式 言語 \0 翻訳キーワード \0 テキスト
+            if let Some(rest) = data
+                .strip_prefix(PNG_XMP_KEYWORD)
+                .and_then(|r| r.strip_prefix(b"\0"))
+            {
+                if rest.first() != Some(&0) {
+                    return None; // 圧縮された XMP は対象外
+                }
+                let rest = rest.get(2..)?;
+                let lang_end = rest.iter().position(|&b| b == 0)?;
+                let rest = &rest[lang_end + 1..];
+                let translated_end = rest.iter().position(|&b| b == 0)?;
+                return Some(rest[translated_end + 1..].to_vec());
+            }
+            reader.seek(SeekFrom::Current(4)).ok()?; // CRC
+        } else {
+            reader.seek(SeekFrom::Current(len as i64 + 4)).ok()?;
+        }
+    }
+}
+
+/// WebP（RIFF）の "XMP " チャンク
+fn webp_xmp(path: &Path) -> Option<Vec<u8>> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).ok()?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WEBP" {
+        return None;
+    }
+
+    loop {
+        let mut head = [0u8; 8];
+        reader.read_exact(&mut head).ok()?;
+        let len = u32::from_le_bytes([head[4], head[5], head[6], head[7]]) as u64;
+        // チャンクは偶数長にパディングされる
+        let padded = len + (len & 1);
+        if &head[0..4] == b"XMP " && len <= MAX_PACKET_SIZE {
+            let mut data = vec![0u8; len as usize];
+            reader.read_exact(&mut data).ok()?;
+            return Some(data);
+        }
+        reader.seek(SeekFrom::Current(padded as i64)).ok()?;
+    }
+}
+
+/// TIFF の IFD0 タグ 700（XMP）
+fn tiff_xmp(path: &Path) -> Option<Vec<u8>> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let exif = exif::Reader::new().read_from_container(&mut reader).ok()?;
+    let field = exif.get_field(exif::Tag(exif::Context::Tiff, 700), exif::In::PRIMARY)?;
+    match &field.value {
+        exif::Value::Byte(data) | exif::Value::Undefined(data, _) => Some(data.clone()),
+        _ => None,
+    }
+}
+
+/// JPEG APP13 の IPTC-IIM からキャプション（2:120）とキーワード（2:25）を読む
+fn read_jpeg_iptc(path: &Path) -> Option<XmpMetadata> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    if ext != "jpg" && ext != "jpeg" {
+        return None;
+    }
+    let irb = jpeg_segments(path, 0xED, JPEG_PHOTOSHOP_HEADER)?;
+    let iim = photoshop_resource(&irb, IPTC_RESOURCE_ID)?;
+    Some(parse_iptc(iim))
+}
+
+/// Photoshop Image Resource Block から指定 ID のリソースを探す
+fn photoshop_resource(mut data: &[u8], id: u16) -> Option<&[u8]> {
+    while data.len() >= 12 && data.starts_with(b"8BIM") {
+        let resource_id = u16::from_be_bytes([data[4], data[5]]);
+        // 名前は Pascal 文字列で、長さバイトを含めて偶数長にパディングされる
+        let name_len = data[6] as usize;
+        let name_total = (name_len + 1 + 1) & !1;
+        let size_at = 6 + name_total;
+        let size = u32::from_be_bytes(data.get(size_at..size_at + 4)?.try_into().ok()?) as usize;
+        let body_at = size_at + 4;
+        let body = data.get(body_at..body_at + size)?;
+        if resource_id == id {
+            return Some(body);
+        }
+        data = data.get(body_at + size + (size & 1)..)?;
+    }
+    None
+}
+
+/// IPTC-IIM のレコード 2 からキャプションとキーワードを取り出す
+fn parse_iptc(mut data: &[u8]) -> XmpMetadata {
+    let mut metadata = XmpMetadata::default();
+    while data.len() >= 5 && data[0] == 0x1C {
+        let (record, dataset) = (data[1], data[2]);
+        let size = u16::from_be_bytes([data[3], data[4]]) as usize;
+        let Some(value) = data.get(5..5 + size) else {
+            break;
+        };
+        let text = String::from_utf8_lossy(value).trim().to_string();
+        match (record, dataset) {
+            (2, 25) if !text.is_empty() => metadata.keywords.push(text),
+            (2, 120) if !text.is_empty() => metadata.caption = Some(text),
+            _ => {}
+        }
+        data = &data[5 + size..];
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sss_xmp_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Lightroom が書き出す形式（要素形式・言語別キャプション）
+    const LIGHTROOM_XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/">
+   <xmp:Rating>4</xmp:Rating>
+   <xmp:Label>Red</xmp:Label>
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>家族</rdf:li>
+     <rdf:li>Beach &amp; Sea</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <dc:description>
+    <rdf:Alt>
+     <rdf:li xml:lang="ja">浜辺にて</rdf:li>
+     <rdf:li xml:lang="x-default">At the beach</rdf:li>
+    </rdf:Alt>
+   </dc:description>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    /// darktable が書き出す形式（属性形式）
+    const DARKTABLE_XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmp:Rating="-1"
+    xmp:Label='Green'>
+   <dc:subject><rdf:Bag><rdf:li>rejected</rdf:li></rdf:Bag></dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_xmp_forms() {
+        let lightroom = parse_xmp(LIGHTROOM_XMP);
+        assert_eq!(lightroom.rating, Some(4));
+        assert_eq!(lightroom.label.as_deref(), Some("Red"));
+        assert_eq!(lightroom.keywords, vec!["家族", "Beach & Sea"]);
+        assert_eq!(lightroom.caption.as_deref(), Some("At the beach"));
+
+        let darktable = parse_xmp(DARKTABLE_XMP);
+        assert_eq!(darktable.rating, Some(-1));
+        assert_eq!(darktable.label.as_deref(), Some("Green"));
+        assert_eq!(darktable.keywords, vec!["rejected"]);
+        assert_eq!(darktable.caption, None);
+
+        assert!(parse_xmp("<x:xmpmeta/>").is_empty());
+    }
+
+    /// APP1（XMP）と APP13（IPTC）を埋め込んだ最小の JPEG（SOI + セグメント + EOI）
+    fn jpeg_with_metadata(xmp: &str, caption: &str, keywords: &[&str]) -> Vec<u8> {
+        let segment = |marker: u8, payload: &[u8]| {
+            let mut out = vec![0xFF, marker];
+            out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            out.extend_from_slice(payload);
+            out
+        };
+
+        let mut iim = Vec::new();
+        let mut dataset = |number: u8, value: &str| {
+            iim.extend_from_slice(&[0x1C, 2, number]);
+            iim.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            iim.extend_from_slice(value.as_bytes());
+        };
+        dataset(120, caption);
+        for keyword in keywords {
+            dataset(25, keyword);
+        }
+
+        let mut irb = JPEG_PHOTOSHOP_HEADER.to_vec();
+        irb.extend_from_slice(b"8BIM");
+        irb.extend_from_slice(&IPTC_RESOURCE_ID.to_be_bytes());
+        irb.extend_from_slice(&[0, 0]); // 空の名前（長さバイト + パディング）
+        irb.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        irb.extend_from_slice(&iim);
+
+        let mut app1 = JPEG_XMP_HEADER.to_vec();
+        app1.extend_from_slice(xmp.as_bytes());
+
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(segment(0xE1, &app1));
+        jpeg.extend(segment(0xED, &irb));
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_embedded_iptc_and_sidecar_precedence() {
+        let dir = temp_dir("precedence");
+        let photo = dir.join("photo.jpg");
+
+        // 埋め込み XMP にはレーティングだけ、キャプションとキーワードは IPTC から
+        let embedded = r#"<x:xmpmeta><rdf:Description xmp:Rating="2"/></x:xmpmeta>"#;
+        std::fs::write(
+            &photo,
+            jpeg_with_metadata(embedded, "IPTC caption", &["iptc", "keyword"]),
+        )
+        .unwrap();
+        let metadata = read_xmp_metadata(&photo);
+        assert_eq!(metadata.rating, Some(2));
+        assert_eq!(metadata.caption.as_deref(), Some("IPTC caption"));
+        assert_eq!(metadata.keywords, vec!["iptc", "keyword"]);
+
+        // サイドカー（darktable 形式の photo.jpg.xmp）の値が優先され、ない項目は埋め込みから
+        std::fs::write(dir.join("photo.jpg.xmp"), DARKTABLE_XMP).unwrap();
+        let metadata = read_xmp_metadata(&photo);
+        assert_eq!(metadata.rating, Some(-1));
+        assert_eq!(metadata.keywords, vec!["rejected"]);
+        assert_eq!(metadata.caption.as_deref(), Some("IPTC caption"));
+        assert!(metadata_modified_time(&photo, 0) > 0);
+
+        // 何もなければ空
+        let plain = dir.join("plain.png");
+        std::fs::write(&plain, b"\x89PNG\r\n\x1a\n").unwrap();
+        assert!(read_xmp_metadata(&plain).is_empty());
+        assert_eq!(metadata_modified_time(&plain, 42), 42);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let rated = parse_xmp(LIGHTROOM_XMP);
+        let rejected = parse_xmp(DARKTABLE_XMP);
+
+        // 条件なしは全部通す
+        let none = MetadataFilter::from_settings(Some(""), Some(" , "), None);
+        assert!(!none.is_active());
+        assert!(none.matches(None));
+
+        // ★3以上。メタデータなしは 0 扱い
+        let min3 = MetadataFilter::from_settings(Some("3"), None, None);
+        assert!(min3.matches(Some(&rated)));
+        assert!(!min3.matches(Some(&rejected)));
+        assert!(!min3.matches(None));
+
+        // 0 以上 = 却下だけ除く
+        let not_rejected = MetadataFilter::from_settings(Some("0"), None, None);
+        assert!(not_rejected.matches(None));
+        assert!(!not_rejected.matches(Some(&rejected)));
+
+        // キーワード（いずれか）・ラベルは大文字小文字を区別しない
+        let keywords = MetadataFilter::from_settings(None, Some("beach & sea, 旅行"), None);
+        assert!(keywords.matches(Some(&rated)));
+        assert!(!keywords.matches(Some(&rejected)));
+        let label = MetadataFilter::from_settings(None, None, Some("red"));
+        assert!(label.matches(Some(&rated)));
+        assert!(!label.matches(Some(&rejected)));
+    }
+}
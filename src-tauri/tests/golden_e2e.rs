@@ -13,6 +13,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use sss_lib::ignore::IgnoreFilter;
+use sss_lib::path_norm::{canonical_path, strip_path_prefix, CasePolicy};
 use sss_lib::playlist::Playlist;
 use sss_lib::scanner::ImageScanner;
 
@@ -65,14 +66,14 @@ fn ignore_patterns() -> Vec<String> {
 
 /// scan 結果の絶対パスを root 相対・スラッシュ正規化した集合に変換する。
 fn relative_set(root: &Path, paths: &[String]) -> BTreeSet<String> {
+    // scan したパスは正規形（`/` 区切り）で返る
+    let root = canonical_path(root);
     paths
         .iter()
         .map(|p| {
-            Path::new(p)
-                .strip_prefix(root)
+            strip_path_prefix(p, &root, CasePolicy::Sensitive)
                 .expect("scan したパスは root 配下のはず")
-                .to_string_lossy()
-                .replace('\\', "/")
+                .to_string()
         })
         .collect()
 }
@@ -234,3 +235,194 @@ fn incremental_scan_detects_added_and_deleted() {
 
     let _ = std::fs::remove_dir_all(&root);
 }
+
+/// ライブラリのルートを基準にした除外ルールで scan し、収集集合を返す。
+fn scan_with_rules(root: &Path, patterns: &[&str]) -> BTreeSet<String> {
+    let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+    let scanner = ImageScanner::new(IgnoreFilter::from_patterns(&patterns).with_root(root));
+    let files = scanner
+        .scan_directory_with_progress(root, |_, _| {})
+        .expect("scan");
+    relative_set(
+        root,
+        &files.iter().map(|f| f.path.clone()).collect::<Vec<_>>(),
+    )
+}
+
+/// gitignore 規則の検証用フィクスチャ（同じ名前がルート直下と深い階層の両方にある）。
+fn build_rules_fixture(root: &Path) {
+    write_file(root, "a.jpg", b"a");
+    write_file(root, "keep.png", b"keep");
+    write_file(root, "drop.png", b"drop");
+    write_file(root, "raw/r.jpg", b"r");
+    write_file(root, "2024/raw/r.jpg", b"r2");
+    write_file(root, "2024/edits/e.jpg", b"e");
+    write_file(root, "2024/edits.jpg", b"file named like the dir");
+    write_file(root, "old/keep.png", b"old keep");
+    write_file(root, "old/x.jpg", b"old x");
+}
+
+fn set(paths: &[&str]) -> BTreeSet<String> {
+    paths.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn ignore_unanchored_name_matches_at_any_depth() {
+    let root = workspace("rule_name");
+    build_rules_fixture(&root);
+
+    // `/` を含まない名前はどの階層のファイル・フォルダにもマッチする
+    let got = scan_with_rules(&root, &["raw", "*.png"]);
+    assert_eq!(
+        got,
+        set(&["a.jpg", "2024/edits/e.jpg", "2024/edits.jpg", "old/x.jpg"])
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ignore_anchored_pattern_is_relative_to_library_root() {
+    let root = workspace("rule_anchor");
+    build_rules_fixture(&root);
+
+    // 先頭 `/`・途中 `/` を含むパターンはライブラリのルートからの位置に固定
+    let got = scan_with_rules(&root, &["/raw", "2024/edits/*.jpg"]);
+    assert!(!got.contains("raw/r.jpg"));
+    assert!(
+        got.contains("2024/raw/r.jpg"),
+        "深い階層の raw まで除外された"
+    );
+    assert!(!got.contains("2024/edits/e.jpg"));
+    assert_eq!(got.len(), 7);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ignore_trailing_slash_matches_directories_only() {
+    let root = workspace("rule_dir");
+    build_rules_fixture(&root);
+
+    // 末尾 `/` のルールはフォルダにだけ効き、同じ名前のファイルには効かない
+    let got = scan_with_rules(&root, &["edits/", "edits.jpg/"]);
+    assert!(!got.contains("2024/edits/e.jpg"));
+    assert!(
+        got.contains("2024/edits.jpg"),
+        "ファイルにディレクトリ専用ルールが効いた"
+    );
+    assert_eq!(got.len(), 8);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ignore_negation_and_last_match_wins() {
+    let root = workspace("rule_negation");
+    build_rules_fixture(&root);
+
+    // `!` で取り消し、後の行ほど優先
+    let got = scan_with_rules(&root, &["*.png", "!keep.png"]);
+    assert!(got.contains("keep.png") && got.contains("old/keep.png"));
+    assert!(!got.contains("drop.png"));
+
+    // 順序を逆にすると後の `*.png` が勝つ
+    let got = scan_with_rules(&root, &["!keep.png", "*.png"]);
+    assert!(!got.contains("keep.png") && !got.contains("old/keep.png"));
+
+    // 除外したフォルダの中身は取り消せない（スキャン時にフォルダごと飛ばす）
+    let got = scan_with_rules(&root, &["old/", "!old/keep.png"]);
+    assert!(got.iter().all(|p| !p.starts_with("old/")));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ignore_legacy_absolute_and_glob_patterns_still_apply() {
+    let root = workspace("rule_legacy");
+    build_rules_fixture(&root);
+
+    // exclude_image が保存してきた絶対パス（ファイル / フォルダ直下）と旧来の `**/x/**`
+    let file = root.join("a.jpg").to_string_lossy().to_string();
+    let directory = format!("{}/*", root.join("2024").to_string_lossy());
+    let got = scan_with_rules(&root, &[&file, &directory, "**/old/**"]);
+    assert_eq!(got, set(&["keep.png", "drop.png", "raw/r.jpg"]));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ignore_per_directory_files_apply_to_their_subtree() {
+    let root = workspace("rule_nested");
+    build_rules_fixture(&root);
+    // フォルダの .sssignore はそのフォルダからの相対パスで、その配下にだけ効く
+    write_file(&root, "2024/.sssignore", b"# 2024 only\n/edits\nr.jpg\n");
+    // 深いフォルダのファイルは DB のルールを `!` で取り消せる
+    write_file(&root, "old/.sssignore", b"!keep.png\n");
+
+    let got = scan_with_rules(&root, &["*.png"]);
+    assert_eq!(
+        got,
+        set(&[
+            "a.jpg",
+            "raw/r.jpg",
+            "2024/edits.jpg",
+            "old/keep.png",
+            "old/x.jpg"
+        ])
+    );
+
+    // 除外したフォルダの中の .sssignore は読まない
+    let got = scan_with_rules(&root, &["old/"]);
+    assert!(got.iter().all(|p| !p.starts_with("old/")));
+
+    // スキャン以外（最近の画像など）でも、判定前に祖先の .sssignore を読み込めば同じ結果になる
+    let mut filter = IgnoreFilter::from_patterns(&["*.png".to_string()]).with_root(&root);
+    for (rel, ignored) in [
+        ("2024/raw/r.jpg", true),
+        ("old/keep.png", false),
+        ("keep.png", true),
+    ] {
+        let path = root.join(rel);
+        filter.load_ignore_files_along(&path);
+        assert_eq!(filter.is_ignored(&path), ignored, "{rel}");
+    }
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn incremental_scan_picks_up_changed_per_directory_files() {
+    let root = workspace("rule_nested_incremental");
+    build_rules_fixture(&root);
+
+    let scanner = ImageScanner::new(IgnoreFilter::from_patterns(&[]).with_root(&root));
+    let first = scanner
+        .scan_directory_with_progress(&root, |_, _| {})
+        .expect("first scan");
+    let previous: Vec<(String, i64, i64)> = first
+        .iter()
+        .map(|f| (f.path.clone(), f.modified_time, f.file_size))
+        .collect();
+
+    // .sssignore を置くと、次のスキャンでは外れる（ファイルはあるので削除扱いにはせず DB に残す）
+    write_file(&root, "old/.sssignore", b"*.jpg\n");
+    let result = scanner
+        .scan_directory_incremental_with_progress(&root, previous.clone(), |_, _| {})
+        .expect("incremental scan");
+    let x = canonical_path(&root.join("old/x.jpg"));
+    assert!(result.files.iter().all(|f| f.path != x));
+    assert!(result.deleted_files.is_empty());
+    assert!(result.new_files.is_empty());
+
+    // 消すと戻ってくる（DB に残っていた分はそのまま使う）
+    std::fs::remove_file(root.join("old/.sssignore")).unwrap();
+    let result = scanner
+        .scan_directory_incremental_with_progress(&root, previous, |_, _| {})
+        .expect("incremental scan");
+    assert!(result.files.iter().any(|f| f.path == x));
+    assert!(result.new_files.is_empty());
+    assert!(result.deleted_files.is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -11,12 +11,21 @@ import {
   Pause,
   Play,
   Ellipsis,
+  Undo2,
 } from 'lucide-react';
-import type { ImageInfo } from '../types';
-import { openInExplorer, pickImage, excludeImage } from '../lib/tauri';
-import { useState, useMemo } from 'react';
+import type { CaptureDateSource, ExifInfo, ImageInfo } from '../types';
+import { openInExplorer, pickImage, excludeImage, undoAction, redoAction } from '../lib/tauri';
+import { useState, useMemo, useEffect, useRef } from 'react';
 import { open } from '@tauri-apps/plugin-shell';
 
+// EXIF 以外から推定した撮影日時の出どころ
+const DATE_SOURCE_LABELS: Partial<Record<CaptureDateSource, string>> = {
+  filename: 'ファイル名',
+  folder: 'フォルダ名',
+  sidecar: 'Takeout',
+  fileModified: '更新日時',
+};
+
 interface OverlayUIProps {
   image: ImageInfo | null;
   canGoBack: boolean;
@@ -50,6 +59,48 @@ export function OverlayUI({
   const [showMoreMenu, setShowMoreMenu] = useState(false);
   const [showExcludeSubmenu, setShowExcludeSubmenu] = useState(false);
   const [statusMessage, setStatusMessage] = useState<string>('');
+  // 直前の除外・ピックを取り消せるあいだ、通知に「元に戻す」を出す
+  const [canUndo, setCanUndo] = useState(false);
+  const statusTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
+
+  const showStatus = (message: string, undoable = false) => {
+    setStatusMessage(message);
+    setCanUndo(undoable);
+    if (statusTimer.current) clearTimeout(statusTimer.current);
+    statusTimer.current = setTimeout(() => {
+      setStatusMessage('');
+      setCanUndo(false);
+    }, 3000);
+  };
+
+  const handleUndo = async (redo = false) => {
+    try {
+      const entry = redo ? await redoAction() : await undoAction();
+      if (!entry) {
+        showStatus(redo ? 'やり直せる操作はありません' : '取り消せる操作はありません');
+      } else {
+        showStatus(`${redo ? 'やり直しました' : '取り消しました'}: ${entry.summary}`);
+      }
+    } catch (err) {
+      console.error('Failed to undo action:', err);
+      showStatus(redo ? 'エラー: やり直し失敗' : 'エラー: 取り消し失敗');
+    }
+  };
+
+  // Ctrl+Z（macOS は ⌘Z）で取り消し、Shift を足すとやり直し。入力欄の中では奪わない
+  useEffect(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (!(e.ctrlKey || e.metaKey) || e.key.toLowerCase() !== 'z') return;
+      const target = e.target as HTMLElement | null;
+      if (target && (target.tagName === 'INPUT' || target.tagName === 'TEXTAREA')) return;
+      e.preventDefault();
+      handleUndo(e.shiftKey);
+    };
+    document.addEventListener('keydown', handleKeyDown);
+    return () => document.removeEventListener('keydown', handleKeyDown);
+    // handleUndo は毎レンダーで再生成されるが、参照する state はセッターだけなので張り直さない
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
 
   const handleOpenDirectory = async () => {
     if (!image) return;
@@ -70,12 +121,10 @@ export function OverlayUI({
 
     try {
       const destPath = await pickImage(image.path);
-      setStatusMessage(`コピー完了: ${destPath}`);
-      setTimeout(() => setStatusMessage(''), 3000);
+      showStatus(`コピー完了: ${destPath}`, true);
     } catch (err) {
       console.error('Failed to share image:', err);
-      setStatusMessage('エラー: コピー失敗');
-      setTimeout(() => setStatusMessage(''), 3000);
+      showStatus('エラー: コピー失敗');
     }
     setShowMoreMenu(false);
   };
@@ -85,12 +134,10 @@ export function OverlayUI({
 
     try {
       const pattern = await excludeImage(image.path, type);
-      setStatusMessage(`除外パターン追加: ${pattern}`);
-      setTimeout(() => setStatusMessage(''), 3000);
+      showStatus(`除外パターン追加: ${pattern}`, true);
     } catch (err) {
       console.error('Failed to exclude image:', err);
-      setStatusMessage('エラー: 除外失敗');
-      setTimeout(() => setStatusMessage(''), 3000);
+      showStatus('エラー: 除外失敗');
     }
     setShowExcludeSubmenu(false);
     setShowMoreMenu(false);
@@ -116,6 +163,22 @@ export function OverlayUI({
     return `${datePart} ${timePart}`;
   };
 
+  // カメラ・撮影設定の要約（例: "X-T5 · 23mm · f/2.8 · 1/250s · ISO 400"）
+  const formatCameraSummary = (exif: ExifInfo | null): string => {
+    if (!exif) return '';
+    const parts: string[] = [];
+    if (exif.cameraModel) parts.push(exif.cameraModel);
+    if (exif.lensModel) parts.push(exif.lensModel);
+    if (exif.focalLength !== null) parts.push(`${Math.round(exif.focalLength)}mm`);
+    if (exif.fNumber !== null) parts.push(`f/${exif.fNumber.toFixed(1)}`);
+    if (exif.exposureTime !== null) {
+      const seconds = exif.exposureTime;
+      parts.push(seconds >= 1 ? `${seconds}s` : `1/${Math.round(1 / seconds)}s`);
+    }
+    if (exif.iso !== null) parts.push(`ISO ${exif.iso}`);
+    return parts.join(' · ');
+  };
+
   const formatFileSize = (bytes: number): string => {
     if (bytes < 1024) return `${bytes} B`;
     if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
@@ -126,8 +189,22 @@ export function OverlayUI({
 
   const hasGps =
     image?.exif != null && image.exif.gpsLatitude !== null && image.exif.gpsLongitude !== null;
-
-  const formattedDate = image?.exif?.dateTime ? formatDateTime(image.exif.dateTime) : '';
+  // 撮影地（オフラインで引いた地名。タイルが読めなくても表示できる）
+  const location = image?.location ?? null;
+  const placeName = location ? `${location.city}, ${location.country}` : null;
+  const placeDetail = location
+    ? [location.city, location.region, location.country].filter(Boolean).join(', ')
+    : undefined;
+
+  // 撮影日時（EXIF・動画の作成日時がなければファイル名や更新日時から解決したもの）
+  const dateTime =
+    image?.captureDate?.dateTime ?? image?.exif?.dateTime ?? image?.video?.creationTime ?? null;
+  const formattedDate = formatDateTime(dateTime);
+  const dateSource = image?.captureDate?.source ?? null;
+  const utcOffset = dateSource === 'exif' || dateSource === null ? image?.exif?.utcOffset : null;
+  // 推定した日付は時刻を持たないことが多いので出どころを添える
+  const dateSourceLabel = dateSource ? DATE_SOURCE_LABELS[dateSource] : undefined;
+  const cameraSummary = formatCameraSummary(image?.exif ?? null);
 
   const tileUrl = useMemo(() => {
     if (!hasGps || !image?.exif?.gpsLatitude || !image?.exif?.gpsLongitude) return null;
@@ -154,8 +231,18 @@ export function OverlayUI({
     >
       {/* ステータスメッセージ（バーの上に表示） */}
       {statusMessage && (
-        <div className="absolute bottom-full left-1/2 -translate-x-1/2 mb-2 bg-black/80 text-white/50 text-xs px-3 py-2 rounded border border-white/10 whitespace-nowrap">
+        <div className="absolute bottom-full left-1/2 -translate-x-1/2 mb-2 bg-black/80 text-white/50 text-xs px-3 py-2 rounded border border-white/10 whitespace-nowrap flex items-center gap-3">
           {statusMessage}
+          {canUndo && (
+            <button
+              onClick={() => handleUndo()}
+              className="flex items-center gap-1 text-white/60 hover:text-white/90 transition-colors"
+              title="元に戻す (Ctrl+Z)"
+            >
+              <Undo2 size={12} />
+              元に戻す
+            </button>
+          )}
         </div>
       )}
 
@@ -189,6 +276,9 @@ export function OverlayUI({
                   src={tileUrl!}
                   alt="Location Map"
                   className="w-full h-full object-cover grayscale opacity-60 group-hover:opacity-80 group-hover:grayscale-0 transition-all"
+                  onError={(e) => {
+                    e.currentTarget.style.visibility = 'hidden';
+                  }}
                 />
                 <div className="absolute inset-0 flex items-center justify-center pointer-events-none">
                   <MapPin
@@ -197,6 +287,22 @@ export function OverlayUI({
                     fill="currentColor"
                   />
                 </div>
+                {placeName && (
+                  <div
+                    className="absolute bottom-0 left-0 right-4 bg-black/50 text-white/60 text-xs text-left px-1 truncate"
+                    title={placeDetail}
+                  >
+                    {placeName}
+                  </div>
+                )}
+                {image.exif!.gpsInferred && (
+                  <div
+                    className="absolute top-0.5 left-0.5 bg-black/50 rounded px-1 text-white/40 text-xs"
+                    title="GPX トラックログから推定した撮影地"
+                  >
+                    推定
+                  </div>
+                )}
                 <div className="absolute bottom-0.5 right-0.5 bg-black/50 rounded p-0.5">
                   <ExternalLink size={8} className="text-white/40" />
                 </div>
@@ -208,8 +314,11 @@ export function OverlayUI({
             )}
           </div>
 
-          {/* 撮影日時 */}
-          <div className="p-2 flex flex-col items-center justify-center">
+          {/* 撮影日時（ホバーでカメラ・撮影設定） */}
+          <div
+            className="p-2 flex flex-col items-center justify-center"
+            title={cameraSummary || undefined}
+          >
             {formattedDate ? (
               <>
                 <div className="text-white/70 font-mono text-sm whitespace-nowrap">
@@ -217,6 +326,8 @@ export function OverlayUI({
                 </div>
                 <div className="text-white/35 font-mono text-xs mt-0.5">
                   {formattedDate.split(' ')[1] || ''}
+                  {utcOffset && <span className="text-white/20"> {utcOffset}</span>}
+                  {dateSourceLabel && <span className="text-white/20"> ({dateSourceLabel})</span>}
                 </div>
               </>
             ) : (
@@ -224,12 +335,17 @@ export function OverlayUI({
             )}
           </div>
 
-          {/* ファイル名・サイズ */}
+          {/* ファイル名・サイズ（キャプションがあればファイル名の代わりに表示） */}
           <div className="p-2 flex flex-col items-center justify-center overflow-hidden">
             <div className="text-white/45 text-xs truncate max-w-full" title={image.path}>
-              {fileName}
+              {image.xmp?.caption ?? fileName}
+            </div>
+            <div className="text-white/20 text-xs mt-0.5">
+              {formatFileSize(image.fileSize)}
+              {image.xmp?.rating != null && image.xmp.rating > 0 && (
+                <span className="ml-1.5 text-white/35">{'★'.repeat(image.xmp.rating)}</span>
+              )}
             </div>
-            <div className="text-white/20 text-xs mt-0.5">{formatFileSize(image.fileSize)}</div>
           </div>
 
           {/* 位置/回数 */}
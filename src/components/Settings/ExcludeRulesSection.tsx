@@ -1,11 +1,115 @@
-import { X, Plus } from 'lucide-react';
+import { X, Plus, Download, Upload, Stethoscope, Tag } from 'lucide-react';
 import { useState, useEffect } from 'react';
-import { getIgnorePatterns, removeIgnorePattern, addIgnorePattern } from '../../lib/tauri';
+import {
+  getIgnorePatterns,
+  removeIgnorePattern,
+  addIgnorePattern,
+  previewIgnorePattern,
+  selectIgnoreExportPath,
+  selectIgnoreFile,
+  exportIgnoreRules,
+  importIgnoreRules,
+  validateIgnorePattern,
+  checkIgnoreRules,
+  setIgnoreRuleEnabled,
+  setIgnoreRuleLabel,
+} from '../../lib/tauri';
+import type {
+  AddPatternError,
+  IgnoreImportReport,
+  IgnorePreview,
+  IgnoreRule,
+  PatternError,
+  RuleHealth,
+  RuleSource,
+} from '../../types';
+
+// スヌーズの期間（'forever' は無期限、'date' は日付を指定）
+const SNOOZE_OPTIONS = [
+  { value: 'forever', label: '無期限' },
+  { value: '7', label: '7日間' },
+  { value: '30', label: '30日間' },
+  { value: '90', label: '90日間' },
+  { value: 'date', label: '日付まで' },
+];
+
+const DAY_SECONDS = 24 * 60 * 60;
+
+/** スヌーズの期限（UNIX 秒）を求める。日付指定はその日の 0 時（ローカル時刻）に戻る */
+function snoozeExpiry(snooze: string, until: string): number | null {
+  if (snooze === 'forever') return null;
+  if (snooze === 'date') {
+    return until ? Math.floor(new Date(`${until}T00:00`).getTime() / 1000) : null;
+  }
+  return Math.floor(Date.now() / 1000) + Number(snooze) * DAY_SECONDS;
+}
+
+const formatExpiry = (expiresAt: number): string =>
+  new Date(expiresAt * 1000).toLocaleDateString();
+
+/** ルールの出どころを一覧の注記にする */
+function describeSource(source: RuleSource): string {
+  switch (source.kind) {
+    case 'default':
+      return '既定のルール';
+    case 'settings':
+      return '設定で追加';
+    case 'menu': {
+      const target = source.excludeType === 'directory' ? 'フォルダ' : 'ファイル';
+      return `写真から${target}を除外: ${source.imagePath}`;
+    }
+    case 'migration':
+      return '~/.sssignore から移行';
+    case 'import':
+      return `読み込み: ${source.filePath}`;
+  }
+}
+
+/** 読めないパターンの、問題のある文字に印を付けて表示する */
+function PatternErrorView({ error }: { error: PatternError }) {
+  const chars = Array.from(error.pattern);
+  const position = error.position;
+  return (
+    <div className="text-sm text-red-400/70 space-y-0.5">
+      {position !== null && (
+        <div className="font-mono text-xs text-white/50">
+          {chars.slice(0, position).join('')}
+          <span className="bg-red-500/40 text-white/80">{chars[position] ?? ' '}</span>
+          {chars.slice(position + 1).join('')}
+        </div>
+      )}
+      <div className="text-xs">
+        {position !== null ? `${position + 1} 文字目: ` : ''}
+        {error.reason}
+      </div>
+    </div>
+  );
+}
 
 export function ExcludeRulesSection() {
-  const [patterns, setPatterns] = useState<string[]>([]);
+  const [patterns, setPatterns] = useState<IgnoreRule[]>([]);
   const [newPattern, setNewPattern] = useState('');
+  const [newLabel, setNewLabel] = useState('');
+  const [snooze, setSnooze] = useState('forever');
+  const [snoozeUntil, setSnoozeUntil] = useState('');
   const [loading, setLoading] = useState(true);
+  // 追加前の試算（確認待ちのパターンと結果）
+  const [preview, setPreview] = useState<{ pattern: string; result: IgnorePreview } | null>(
+    null,
+  );
+  const [error, setError] = useState<string | null>(null);
+  // ファイルの書き出し・読み込みの結果
+  const [fileMessage, setFileMessage] = useState<string | null>(null);
+  const [importReport, setImportReport] = useState<IgnoreImportReport | null>(null);
+  // 入力中のパターンの書式エラー
+  const [patternError, setPatternError] = useState<PatternError | null>(null);
+  // 保存済みのルールの点検結果
+  const [health, setHealth] = useState<RuleHealth[] | null>(null);
+  const [checking, setChecking] = useState(false);
+  // 編集中のラベル（対象のパターンと入力中の値）
+  const [editingLabel, setEditingLabel] = useState<{ pattern: string; value: string } | null>(
+    null,
+  );
 
   useEffect(() => {
     getIgnorePatterns()
@@ -19,25 +123,151 @@ export function ExcludeRulesSection() {
       });
   }, []);
 
+  // 入力のたびに書式を確かめる（入力が変わったら前の問い合わせの結果は捨てる）
+  useEffect(() => {
+    const trimmed = newPattern.trim();
+    if (!trimmed) {
+      setPatternError(null);
+      return;
+    }
+    let cancelled = false;
+    validateIgnorePattern(trimmed)
+      .then((result) => {
+        if (!cancelled) setPatternError(result);
+      })
+      .catch((err) => console.error('Failed to validate ignore pattern:', err));
+    return () => {
+      cancelled = true;
+    };
+  }, [newPattern]);
+
   const handleRemove = async (pattern: string) => {
     try {
       await removeIgnorePattern(pattern);
-      setPatterns((prev) => prev.filter((p) => p !== pattern));
+      setPatterns((prev) => prev.filter((p) => p.pattern !== pattern));
+      setHealth((prev) => prev?.filter((h) => h.pattern !== pattern) ?? null);
     } catch (err) {
       console.error('Failed to remove ignore pattern:', err);
     }
   };
 
+  const handleToggle = async (pattern: string, enabled: boolean) => {
+    try {
+      await setIgnoreRuleEnabled(pattern, enabled);
+      setPatterns((prev) => prev.map((p) => (p.pattern === pattern ? { ...p, enabled } : p)));
+      setError(null);
+    } catch (err) {
+      console.error('Failed to toggle ignore rule:', err);
+      setError(String(err));
+    }
+  };
+
+  const handleSaveLabel = async () => {
+    if (!editingLabel) return;
+    const label = editingLabel.value.trim() || null;
+    try {
+      await setIgnoreRuleLabel(editingLabel.pattern, label);
+      setPatterns((prev) =>
+        prev.map((p) => (p.pattern === editingLabel.pattern ? { ...p, label } : p)),
+      );
+      setEditingLabel(null);
+    } catch (err) {
+      console.error('Failed to update ignore rule label:', err);
+      setError(String(err));
+    }
+  };
+
+  // 追加の前に、いまのライブラリで何件が除外されるかを試して確認を求める
   const handleAdd = async () => {
     const trimmed = newPattern.trim();
-    if (!trimmed || patterns.includes(trimmed)) return;
+    if (!trimmed || patternError) return;
+
+    try {
+      setPreview({ pattern: trimmed, result: await previewIgnorePattern(trimmed) });
+      setError(null);
+    } catch (err) {
+      console.error('Failed to preview ignore pattern:', err);
+      setError(String(err));
+    }
+  };
+
+  const handleConfirm = async () => {
+    if (!preview) return;
 
     try {
-      await addIgnorePattern(trimmed);
-      setPatterns((prev) => [...prev, trimmed]);
+      await addIgnorePattern(
+        preview.pattern,
+        snoozeExpiry(snooze, snoozeUntil),
+        newLabel.trim() || null,
+      );
+      // 既存のパターンなら期限とラベルだけが変わり、止めていたものは再開する
+      setPatterns(await getIgnorePatterns());
       setNewPattern('');
+      setNewLabel('');
+      setSnooze('forever');
+      setSnoozeUntil('');
+      setPreview(null);
     } catch (err) {
       console.error('Failed to add ignore pattern:', err);
+      const failure = err as AddPatternError;
+      if (failure?.kind === 'invalid') {
+        // 入力中の確認をすり抜けた書式の誤りは、入力欄の下に位置と理由を出す
+        setPatternError(failure.error);
+        setPreview(null);
+      } else {
+        setError(failure?.kind === 'failed' ? failure.message : String(err));
+      }
+    }
+  };
+
+  const handleCheck = async () => {
+    setChecking(true);
+    try {
+      setHealth(await checkIgnoreRules());
+      setError(null);
+    } catch (err) {
+      console.error('Failed to check ignore rules:', err);
+      setError(String(err));
+    } finally {
+      setChecking(false);
+    }
+  };
+
+  const handleExport = async () => {
+    try {
+      const path = await selectIgnoreExportPath();
+      if (!path) return;
+      const count = await exportIgnoreRules(path);
+      setFileMessage(`${count} 件のルールを書き出しました`);
+      setImportReport(null);
+      setError(null);
+    } catch (err) {
+      console.error('Failed to export ignore rules:', err);
+      setError(String(err));
+    }
+  };
+
+  const handleImport = async (replace: boolean) => {
+    try {
+      const path = await selectIgnoreFile();
+      if (!path) return;
+      if (
+        replace &&
+        !window.confirm('いまの除外ルールをすべて、このファイルの内容で置き換えますか？')
+      ) {
+        return;
+      }
+      const report = await importIgnoreRules(path, replace);
+      setPatterns(await getIgnorePatterns());
+      setFileMessage(
+        `${report.added} 件を読み込みました` +
+          (report.unchanged > 0 ? `（${report.unchanged} 件は登録済み）` : ''),
+      );
+      setImportReport(report);
+      setError(null);
+    } catch (err) {
+      console.error('Failed to import ignore rules:', err);
+      setError(String(err));
     }
   };
 
@@ -59,12 +289,61 @@ export function ExcludeRulesSection() {
         <div className="text-white/30 text-sm">除外ルールはありません</div>
       ) : (
         <div className="space-y-1">
-          {patterns.map((pattern) => (
+          {patterns.map(({ pattern, expiresAt, source, label, enabled }) => (
             <div
               key={pattern}
               className="flex items-center justify-between gap-2 px-3 py-1.5 bg-black/40 rounded border border-white/8 group"
             >
-              <span className="text-white/55 text-sm truncate">{pattern}</span>
+              <input
+                type="checkbox"
+                checked={enabled}
+                onChange={(e) => handleToggle(pattern, e.target.checked)}
+                title={enabled ? '止める（消さずに一時的に無効にする）' : '再開する'}
+                className="shrink-0 accent-white/60"
+              />
+              <div className={`min-w-0 ${enabled ? '' : 'opacity-40'}`}>
+                <div className="text-white/55 text-sm truncate">
+                  {pattern}
+                  {label && editingLabel?.pattern !== pattern && (
+                    <span className="ml-2 text-white/35 text-xs">{label}</span>
+                  )}
+                </div>
+                {editingLabel?.pattern === pattern ? (
+                  <input
+                    type="text"
+                    autoFocus
+                    value={editingLabel.value}
+                    onChange={(e) => setEditingLabel({ pattern, value: e.target.value })}
+                    onKeyDown={(e) => {
+                      if (e.key === 'Enter') handleSaveLabel();
+                      if (e.key === 'Escape') setEditingLabel(null);
+                    }}
+                    onBlur={handleSaveLabel}
+                    placeholder="ラベル"
+                    className="w-full px-2 py-0.5 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-xs"
+                  />
+                ) : (
+                  source && (
+                    <div className="text-white/25 text-xs truncate" title={describeSource(source)}>
+                      {describeSource(source)}
+                    </div>
+                  )
+                )}
+              </div>
+              {expiresAt !== null && (
+                <span className="text-white/30 text-xs shrink-0 ml-auto">
+                  {expiresAt * 1000 <= Date.now()
+                    ? `${formatExpiry(expiresAt)} に期限切れ`
+                    : `${formatExpiry(expiresAt)} まで`}
+                </span>
+              )}
+              <button
+                onClick={() => setEditingLabel({ pattern, value: label ?? '' })}
+                className={`p-1 hover:bg-white/8 rounded transition-colors shrink-0 opacity-0 group-hover:opacity-100 ${expiresAt === null ? 'ml-auto' : ''}`}
+                title="ラベルを編集"
+              >
+                <Tag className="w-3.5 h-3.5 text-white/30 hover:text-white/60" />
+              </button>
               <button
                 onClick={() => handleRemove(pattern)}
                 className="p-1 hover:bg-white/8 rounded transition-colors shrink-0 opacity-0 group-hover:opacity-100"
@@ -81,20 +360,176 @@ export function ExcludeRulesSection() {
         <input
           type="text"
           value={newPattern}
-          onChange={(e) => setNewPattern(e.target.value)}
+          onChange={(e) => {
+            setNewPattern(e.target.value);
+            setPreview(null);
+          }}
           onKeyDown={handleKeyDown}
           placeholder="パターンを入力（例: **/thumbs/）"
           className="flex-1 px-3 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm"
         />
+        <input
+          type="text"
+          value={newLabel}
+          onChange={(e) => setNewLabel(e.target.value)}
+          onKeyDown={handleKeyDown}
+          placeholder="ラベル（任意）"
+          className="w-32 px-3 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm shrink-0"
+        />
+        <select
+          value={snooze}
+          onChange={(e) => setSnooze(e.target.value)}
+          title="除外する期間（過ぎたら写真が戻る）"
+          className="px-2 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm shrink-0"
+        >
+          {SNOOZE_OPTIONS.map(({ value, label }) => (
+            <option key={value} value={value}>
+              {label}
+            </option>
+          ))}
+        </select>
+        {snooze === 'date' && (
+          <input
+            type="date"
+            value={snoozeUntil}
+            onChange={(e) => setSnoozeUntil(e.target.value)}
+            className="px-3 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm shrink-0"
+          />
+        )}
         <button
           onClick={handleAdd}
-          disabled={!newPattern.trim()}
+          disabled={
+            !newPattern.trim() || patternError !== null || (snooze === 'date' && !snoozeUntil)
+          }
           className="flex items-center gap-2 px-4 py-2 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition shrink-0 text-sm disabled:opacity-30 disabled:cursor-not-allowed"
         >
           <Plus className="w-4 h-4" />
           追加
         </button>
       </div>
+
+      {patternError && <PatternErrorView error={patternError} />}
+      {error && <div className="text-sm text-red-400/70">{error}</div>}
+
+      {preview && (
+        <div className="space-y-2 p-3 bg-black/30 rounded border border-white/8 text-sm">
+          <div className="text-white/60">
+            「{preview.pattern}」で {preview.result.excludedCount.toLocaleString()} 件が除外されます
+            （全 {preview.result.totalFiles.toLocaleString()} 件中）
+          </div>
+          {preview.result.sample.length > 0 && (
+            <div className="space-y-0.5">
+              {preview.result.sample.map((path) => (
+                <div key={path} className="text-white/35 text-xs truncate" title={path}>
+                  {path}
+                </div>
+              ))}
+              {preview.result.excludedCount > preview.result.sample.length && (
+                <div className="text-white/25 text-xs">
+                  ほか {preview.result.excludedCount - preview.result.sample.length} 件
+                </div>
+              )}
+            </div>
+          )}
+          {preview.result.alreadyExcludedCount > 0 && (
+            <div className="text-white/35 text-xs">
+              {preview.result.alreadyExcludedCount} 件はすでに除外済み
+              {preview.result.coveringRules.length > 0 &&
+                `（${preview.result.coveringRules.map((r) => `${r.pattern}: ${r.count}`).join('、')}）`}
+            </div>
+          )}
+          <div className="flex gap-2 pt-1">
+            <button
+              onClick={handleConfirm}
+              className="px-3 py-1.5 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition text-sm"
+            >
+              このルールを追加
+            </button>
+            <button
+              onClick={() => setPreview(null)}
+              className="px-3 py-1.5 hover:bg-white/8 text-white/40 hover:text-white/60 rounded transition text-sm"
+            >
+              キャンセル
+            </button>
+          </div>
+        </div>
+      )}
+
+      <div className="flex gap-2">
+        <button
+          onClick={handleExport}
+          className="flex items-center gap-2 px-3 py-1.5 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition text-sm"
+        >
+          <Download className="w-4 h-4" />
+          ファイルに書き出す
+        </button>
+        <button
+          onClick={() => handleImport(false)}
+          className="flex items-center gap-2 px-3 py-1.5 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition text-sm"
+        >
+          <Upload className="w-4 h-4" />
+          ファイルから追加
+        </button>
+        <button
+          onClick={() => handleImport(true)}
+          className="px-3 py-1.5 hover:bg-white/8 text-white/40 hover:text-white/60 rounded transition text-sm"
+        >
+          ファイルで置き換え
+        </button>
+        <button
+          onClick={handleCheck}
+          disabled={checking}
+          className="flex items-center gap-2 px-3 py-1.5 hover:bg-white/8 text-white/40 hover:text-white/60 rounded transition text-sm ml-auto disabled:opacity-30"
+        >
+          <Stethoscope className="w-4 h-4" />
+          {checking ? '点検中...' : 'ルールを点検'}
+        </button>
+      </div>
+
+      {health &&
+        (health.length === 0 ? (
+          <div className="text-sm text-white/40">問題のあるルールはありません</div>
+        ) : (
+          <div className="space-y-1">
+            {health.map(({ pattern, problem }) => (
+              <div
+                key={pattern}
+                className="flex items-center justify-between gap-2 px-3 py-1.5 bg-black/30 rounded border border-white/8"
+              >
+                <div className="min-w-0">
+                  <div className="text-white/55 text-sm truncate">{pattern}</div>
+                  <div className="text-xs text-amber-300/60">
+                    {problem.kind === 'invalid'
+                      ? `読めないため無視されています（${problem.error.reason}）`
+                      : 'ライブラリのどのファイル・フォルダにもマッチしません'}
+                  </div>
+                </div>
+                <button
+                  onClick={() => handleRemove(pattern)}
+                  className="px-2 py-1 hover:bg-white/8 text-white/40 hover:text-white/60 rounded transition text-xs shrink-0"
+                >
+                  解除
+                </button>
+              </div>
+            ))}
+          </div>
+        ))}
+
+      {fileMessage && <div className="text-sm text-white/40">{fileMessage}</div>}
+      {importReport && importReport.conflicts.length + importReport.invalid.length > 0 && (
+        <div className="space-y-0.5 p-3 bg-black/30 rounded border border-white/8">
+          {importReport.conflicts.map((issue) => (
+            <div key={`c${issue.line}`} className="text-amber-300/60 text-xs truncate">
+              {issue.line} 行目「{issue.text}」: 衝突のため読み込みませんでした（{issue.message}）
+            </div>
+          ))}
+          {importReport.invalid.map((issue) => (
+            <div key={`i${issue.line}`} className="text-red-400/60 text-xs truncate">
+              {issue.line} 行目「{issue.text}」: 読み込めませんでした（{issue.message}）
+            </div>
+          ))}
+        </div>
+      )}
     </div>
   );
 }
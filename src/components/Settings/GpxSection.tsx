@@ -0,0 +1,129 @@
+import { Route, X } from 'lucide-react';
+import { useState, useEffect } from 'react';
+import {
+  getGpxTracks,
+  importGpx,
+  removeGpxTrack,
+  selectGpxFile,
+  getSetting,
+  saveSetting,
+} from '../../lib/tauri';
+import type { GpxTrack } from '../../types';
+
+const formatTime = (time: number | null): string =>
+  time !== null ? new Date(time).toLocaleString() : '-';
+
+export function GpxSection() {
+  const [tracks, setTracks] = useState<GpxTrack[]>([]);
+  // カメラの時計のずれ（秒）。カメラの時刻 + この値 = 正しい時刻
+  const [clockOffset, setClockOffset] = useState('0');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    getGpxTracks()
+      .then(setTracks)
+      .catch((err) => console.error('Failed to load GPX tracks:', err));
+
+    getSetting('gpx_clock_offset_seconds')
+      .then((value) => {
+        if (value !== null) setClockOffset(value);
+      })
+      .catch((err) => console.error('Failed to load gpx_clock_offset_seconds:', err));
+  }, []);
+
+  const handleImport = async () => {
+    try {
+      const path = await selectGpxFile();
+      if (!path) return;
+      await importGpx(path);
+      setTracks(await getGpxTracks());
+      setError(null);
+    } catch (err) {
+      console.error('Failed to import GPX track:', err);
+      setError(String(err));
+    }
+  };
+
+  const handleRemove = async (id: number) => {
+    try {
+      await removeGpxTrack(id);
+      setTracks((prev) => prev.filter((track) => track.id !== id));
+    } catch (err) {
+      console.error('Failed to remove GPX track:', err);
+    }
+  };
+
+  const handleClockOffsetSave = async () => {
+    const seconds = Number.parseInt(clockOffset, 10);
+    const value = Number.isNaN(seconds) ? '0' : String(seconds);
+    setClockOffset(value);
+    try {
+      await saveSetting('gpx_clock_offset_seconds', value);
+    } catch (err) {
+      console.error('Failed to save gpx_clock_offset_seconds:', err);
+    }
+  };
+
+  return (
+    <div className="space-y-4">
+      <h3 className="text-sm font-medium text-white/50 uppercase tracking-wider">
+        GPX トラックログ（GPS のない写真の撮影地を推定）
+      </h3>
+
+      {tracks.length === 0 ? (
+        <div className="p-4 bg-black/30 rounded text-center text-white/30 text-sm border border-white/5">
+          取り込んだトラックはありません
+        </div>
+      ) : (
+        <div className="space-y-1">
+          {tracks.map((track) => (
+            <div
+              key={track.id}
+              className="flex items-center gap-2 px-3 py-2 bg-black/30 rounded border border-white/5"
+            >
+              <div className="flex-1 min-w-0">
+                <div className="text-white/55 text-sm truncate" title={track.path}>
+                  {track.name ?? track.path.split(/[\\/]/).pop()}
+                </div>
+                <div className="text-white/25 text-xs">
+                  {formatTime(track.startTime)} 〜 {formatTime(track.endTime)} ·{' '}
+                  {track.pointCount.toLocaleString()} 点
+                </div>
+              </div>
+              <button
+                onClick={() => handleRemove(track.id)}
+                className="p-1 hover:bg-white/8 rounded transition-colors"
+                title="削除"
+              >
+                <X className="w-3.5 h-3.5 text-white/30 hover:text-white/60" />
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+
+      {error && <div className="text-sm text-red-400/70">{error}</div>}
+
+      <button
+        onClick={handleImport}
+        className="flex items-center gap-2 px-4 py-2 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition text-sm"
+      >
+        <Route className="w-4 h-4" />
+        GPX を取り込む
+      </button>
+
+      <div className="space-y-2">
+        <div className="text-white/55 text-sm">
+          カメラの時計のずれ（秒。カメラが 1 分遅れているなら 60）
+        </div>
+        <input
+          type="number"
+          value={clockOffset}
+          onChange={(e) => setClockOffset(e.target.value)}
+          onBlur={handleClockOffsetSave}
+          className="w-full px-2 py-1 bg-black/40 text-white/60 rounded border border-white/8 text-sm focus:outline-none focus:border-white/20"
+        />
+      </div>
+    </div>
+  );
+}
@@ -1,12 +1,14 @@
 import { Ban, ChevronRight } from 'lucide-react';
 import { useState, useEffect } from 'react';
 import { convertFileSrc } from '@tauri-apps/api/core';
-import { getRecentImages, excludeImage } from '../../lib/tauri';
+import { getRecentImages, excludeImage, getThumbnailGrid } from '../../lib/tauri';
 import type { RecentImage } from '../../types';
 
 export function HistorySection() {
   const [images, setImages] = useState<RecentImage[]>([]);
   const [loading, setLoading] = useState(true);
+  // 元パス → サムネイルのパス（動画はポスターフレームから作られる）
+  const [thumbnails, setThumbnails] = useState<Record<string, string>>({});
   const [activeMenu, setActiveMenu] = useState<string | null>(null);
 
   useEffect(() => {
@@ -19,6 +21,16 @@ export function HistorySection() {
         console.error('Failed to load recent images:', err);
         setLoading(false);
       });
+
+    getThumbnailGrid('recent', 0, 100)
+      .then((page) => {
+        const map: Record<string, string> = {};
+        for (const item of page.items) {
+          if (item.thumbnailPath) map[item.path] = item.thumbnailPath;
+        }
+        setThumbnails(map);
+      })
+      .catch((err) => console.error('Failed to load thumbnails:', err));
   }, []);
 
   const handleExclude = async (path: string, type: 'date' | 'file' | 'directory') => {
@@ -50,7 +62,7 @@ export function HistorySection() {
           {images.map((img) => (
             <div key={img.path} className="relative group">
               <img
-                src={convertFileSrc(img.path)}
+                src={convertFileSrc(thumbnails[img.path] ?? img.path)}
                 alt=""
                 className="w-full aspect-square object-cover rounded border border-white/5"
                 loading="lazy"
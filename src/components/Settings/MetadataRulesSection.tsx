@@ -0,0 +1,234 @@
+import { X, Plus } from 'lucide-react';
+import { useState, useEffect } from 'react';
+import { getMetadataRules, addMetadataRule, removeMetadataRule } from '../../lib/tauri';
+import type { MetadataRule, MetadataRuleEntry } from '../../types';
+
+type RuleType = MetadataRule['type'];
+
+const RULE_TYPES: Array<{ value: RuleType; label: string }> = [
+  { value: 'dateRange', label: '撮影日の範囲' },
+  { value: 'camera', label: 'カメラ機種' },
+  { value: 'minDimensions', label: '最小画素数' },
+  { value: 'fileSize', label: 'ファイルサイズ' },
+  { value: 'mediaType', label: '種類' },
+  { value: 'aspectRatio', label: '縦横比' },
+];
+
+const MB = 1024 * 1024;
+
+const formatMb = (bytes: number): string => `${Math.round((bytes / MB) * 100) / 100}MB`;
+
+/** ルールを一覧表示用の文にする */
+function describeRule(rule: MetadataRule): string {
+  switch (rule.type) {
+    case 'dateRange':
+      return rule.from === rule.to
+        ? `撮影日が ${rule.from}`
+        : `撮影日が ${rule.from ?? ''} 〜 ${rule.to ?? ''}`;
+    case 'camera':
+      return `カメラが「${rule.model}」`;
+    case 'minDimensions':
+      return `${rule.width}×${rule.height} より小さい`;
+    case 'fileSize':
+      return [
+        rule.minBytes !== null ? `${formatMb(rule.minBytes)} 未満` : null,
+        rule.maxBytes !== null ? `${formatMb(rule.maxBytes)} 超` : null,
+      ]
+        .filter(Boolean)
+        .join('・');
+    case 'mediaType':
+      return rule.kind === 'video' ? '動画' : '画像';
+    case 'aspectRatio':
+      return [
+        rule.min !== null ? `縦横比 ${rule.min} 未満` : null,
+        rule.max !== null ? `縦横比 ${rule.max} 超` : null,
+      ]
+        .filter(Boolean)
+        .join('・');
+  }
+}
+
+const toNumber = (value: string): number | null => {
+  const n = Number.parseFloat(value);
+  return value.trim() === '' || Number.isNaN(n) ? null : n;
+};
+
+const inputClass =
+  'flex-1 min-w-0 px-3 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm';
+
+export function MetadataRulesSection() {
+  const [rules, setRules] = useState<MetadataRuleEntry[]>([]);
+  const [type, setType] = useState<RuleType>('dateRange');
+  // 種類ごとの入力欄（2つまで）
+  const [first, setFirst] = useState('');
+  const [second, setSecond] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    getMetadataRules()
+      .then(setRules)
+      .catch((err) => console.error('Failed to load metadata rules:', err));
+  }, []);
+
+  const buildRule = (): MetadataRule => {
+    switch (type) {
+      case 'dateRange':
+        return { type, from: first || null, to: second || first || null };
+      case 'camera':
+        return { type, model: first.trim() };
+      case 'minDimensions':
+        return { type, width: toNumber(first) ?? 0, height: toNumber(second) ?? 0 };
+      case 'fileSize': {
+        const min = toNumber(first);
+        const max = toNumber(second);
+        return {
+          type,
+          minBytes: min !== null ? Math.round(min * MB) : null,
+          maxBytes: max !== null ? Math.round(max * MB) : null,
+        };
+      }
+      case 'mediaType':
+        return { type, kind: first === 'image' ? 'image' : 'video' };
+      case 'aspectRatio':
+        return { type, min: toNumber(first), max: toNumber(second) };
+    }
+  };
+
+  const handleAdd = async () => {
+    const rule = buildRule();
+    try {
+      const id = await addMetadataRule(rule);
+      setRules((prev) => (prev.some((r) => r.id === id) ? prev : [...prev, { id, rule }]));
+      setFirst('');
+      setSecond('');
+      setError(null);
+    } catch (err) {
+      console.error('Failed to add metadata rule:', err);
+      setError(String(err));
+    }
+  };
+
+  const handleRemove = async (id: number) => {
+    try {
+      await removeMetadataRule(id);
+      setRules((prev) => prev.filter((r) => r.id !== id));
+    } catch (err) {
+      console.error('Failed to remove metadata rule:', err);
+    }
+  };
+
+  const handleTypeChange = (value: RuleType) => {
+    setType(value);
+    setFirst(value === 'mediaType' ? 'video' : '');
+    setSecond('');
+    setError(null);
+  };
+
+  return (
+    <div className="space-y-4">
+      <h3 className="text-sm font-medium text-white/50 uppercase tracking-wider">
+        メタデータの除外ルール
+      </h3>
+
+      {rules.length === 0 ? (
+        <div className="text-white/30 text-sm">メタデータの除外ルールはありません</div>
+      ) : (
+        <div className="space-y-1">
+          {rules.map(({ id, rule }) => (
+            <div
+              key={id}
+              className="flex items-center justify-between gap-2 px-3 py-1.5 bg-black/40 rounded border border-white/8 group"
+            >
+              <span className="text-white/55 text-sm truncate">{describeRule(rule)}</span>
+              <button
+                onClick={() => handleRemove(id)}
+                className="p-1 hover:bg-white/8 rounded transition-colors shrink-0 opacity-0 group-hover:opacity-100"
+                title="解除"
+              >
+                <X className="w-3.5 h-3.5 text-white/30 hover:text-white/60" />
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+
+      <div className="flex gap-2">
+        <select
+          value={type}
+          onChange={(e) => handleTypeChange(e.target.value as RuleType)}
+          className="px-2 py-2 bg-black/40 text-white/50 rounded border border-white/8 focus:outline-none focus:border-white/20 text-sm shrink-0"
+        >
+          {RULE_TYPES.map(({ value, label }) => (
+            <option key={value} value={value}>
+              {label}
+            </option>
+          ))}
+        </select>
+
+        {type === 'dateRange' && (
+          <>
+            <input
+              type="date"
+              value={first}
+              onChange={(e) => setFirst(e.target.value)}
+              className={inputClass}
+            />
+            <input
+              type="date"
+              value={second}
+              onChange={(e) => setSecond(e.target.value)}
+              className={inputClass}
+            />
+          </>
+        )}
+        {type === 'camera' && (
+          <input
+            type="text"
+            value={first}
+            onChange={(e) => setFirst(e.target.value)}
+            placeholder="機種名（例: iPhone）"
+            className={inputClass}
+          />
+        )}
+        {(type === 'minDimensions' || type === 'fileSize' || type === 'aspectRatio') && (
+          <>
+            <input
+              type="number"
+              value={first}
+              onChange={(e) => setFirst(e.target.value)}
+              placeholder={
+                type === 'minDimensions' ? '幅（px）' : type === 'fileSize' ? '最小（MB）' : '最小'
+              }
+              className={inputClass}
+            />
+            <input
+              type="number"
+              value={second}
+              onChange={(e) => setSecond(e.target.value)}
+              placeholder={
+                type === 'minDimensions' ? '高さ（px）' : type === 'fileSize' ? '最大（MB）' : '最大'
+              }
+              className={inputClass}
+            />
+          </>
+        )}
+        {type === 'mediaType' && (
+          <select value={first} onChange={(e) => setFirst(e.target.value)} className={inputClass}>
+            <option value="video">動画</option>
+            <option value="image">画像</option>
+          </select>
+        )}
+
+        <button
+          onClick={handleAdd}
+          className="flex items-center gap-2 px-4 py-2 bg-white/8 hover:bg-white/15 text-white/60 hover:text-white/80 rounded border border-white/8 transition shrink-0 text-sm"
+        >
+          <Plus className="w-4 h-4" />
+          追加
+        </button>
+      </div>
+
+      {error && <div className="text-sm text-red-400/70">{error}</div>}
+    </div>
+  );
+}
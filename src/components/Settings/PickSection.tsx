@@ -1,11 +1,13 @@
 import { X } from 'lucide-react';
 import { useState, useEffect } from 'react';
 import { convertFileSrc } from '@tauri-apps/api/core';
-import { getPickedImages, deletePickedImage } from '../../lib/tauri';
+import { getPickedImages, deletePickedImage, getThumbnailGrid } from '../../lib/tauri';
 
 export function PickSection() {
   const [images, setImages] = useState<string[]>([]);
   const [loading, setLoading] = useState(true);
+  // 元パス → サムネイルのパス（動画はポスターフレームから作られる）
+  const [thumbnails, setThumbnails] = useState<Record<string, string>>({});
 
   useEffect(() => {
     getPickedImages()
@@ -17,6 +19,16 @@ export function PickSection() {
         console.error('Failed to load picked images:', err);
         setLoading(false);
       });
+
+    getThumbnailGrid('picked', 0, 100)
+      .then((page) => {
+        const map: Record<string, string> = {};
+        for (const item of page.items) {
+          if (item.thumbnailPath) map[item.path] = item.thumbnailPath;
+        }
+        setThumbnails(map);
+      })
+      .catch((err) => console.error('Failed to load thumbnails:', err));
   }, []);
 
   const handleDelete = async (path: string) => {
@@ -46,7 +58,7 @@ export function PickSection() {
           {images.map((path) => (
             <div key={path} className="relative group">
               <img
-                src={convertFileSrc(path)}
+                src={convertFileSrc(thumbnails[path] ?? path)}
                 alt=""
                 className="w-full aspect-square object-cover rounded border border-white/5"
                 loading="lazy"
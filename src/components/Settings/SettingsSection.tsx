@@ -1,8 +1,36 @@
 import { useState, useEffect } from 'react';
-import { getSetting, saveSetting } from '../../lib/tauri';
+import { getSetting, saveSetting, getLastDirectoryPath } from '../../lib/tauri';
+import type { ToneMapping } from '../../types';
+
+const TONE_MAPPING_OPTIONS: { value: ToneMapping; label: string }[] = [
+  { value: 'auto', label: '自動' },
+  { value: 'levels', label: 'レベル補正（スキャン向け）' },
+  { value: 'channel_levels', label: 'チャンネルごとのレベル補正（色あせたスキャン向け）' },
+  { value: 'filmic', label: 'フィルミック（HDR向け）' },
+  { value: 'clip', label: 'そのまま丸める' },
+];
+
+const MIN_RATING_OPTIONS: { value: string; label: string }[] = [
+  { value: '', label: 'すべて' },
+  { value: '0', label: '却下（-1）以外' },
+  { value: '1', label: '★1 以上' },
+  { value: '2', label: '★2 以上' },
+  { value: '3', label: '★3 以上' },
+  { value: '4', label: '★4 以上' },
+  { value: '5', label: '★5 のみ' },
+];
+
+const LABEL_OPTIONS = ['', 'Red', 'Yellow', 'Green', 'Blue', 'Purple'];
 
 export function SettingsSection() {
   const [applyExifRotation, setApplyExifRotation] = useState(true);
+  const [blurLetterbox, setBlurLetterbox] = useState(false);
+  const [libraryPath, setLibraryPath] = useState<string | null>(null);
+  const [toneMapping, setToneMapping] = useState<ToneMapping>('auto');
+  // プレイリストの絞り込み（XMP のレーティング・キーワード・ラベル）
+  const [minRating, setMinRating] = useState('');
+  const [keywords, setKeywords] = useState('');
+  const [label, setLabel] = useState('');
 
   useEffect(() => {
     // apply_exif_rotation設定を読み込む
@@ -15,6 +43,45 @@ export function SettingsSection() {
       .catch((err) => {
         console.error('Failed to load apply_exif_rotation:', err);
       });
+
+    // letterbox_background設定を読み込む
+    getSetting('letterbox_background')
+      .then((value) => {
+        setBlurLetterbox(value === 'blur');
+      })
+      .catch((err) => {
+        console.error('Failed to load letterbox_background:', err);
+      });
+
+    // tone_mapping設定を読み込む（ライブラリごとの設定 → 全体の設定）
+    getLastDirectoryPath()
+      .then(async (path) => {
+        setLibraryPath(path);
+        const value =
+          (path !== null ? await getSetting(`tone_mapping:${path}`) : null) ??
+          (await getSetting('tone_mapping'));
+        if (value !== null) {
+          setToneMapping(value as ToneMapping);
+        }
+      })
+      .catch((err) => {
+        console.error('Failed to load tone_mapping:', err);
+      });
+
+    // プレイリストの絞り込み設定を読み込む
+    Promise.all([
+      getSetting('playlist_min_rating'),
+      getSetting('playlist_keywords'),
+      getSetting('playlist_label'),
+    ])
+      .then(([rating, words, color]) => {
+        setMinRating(rating ?? '');
+        setKeywords(words ?? '');
+        setLabel(color ?? '');
+      })
+      .catch((err) => {
+        console.error('Failed to load playlist filter:', err);
+      });
   }, []);
 
   const handleExifRotationChange = async (checked: boolean) => {
@@ -26,6 +93,37 @@ export function SettingsSection() {
     }
   };
 
+  const handleBlurLetterboxChange = async (checked: boolean) => {
+    setBlurLetterbox(checked);
+    try {
+      // 合成画像のキャンバス比率は、この画面の比率に合わせる
+      await saveSetting('display_aspect_ratio', `${window.screen.width}:${window.screen.height}`);
+      await saveSetting('letterbox_background', checked ? 'blur' : 'black');
+    } catch (err) {
+      console.error('Failed to save letterbox_background:', err);
+    }
+  };
+
+  const handleToneMappingChange = async (value: ToneMapping) => {
+    setToneMapping(value);
+    try {
+      // ライブラリを選択済みならそのライブラリだけに適用
+      const key = libraryPath !== null ? `tone_mapping:${libraryPath}` : 'tone_mapping';
+      await saveSetting(key, value);
+    } catch (err) {
+      console.error('Failed to save tone_mapping:', err);
+    }
+  };
+
+  // 絞り込みは次回スキャン時にプレイリストへ反映される
+  const handlePlaylistFilterChange = async (key: string, value: string) => {
+    try {
+      await saveSetting(key, value);
+    } catch (err) {
+      console.error(`Failed to save ${key}:`, err);
+    }
+  };
+
   return (
     <div className="space-y-4">
       {/* EXIF回転設定 */}
@@ -40,6 +138,76 @@ export function SettingsSection() {
           EXIF回転情報に従って画像を自動回転
         </div>
       </label>
+
+      {/* 余白のぼかし背景設定 */}
+      <label className="flex items-start gap-3 cursor-pointer group">
+        <input
+          type="checkbox"
+          checked={blurLetterbox}
+          onChange={(e) => handleBlurLetterboxChange(e.target.checked)}
+          className="mt-0.5 w-4 h-4 rounded border-white/20 bg-white/5 text-white/50 focus:ring-0 focus:ring-offset-0 accent-white/50"
+        />
+        <div className="text-white/55 text-sm group-hover:text-white/75 transition-colors">
+          余白を黒帯ではなく写真のぼかしで埋める
+        </div>
+      </label>
+
+      {/* 16bit / HDR 画像の階調変換設定 */}
+      <div className="space-y-2">
+        <div className="text-white/55 text-sm">16bit / HDR 画像の階調変換（このライブラリ）</div>
+        <select
+          value={toneMapping}
+          onChange={(e) => handleToneMappingChange(e.target.value as ToneMapping)}
+          className="w-full px-2 py-1 bg-black/40 text-white/60 rounded border border-white/8 text-sm focus:outline-none focus:border-white/20"
+        >
+          {TONE_MAPPING_OPTIONS.map((option) => (
+            <option key={option.value} value={option.value}>
+              {option.label}
+            </option>
+          ))}
+        </select>
+      </div>
+
+      {/* プレイリストの絞り込み（Lightroom / darktable のレーティング・キーワード・ラベル） */}
+      <div className="space-y-2">
+        <div className="text-white/55 text-sm">表示する写真の絞り込み（再スキャンで反映）</div>
+        <select
+          value={minRating}
+          onChange={(e) => {
+            setMinRating(e.target.value);
+            handlePlaylistFilterChange('playlist_min_rating', e.target.value);
+          }}
+          className="w-full px-2 py-1 bg-black/40 text-white/60 rounded border border-white/8 text-sm focus:outline-none focus:border-white/20"
+        >
+          {MIN_RATING_OPTIONS.map((option) => (
+            <option key={option.value} value={option.value}>
+              {option.label}
+            </option>
+          ))}
+        </select>
+        <input
+          type="text"
+          value={keywords}
+          placeholder="キーワード（カンマ区切り、いずれかを含む）"
+          onChange={(e) => setKeywords(e.target.value)}
+          onBlur={() => handlePlaylistFilterChange('playlist_keywords', keywords)}
+          className="w-full px-2 py-1 bg-black/40 text-white/60 rounded border border-white/8 text-sm placeholder:text-white/20 focus:outline-none focus:border-white/20"
+        />
+        <select
+          value={label}
+          onChange={(e) => {
+            setLabel(e.target.value);
+            handlePlaylistFilterChange('playlist_label', e.target.value);
+          }}
+          className="w-full px-2 py-1 bg-black/40 text-white/60 rounded border border-white/8 text-sm focus:outline-none focus:border-white/20"
+        >
+          {LABEL_OPTIONS.map((color) => (
+            <option key={color} value={color}>
+              {color === '' ? 'ラベル指定なし' : color}
+            </option>
+          ))}
+        </select>
+      </div>
     </div>
   );
 }
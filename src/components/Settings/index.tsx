@@ -5,7 +5,9 @@ import { ScanSection } from './ScanSection';
 import { IntervalSection } from './IntervalSection';
 import { SettingsSection } from './SettingsSection';
 import { ShareDirectorySection } from './ShareDirectorySection';
+import { GpxSection } from './GpxSection';
 import { ExcludeRulesSection } from './ExcludeRulesSection';
+import { MetadataRulesSection } from './MetadataRulesSection';
 import { PickSection } from './PickSection';
 import { HistorySection } from './HistorySection';
 import { GraphSection } from './GraphSection';
@@ -143,11 +145,13 @@ export function Settings({
               <IntervalSection onIntervalChange={onIntervalChange} />
               <SettingsSection />
               <ShareDirectorySection />
+              <GpxSection />
             </div>
           )}
           {activeTab === 'exclude' && (
             <div className="space-y-8">
               <ExcludeRulesSection />
+              <MetadataRulesSection />
             </div>
           )}
           {activeTab === 'pick' && (
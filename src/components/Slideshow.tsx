@@ -37,6 +37,7 @@ export function Slideshow({ image, isLoading, onVideoEnded }: SlideshowProps) {
           <motion.video
             key={image.path}
             src={srcUrl}
+            poster={image.posterPath ? convertFileSrc(image.posterPath) : undefined}
             initial={{ opacity: 0 }}
             animate={{ opacity: 1 }}
             exit={{ opacity: 0 }}
@@ -30,6 +30,15 @@ function makeImage(path: string, isVideo = false): ImageInfo {
     exif: null,
     displayCount: 0,
     lastDisplayed: null,
+    focalPoint: null,
+    letterboxed: false,
+    frameCount: null,
+    animationDurationMs: null,
+    video: null,
+    posterPath: null,
+    captureDate: null,
+    xmp: null,
+    location: null,
   };
 }
 
@@ -206,6 +215,28 @@ describe('auto-advance timer', () => {
     expect(getNextImage).toHaveBeenCalled();
   });
 
+  it('waits for the running animation loop to finish before advancing', async () => {
+    // 1000ms 間隔・1ループ 400ms のアニメーションは 3ループ目の終わり（1200ms）で次へ
+    getNextImage.mockResolvedValue({ ...makeImage('/anim.gif'), animationDurationMs: 400 });
+    const { result } = renderHook(() => useSlideshow(1000));
+
+    await act(async () => {
+      await result.current.loadNextImage();
+    });
+    getNextImage.mockClear();
+
+    act(() => result.current.play());
+    await act(async () => {
+      await vi.advanceTimersByTimeAsync(1100);
+    });
+    expect(getNextImage).not.toHaveBeenCalled();
+
+    await act(async () => {
+      await vi.advanceTimersByTimeAsync(100);
+    });
+    expect(getNextImage).toHaveBeenCalled();
+  });
+
   it('does NOT start the auto-advance timer for a video (driven by onEnded instead)', async () => {
     getNextImage.mockResolvedValue(makeImage('/clip.mp4', true));
     const { result } = renderHook(() => useSlideshow(1000));
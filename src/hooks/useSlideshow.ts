@@ -80,6 +80,16 @@ export function useSlideshow(interval: number = 10000) {
    */
   const isCurrentVideo = currentImage?.isVideo ?? false;
 
+  /**
+   * 現在のスライドの表示時間
+   * アニメーション画像は間隔が過ぎても再生中のループを最後まで見せる
+   */
+  const animationDurationMs = currentImage?.animationDurationMs ?? 0;
+  const slideDuration =
+    animationDurationMs > 0
+      ? Math.ceil(interval / animationDurationMs) * animationDurationMs
+      : interval;
+
   /**
    * 動画再生終了時に次へ進むコールバック
    */
@@ -101,14 +111,14 @@ export function useSlideshow(interval: number = 10000) {
       // プログレスバーの更新（60FPS）
       progressIntervalRef.current = window.setInterval(() => {
         const elapsed = Date.now() - startTimeRef.current;
-        const newProgress = Math.min((elapsed / interval) * 100, 100);
+        const newProgress = Math.min((elapsed / slideDuration) * 100, 100);
         setProgress(newProgress);
       }, 16); // 約60FPS
 
       // 画像切り替えタイマー
       intervalRef.current = window.setInterval(() => {
         loadNextImage();
-      }, interval);
+      }, slideDuration);
 
       return () => {
         if (intervalRef.current !== undefined) {
@@ -125,7 +135,7 @@ export function useSlideshow(interval: number = 10000) {
         window.clearInterval(progressIntervalRef.current);
       }
     }
-  }, [isPlaying, isLoading, isCurrentVideo, interval, loadNextImage]);
+  }, [isPlaying, isLoading, isCurrentVideo, slideDuration, loadNextImage]);
 
   /**
    * 初回画像読み込み
@@ -6,20 +6,31 @@ import { describe, it, expect, vi, beforeEach } from 'vitest';
 // and (c) how each wrapper passes the invoke return value straight through.
 const invoke = vi.fn();
 const open = vi.fn();
+const save = vi.fn();
 
 vi.mock('@tauri-apps/api/core', () => ({
   invoke: (...args: unknown[]) => invoke(...args),
 }));
 vi.mock('@tauri-apps/plugin-dialog', () => ({
   open: (...args: unknown[]) => open(...args),
+  save: (...args: unknown[]) => save(...args),
 }));
 
 import * as tauri from './tauri';
-import type { ImageInfo, ScanProgress, Stats, RecentImage } from '../types';
+import type {
+  GpxTrack,
+  ImageInfo,
+  MetadataRule,
+  ScanProgress,
+  Stats,
+  RecentImage,
+  ThumbnailPage,
+} from '../types';
 
 beforeEach(() => {
   invoke.mockReset();
   open.mockReset();
+  save.mockReset();
 });
 
 describe('tauri command wrappers', () => {
@@ -54,6 +65,15 @@ describe('tauri command wrappers', () => {
       exif: null,
       displayCount: 0,
       lastDisplayed: null,
+      focalPoint: null,
+      letterboxed: false,
+      frameCount: null,
+      animationDurationMs: null,
+      video: null,
+      posterPath: null,
+      captureDate: null,
+      xmp: null,
+      location: null,
     };
     invoke.mockResolvedValue(image);
     const result = await tauri.getNextImage();
@@ -138,10 +158,82 @@ describe('tauri command wrappers', () => {
     expect(invoke).toHaveBeenCalledWith('remove_ignore_pattern', { pattern: '*.tmp' });
   });
 
+  it('previewIgnorePattern invokes preview_ignore_pattern with pattern', async () => {
+    invoke.mockResolvedValue({ totalFiles: 0 });
+    await tauri.previewIgnorePattern('*.png');
+    expect(invoke).toHaveBeenCalledWith('preview_ignore_pattern', { pattern: '*.png' });
+  });
+
   it('addIgnorePattern invokes add_ignore_pattern with pattern', async () => {
     invoke.mockResolvedValue(undefined);
     await tauri.addIgnorePattern('*.tmp');
-    expect(invoke).toHaveBeenCalledWith('add_ignore_pattern', { pattern: '*.tmp' });
+    expect(invoke).toHaveBeenCalledWith('add_ignore_pattern', {
+      pattern: '*.tmp',
+      expiresAt: null,
+      label: null,
+    });
+  });
+
+  it('addIgnorePattern passes the snooze expiry', async () => {
+    invoke.mockResolvedValue(undefined);
+    await tauri.addIgnorePattern('trip/', 1800000000);
+    expect(invoke).toHaveBeenCalledWith('add_ignore_pattern', {
+      pattern: 'trip/',
+      expiresAt: 1800000000,
+      label: null,
+    });
+  });
+
+  it('addIgnorePattern passes the label', async () => {
+    invoke.mockResolvedValue(undefined);
+    await tauri.addIgnorePattern('*.gif', null, 'アニメ');
+    expect(invoke).toHaveBeenCalledWith('add_ignore_pattern', {
+      pattern: '*.gif',
+      expiresAt: null,
+      label: 'アニメ',
+    });
+  });
+
+  it('setIgnoreRuleEnabled invokes set_ignore_rule_enabled', async () => {
+    invoke.mockResolvedValue(undefined);
+    await tauri.setIgnoreRuleEnabled('trip/', false);
+    expect(invoke).toHaveBeenCalledWith('set_ignore_rule_enabled', {
+      pattern: 'trip/',
+      enabled: false,
+    });
+  });
+
+  it('setIgnoreRuleLabel invokes set_ignore_rule_label', async () => {
+    invoke.mockResolvedValue(undefined);
+    await tauri.setIgnoreRuleLabel('trip/', '旅行');
+    expect(invoke).toHaveBeenCalledWith('set_ignore_rule_label', {
+      pattern: 'trip/',
+      label: '旅行',
+    });
+  });
+
+  it('validateIgnorePattern returns the structured error', async () => {
+    const error = { pattern: 'a[b', position: 1, reason: 'unclosed character class' };
+    invoke.mockResolvedValue(error);
+    expect(await tauri.validateIgnorePattern('a[b')).toEqual(error);
+    expect(invoke).toHaveBeenCalledWith('validate_ignore_pattern', { pattern: 'a[b' });
+  });
+
+  it('selectIgnoreExportPath returns the chosen path or null', async () => {
+    save.mockResolvedValue('/rules/.sssignore');
+    expect(await tauri.selectIgnoreExportPath()).toBe('/rules/.sssignore');
+    save.mockResolvedValue(null);
+    expect(await tauri.selectIgnoreExportPath()).toBeNull();
+  });
+
+  it('importIgnoreRules passes the file path and mode', async () => {
+    const report = { added: 1, unchanged: 0, conflicts: [], invalid: [] };
+    invoke.mockResolvedValue(report);
+    expect(await tauri.importIgnoreRules('/rules/.sssignore', false)).toEqual(report);
+    expect(invoke).toHaveBeenCalledWith('import_ignore_rules', {
+      filePath: '/rules/.sssignore',
+      replace: false,
+    });
   });
 
   it('getRecentImages returns the RecentImage list', async () => {
@@ -159,6 +251,64 @@ describe('tauri command wrappers', () => {
     expect(invoke).toHaveBeenCalledWith('delete_picked_image', { imagePath: '/a.jpg' });
   });
 
+  it('undoAction returns the reversed entry', async () => {
+    const entry = {
+      id: 1,
+      action: { kind: 'pick', sourcePath: '/a.jpg', pickedPath: '/picked/a.jpg' },
+      summary: 'ピック: /picked/a.jpg',
+      createdAt: '2026-10-19 08:00:00',
+    };
+    invoke.mockResolvedValue(entry);
+    expect(await tauri.undoAction()).toEqual(entry);
+    expect(invoke).toHaveBeenCalledWith('undo_action');
+  });
+
+  it('redoAction returns null when there is nothing to redo', async () => {
+    invoke.mockResolvedValue(null);
+    expect(await tauri.redoAction()).toBeNull();
+    expect(invoke).toHaveBeenCalledWith('redo_action');
+  });
+
+  it('getThumbnailGrid passes source, paging and options (null when omitted)', async () => {
+    const page: ThumbnailPage = {
+      items: [{ path: '/a.jpg', thumbnailPath: '/thumbs/x.jpg', isVideo: false }],
+      total: 1,
+      page: 0,
+      pageSize: 50,
+    };
+    invoke.mockResolvedValue(page);
+    expect(await tauri.getThumbnailGrid('folder', 0, 50, { folder: '/photos' })).toEqual(page);
+    expect(invoke).toHaveBeenCalledWith('get_thumbnail_grid', {
+      source: 'folder',
+      page: 0,
+      pageSize: 50,
+      folder: '/photos',
+      size: null,
+      mode: null,
+    });
+  });
+
+  it('importGpx invokes import_gpx with filePath', async () => {
+    const track: GpxTrack = {
+      id: 1,
+      path: '/logs/walk.gpx',
+      name: 'walk',
+      pointCount: 2,
+      startTime: 0,
+      endTime: 60000,
+    };
+    invoke.mockResolvedValue(track);
+    expect(await tauri.importGpx('/logs/walk.gpx')).toEqual(track);
+    expect(invoke).toHaveBeenCalledWith('import_gpx', { filePath: '/logs/walk.gpx' });
+  });
+
+  it('addMetadataRule forwards the tagged rule object', async () => {
+    const rule: MetadataRule = { type: 'dateRange', from: '2023-05-15', to: '2023-05-15' };
+    invoke.mockResolvedValue(3);
+    expect(await tauri.addMetadataRule(rule)).toBe(3);
+    expect(invoke).toHaveBeenCalledWith('add_metadata_rule', { rule });
+  });
+
   it('propagates rejections from invoke', async () => {
     invoke.mockRejectedValue(new Error('backend boom'));
     await expect(tauri.getNextImage()).rejects.toThrow('backend boom');
@@ -1,6 +1,23 @@
 import { invoke } from '@tauri-apps/api/core';
-import { open } from '@tauri-apps/plugin-dialog';
-import type { ImageInfo, RecentImage, ScanProgress, Stats } from '../types';
+import { open, save } from '@tauri-apps/plugin-dialog';
+import type {
+  GpxTrack,
+  IgnoreImportReport,
+  IgnorePreview,
+  IgnoreRule,
+  ImageInfo,
+  JournalEntry,
+  MetadataRule,
+  MetadataRuleEntry,
+  PatternError,
+  RecentImage,
+  RuleHealth,
+  ScanProgress,
+  Stats,
+  ThumbnailMode,
+  ThumbnailPage,
+  ThumbnailSource,
+} from '../types';
 
 /**
  * デフォルトのピック先ディレクトリパスを取得
@@ -116,8 +133,8 @@ export async function getDisplayStats(): Promise<Array<[string, number]>> {
 /**
  * 除外ルール一覧を取得
  */
-export async function getIgnorePatterns(): Promise<string[]> {
-  return await invoke<string[]>('get_ignore_patterns');
+export async function getIgnorePatterns(): Promise<IgnoreRule[]> {
+  return await invoke<IgnoreRule[]>('get_ignore_patterns');
 }
 
 /**
@@ -128,10 +145,114 @@ export async function removeIgnorePattern(pattern: string): Promise<void> {
 }
 
 /**
- * 除外ルールを手動追加
+ * 除外ルールを手動追加（expiresAt は UNIX 秒。指定するとその時刻までのスヌーズ。label は覚え書き）
+ * 失敗すると AddPatternError で reject する
  */
-export async function addIgnorePattern(pattern: string): Promise<void> {
-  await invoke('add_ignore_pattern', { pattern });
+export async function addIgnorePattern(
+  pattern: string,
+  expiresAt: number | null = null,
+  label: string | null = null,
+): Promise<void> {
+  await invoke('add_ignore_pattern', { pattern, expiresAt, label });
+}
+
+/**
+ * 除外ルールを止める・再開する（消さずに一時的に効かなくする）
+ */
+export async function setIgnoreRuleEnabled(pattern: string, enabled: boolean): Promise<void> {
+  await invoke('set_ignore_rule_enabled', { pattern, enabled });
+}
+
+/**
+ * 除外ルールのラベルを変える（null で消す）
+ */
+export async function setIgnoreRuleLabel(pattern: string, label: string | null): Promise<void> {
+  await invoke('set_ignore_rule_label', { pattern, label });
+}
+
+/**
+ * 除外ルールを追加したら何件が除外されるかを試す（保存はしない）
+ */
+export async function previewIgnorePattern(pattern: string): Promise<IgnorePreview> {
+  return await invoke<IgnorePreview>('preview_ignore_pattern', { pattern });
+}
+
+/**
+ * 除外ルールとして保存できるか確かめる（読めなければ位置と理由を返す）
+ */
+export async function validateIgnorePattern(pattern: string): Promise<PatternError | null> {
+  return await invoke<PatternError | null>('validate_ignore_pattern', { pattern });
+}
+
+/**
+ * 保存済みの除外ルールのうち、読めないものとどこにもマッチしないものを返す
+ */
+export async function checkIgnoreRules(): Promise<RuleHealth[]> {
+  return await invoke<RuleHealth[]>('check_ignore_rules');
+}
+
+/**
+ * 除外ルールの書き出し先を選ぶダイアログを開く
+ */
+export async function selectIgnoreExportPath(): Promise<string | null> {
+  return await save({
+    title: 'Export Exclusion Rules',
+    defaultPath: '.sssignore',
+  });
+}
+
+/**
+ * 読み込む除外ルールファイルを選ぶダイアログを開く
+ */
+export async function selectIgnoreFile(): Promise<string | null> {
+  const selected = await open({
+    multiple: false,
+    title: 'Import Exclusion Rules',
+  });
+
+  if (typeof selected === 'string') {
+    return selected;
+  }
+
+  return null;
+}
+
+/**
+ * 除外ルールを gitignore 形式のファイルに書き出す（書き出した数を返す）
+ */
+export async function exportIgnoreRules(filePath: string): Promise<number> {
+  return await invoke<number>('export_ignore_rules', { filePath });
+}
+
+/**
+ * 除外ルールファイルを読み込む（replace なら置き換え、そうでなければ追加）
+ */
+export async function importIgnoreRules(
+  filePath: string,
+  replace: boolean,
+): Promise<IgnoreImportReport> {
+  return await invoke<IgnoreImportReport>('import_ignore_rules', { filePath, replace });
+}
+
+/**
+ * メタデータの除外ルール一覧を取得
+ */
+export async function getMetadataRules(): Promise<MetadataRuleEntry[]> {
+  return await invoke<MetadataRuleEntry[]>('get_metadata_rules');
+}
+
+/**
+ * メタデータの除外ルールを追加（再生中のプレイリストにもすぐ反映。追加したルールの id を返す）
+ */
+export async function addMetadataRule(rule: MetadataRule): Promise<number> {
+  return await invoke<number>('add_metadata_rule', { rule });
+}
+
+/**
+ * メタデータの除外ルールを削除
+ */
+export async function removeMetadataRule(id: number): Promise<void> {
+  await invoke('remove_metadata_rule', { id });
 }
 
 /**
@@ -162,9 +283,80 @@ export async function deletePickedImage(imagePath: string): Promise<void> {
   await invoke('delete_picked_image', { imagePath });
 }
 
+/**
+ * 最後の除外・ピック・ピックの削除を取り消す（取り消せる操作がなければ null）
+ */
+export async function undoAction(): Promise<JournalEntry | null> {
+  return await invoke<JournalEntry | null>('undo_action');
+}
+
+/**
+ * 最後に取り消した操作をやり直す（やり直せる操作がなければ null）
+ */
+export async function redoAction(): Promise<JournalEntry | null> {
+  return await invoke<JournalEntry | null>('redo_action');
+}
+
 /**
  * 全画像の表示回数をリセット
  */
 export async function resetAllDisplayCounts(): Promise<void> {
   await invoke('reset_all_display_counts');
 }
+
+/**
+ * サムネイルグリッドを1ページ分取得（ライブラリ全体 / フォルダ / 最近表示 / ピック済み）
+ */
+export async function getThumbnailGrid(
+  source: ThumbnailSource,
+  page: number,
+  pageSize: number,
+  options: { folder?: string; size?: number; mode?: ThumbnailMode } = {},
+): Promise<ThumbnailPage> {
+  return await invoke<ThumbnailPage>('get_thumbnail_grid', {
+    source,
+    page,
+    pageSize,
+    folder: options.folder ?? null,
+    size: options.size ?? null,
+    mode: options.mode ?? null,
+  });
+}
+
+/**
+ * GPX ファイル選択ダイアログを開く
+ */
+export async function selectGpxFile(): Promise<string | null> {
+  const selected = await open({
+    multiple: false,
+    title: 'Select GPX Track Log',
+    filters: [{ name: 'GPX', extensions: ['gpx'] }],
+  });
+
+  if (typeof selected === 'string') {
+    return selected;
+  }
+
+  return null;
+}
+
+/**
+ * GPX トラックログを取り込む（同じファイルは置き換え）
+ */
+export async function importGpx(filePath: string): Promise<GpxTrack | null> {
+  return await invoke<GpxTrack | null>('import_gpx', { filePath });
+}
+
+/**
+ * 取り込み済みの GPX トラック一覧を取得
+ */
+export async function getGpxTracks(): Promise<GpxTrack[]> {
+  return await invoke<GpxTrack[]>('get_gpx_tracks');
+}
+
+/**
+ * GPX トラックを削除
+ */
+export async function removeGpxTrack(id: number): Promise<void> {
+  await invoke('remove_gpx_track', { id });
+}
@@ -9,17 +9,185 @@ export interface ImageInfo {
   exif: ExifInfo | null;
   displayCount: number;
   lastDisplayed: string | null;
+  focalPoint: FocalPoint | null; // 注目点（未解析・動画・アニメーションは null）
+  letterboxed: boolean; // optimizedPath がぼかし背景付きの全画面合成画像か
+  frameCount: number | null; // アニメーション画像のフレーム数（静止画は null）
+  animationDurationMs: number | null; // アニメーション1ループの再生時間
+  video: VideoMetadata | null; // 動画のメタデータ（画像は null）
+  posterPath: string | null; // 動画のポスターフレーム（未生成・ffmpeg なしは null）
+  captureDate: CaptureDate | null; // 撮影日時と出どころ（EXIF がなければファイル名・更新日時等）
+  xmp: XmpMetadata | null; // レーティング・キーワード・キャプション・ラベル（何もなければ null）
+  location: Location | null; // GPS 座標から引いた撮影地（GPS なし・近くに地名なしは null）
 }
 
-// EXIF情報
+// 撮影地（同梱の GeoNames 地名データでオフラインに逆ジオコーディングした結果）
+export interface Location {
+  city: string;
+  region: string | null; // 都道府県・州など
+  country: string;
+}
+
+// XMP / IPTC メタデータ（サイドカー → 埋め込み XMP → IPTC の順に優先）
+export interface XmpMetadata {
+  rating: number | null; // 0〜5、却下は -1
+  keywords: string[];
+  caption: string | null;
+  label: string | null; // カラーラベル（"Red" 等）
+}
+
+// 撮影日時（EXIF → ファイル名 → フォルダ名 → Takeout サイドカー → 更新日時 の順に解決）
+export interface CaptureDate {
+  dateTime: string; // "YYYY-MM-DD HH:MM:SS"（日付しか分からない場合は 00:00:00）
+  source: CaptureDateSource;
+}
+
+export type CaptureDateSource = 'exif' | 'video' | 'filename' | 'folder' | 'sidecar' | 'fileModified';
+
+// 動画メタデータ（コンテナのヘッダから読み取る）
+export interface VideoMetadata {
+  durationMs: number | null;
+  width: number; // 回転適用後の表示サイズ
+  height: number;
+  rotation: number; // 時計回りの回転角（0 / 90 / 180 / 270）
+  creationTime: string | null; // UTC "YYYY-MM-DD HH:MM:SS"
+}
+
+// 注目点（幅・高さに対する 0〜1 の相対座標。object-position にそのまま使える）
+export interface FocalPoint {
+  x: number;
+  y: number;
+}
+
+// 16bit / HDR 画像の階調変換（設定 tone_mapping の値）
+export type ToneMapping = 'auto' | 'clip' | 'levels' | 'channel_levels' | 'filmic';
+
+// EXIF情報（欠けている項目は代替タグを探し、どれもなければ null）
 export interface ExifInfo {
-  dateTime: string | null;
+  dateTime: string | null; // 撮影日時 "YYYY-MM-DD HH:MM:SS"（カメラの現地時刻）
+  dateTimeSource: CaptureTimeSource | null; // dateTime をどのタグから取ったか
+  captureTime: string | null; // ISO 8601（秒未満・UTC オフセットは記録がある場合のみ）
+  utcOffset: string | null; // "+09:00"
+  cameraMake: string | null;
+  cameraModel: string | null;
+  lensModel: string | null;
+  focalLength: number | null; // mm
+  focalLength35mm: number | null; // 35mm 判換算（mm）
+  fNumber: number | null;
+  exposureTime: number | null; // 秒
+  iso: number | null;
+  flashFired: boolean | null;
   gpsLatitude: number | null;
   gpsLongitude: number | null;
+  gpsAltitude: number | null; // m（海面下は負）
+  gpsBearing: number | null; // 撮影方向（度）
+  gpsInferred: boolean; // GPS 座標が EXIF ではなく GPX トラックログから推定したものか
   width: number | null;
   height: number | null;
 }
 
+// 撮影日時の出どころ（DateTimeOriginal → DateTimeDigitized → DateTime の順に探す）
+export type CaptureTimeSource = 'original' | 'digitized' | 'modified';
+
+// 取り込み済みの GPX トラックログ
+export interface GpxTrack {
+  id: number;
+  path: string;
+  name: string | null;
+  pointCount: number;
+  startTime: number | null; // 最初のトラックポイントの時刻（UNIX ミリ秒）
+  endTime: number | null;
+}
+
+// 除外ルール（get_ignore_patterns）
+export interface IgnoreRule {
+  pattern: string;
+  expiresAt: number | null; // この時刻（UNIX 秒）を過ぎたら効かなくなる（スヌーズ）。null は無期限
+  addedAt: string | null; // 追加日時（UTC の "YYYY-MM-DD HH:MM:SS"）
+  source: RuleSource | null; // どこで追加されたか（記録を始める前からあるルールは null）
+  label: string | null; // 覚え書き
+  enabled: boolean; // false なら消さずに止めている
+}
+
+// 除外ルールの出どころ
+export type RuleSource =
+  | { kind: 'default' } // 初回起動時の既定のルール
+  | { kind: 'settings' } // 設定画面で手動追加
+  | { kind: 'menu'; imagePath: string; excludeType: 'file' | 'directory' } // 写真の「…」メニューから
+  | { kind: 'migration' } // 旧 ~/.sssignore からの移行
+  | { kind: 'import'; filePath: string }; // ルールファイルの読み込み
+
+// 除外ルールとして読めないパターン（validate_ignore_pattern）
+export interface PatternError {
+  pattern: string;
+  position: number | null; // 問題のある文字の位置（0 始まり）
+  reason: string;
+}
+
+// 除外ルールを手動追加できなかった理由（add_ignore_pattern の reject）
+export type AddPatternError =
+  | { kind: 'invalid'; error: PatternError } // パターンとして読めない
+  | { kind: 'failed'; message: string }; // スヌーズの期限切れ・DB エラーなど
+
+// 保存済みの除外ルールの問題（check_ignore_rules）
+export interface RuleHealth {
+  pattern: string;
+  problem: { kind: 'invalid'; error: PatternError } | { kind: 'unmatched' };
+}
+
+// ルールファイルの読み込み結果（import_ignore_rules）
+export interface IgnoreImportReport {
+  added: number;
+  unchanged: number; // 同じ内容のルールがすでにあった数
+  conflicts: ImportIssue[]; // 既存のルールとぶつかって読み込まなかった行
+  invalid: ImportIssue[]; // パターンや期限が読めなかった行
+}
+
+export interface ImportIssue {
+  line: number; // 1 始まり
+  text: string;
+  message: string;
+}
+
+// 除外ルールを追加する前の試算（preview_ignore_pattern）
+export interface IgnorePreview {
+  totalFiles: number;
+  matchedCount: number; // パターンにマッチしたファイル数
+  excludedCount: number; // 追加すると新たに除外されるファイル数
+  alreadyExcludedCount: number; // マッチしたが既存のルールですでに除外されているファイル数
+  sample: string[]; // 新たに除外されるファイルの例
+  coveringRules: Array<{ pattern: string; count: number }>; // すでに除外している既存のルール
+}
+
+// メタデータの除外ルール（当てはまったファイルをプレイリストから除く。null は上限・下限なし）
+export type MetadataRule =
+  | { type: 'dateRange'; from: string | null; to: string | null } // 撮影日 "YYYY-MM-DD"（両端を含む）
+  | { type: 'camera'; model: string } // メーカー名か機種名に含む（大文字小文字を区別しない）
+  | { type: 'minDimensions'; width: number; height: number } // これより小さい（向きは問わない）
+  | { type: 'fileSize'; minBytes: number | null; maxBytes: number | null } // この範囲の外
+  | { type: 'mediaType'; kind: 'image' | 'video' }
+  | { type: 'aspectRatio'; min: number | null; max: number | null }; // 幅 / 高さがこの範囲の外
+
+// 保存済みのメタデータの除外ルール
+export interface MetadataRuleEntry {
+  id: number;
+  rule: MetadataRule;
+}
+
+// 取り消せる操作（undo_action / redo_action）
+export type JournalAction =
+  | { kind: 'addIgnoreRule'; rule: IgnoreRule; previous: IgnoreRule | null } // previous は上書き前のルール
+  | { kind: 'addMetadataRule'; id: number; rule: MetadataRule }
+  | { kind: 'pick'; sourcePath: string; pickedPath: string }
+  | { kind: 'deletePicked'; pickedPath: string; trashPath: string }; // trashPath は削除したピックの退避先
+
+// 取り消し・やり直しした操作
+export interface JournalEntry {
+  id: number;
+  action: JournalAction;
+  summary: string; // 通知に出す要約
+  createdAt: string; // 記録日時（UTC の "YYYY-MM-DD HH:MM:SS"）
+}
+
 // スキャン進捗
 export interface ScanProgress {
   totalFiles: number;
@@ -40,3 +208,21 @@ export interface RecentImage {
   displayCount: number;
   lastDisplayed: string;
 }
+
+// サムネイルグリッドの1件
+export interface ThumbnailItem {
+  path: string;
+  thumbnailPath: string | null; // 生成できなかった場合（動画・破損ファイル）は null
+  isVideo: boolean;
+}
+
+// サムネイルグリッドの1ページ
+export interface ThumbnailPage {
+  items: ThumbnailItem[];
+  total: number;
+  page: number;
+  pageSize: number;
+}
+
+export type ThumbnailSource = 'library' | 'folder' | 'recent' | 'picked';
+export type ThumbnailMode = 'square' | 'fit';